@@ -1,5 +1,25 @@
 import type { PlaywrightTestConfig } from "@playwright/test";
 import { devices } from "@playwright/test";
+import { execFileSync } from "child_process";
+import * as fs from "fs";
+import * as os from "os";
+import * as path from "path";
+
+// the specs import with mode=replace, so the server they start keeps todos in a
+// throwaway database rather than in Todos.db
+process.env.TODOS_DB = path.join(
+  fs.mkdtempSync(path.join(os.tmpdir(), "todos-e2e-")),
+  "todos.db",
+);
+
+// the server's one user, whose token every request passes, added before it starts.
+// Workers load this file again, and reuse the token they inherit.
+process.env.E2E_TOKEN ??= execFileSync(
+  "cargo",
+  ["run", "--quiet", "--features", "ssr", "--bin", "leptos-todo", "--", "create-user", "e2e"],
+  { cwd: "..", encoding: "utf8", stdio: ["ignore", "pipe", "inherit"] },
+).trim();
+const token = process.env.E2E_TOKEN;
 
 /**
  * Read environment variables from file.
@@ -21,14 +41,13 @@ const config: PlaywrightTestConfig = {
      */
     timeout: 5000,
   },
-  /* Run tests in files in parallel */
-  fullyParallel: true,
+  /* Specs share the server and its todos, and replace them, so they run one at a time. */
+  fullyParallel: false,
   /* Fail the build on CI if you accidentally left test.only in the source code. */
   forbidOnly: !!process.env.CI,
   /* Retry on CI only */
   retries: process.env.CI ? 2 : 0,
-  /* Opt out of parallel tests on CI. */
-  workers: process.env.CI ? 1 : undefined,
+  workers: 1,
   /* Reporter to use. See https://playwright.dev/docs/test-reporters */
   reporter: "html",
   /* Shared settings for all the projects below. See https://playwright.dev/docs/api/class-testoptions. */
@@ -38,6 +57,9 @@ const config: PlaywrightTestConfig = {
     /* Base URL to use in actions like `await page.goto('/')`. */
     // baseURL: 'http://localhost:3000',
 
+    /* Sign every request in, from pages and the API alike. */
+    extraHTTPHeaders: { authorization: `Bearer ${token}` },
+
     /* Collect trace when retrying the failed test. See https://playwright.dev/docs/trace-viewer */
     trace: "on-first-retry",
   },
@@ -97,11 +119,15 @@ const config: PlaywrightTestConfig = {
   /* Folder for test artifacts such as screenshots, videos, traces, etc. */
   // outputDir: 'test-results/',
 
-  /* Run your local dev server before starting the tests */
-  // webServer: {
-  //   command: 'npm run start',
-  //   port: 3000,
-  // },
+  /* Run the server on the throwaway database before starting the tests. It fails rather
+     than use a server already running, which could be on real todos. */
+  webServer: {
+    command: "cargo leptos serve",
+    cwd: "..",
+    url: "http://localhost:3000",
+    reuseExistingServer: false,
+    timeout: 10 * 60 * 1000,
+  },
 };
 
 export default config;
@@ -0,0 +1,233 @@
+import { test, expect, request as requests, APIRequestContext } from "@playwright/test";
+
+// a scripted CalDAV client, each step needs the ones before it
+test.describe.configure({ mode: "serial" });
+
+const server = "http://localhost:3000";
+const collection = "/caldav/todos/";
+
+type Todo = { task: string; done?: boolean; priority?: string; due?: string; tags?: string[] };
+
+async function reset(request: APIRequestContext, todos: Todo[]) {
+  const backup = {
+    format: 1,
+    todos: todos.map((todo, i) => ({ id: i + 1, version: 0, done: false, ...todo })),
+  };
+  const response = await request.post(`${server}/api/import.json?mode=replace`, {
+    data: JSON.stringify(backup),
+    headers: { "content-type": "application/json" },
+  });
+  expect(response.ok()).toBeTruthy();
+}
+
+// the todos as the app has them, without ids and versions
+async function exported(request: APIRequestContext) {
+  const backup = await (await request.get(`${server}/api/export.json`)).json();
+  return backup.todos.map(({ task, done, priority, due, tags }) => ({ task, done, priority, due, tags }));
+}
+
+async function dav(request: APIRequestContext, method: string, path: string, body = "", headers = {}) {
+  return request.fetch(`${server}${path}`, {
+    method,
+    data: body,
+    headers: { "content-type": "application/xml; charset=utf-8", ...headers },
+  });
+}
+
+// hrefs and etags of a multistatus, in order
+function resources(xml: string) {
+  return [...xml.matchAll(/<d:href>([^<]*)<\/d:href>[^]*?<d:getetag>([^<]*)<\/d:getetag>/g)].map(
+    ([, href, etag]) => ({ href, etag: etag.replaceAll("&quot;", '"') }),
+  );
+}
+
+function vtodo(uid: string, summary: string, extra = "") {
+  return [
+    "BEGIN:VCALENDAR",
+    "VERSION:2.0",
+    "PRODID:-//test//client//EN",
+    "BEGIN:VTODO",
+    `UID:${uid}`,
+    "DTSTAMP:20240301T120000Z",
+    `SUMMARY:${summary}`,
+    extra,
+    "BEGIN:VALARM",
+    "ACTION:DISPLAY",
+    "DESCRIPTION:not the summary",
+    "TRIGGER:-PT15M",
+    "END:VALARM",
+    "END:VTODO",
+    "END:VCALENDAR",
+    "",
+  ]
+    .filter((line) => line !== "")
+    .join("\r\n");
+}
+
+test("clients sign in with a token as the password", async () => {
+  // without the bearer token every other request passes
+  const client = await requests.newContext();
+  const anonymous = await client.fetch(`${server}${collection}`, { method: "PROPFIND" });
+  expect(anonymous.status()).toBe(401);
+  expect(anonymous.headers()["www-authenticate"]).toContain("Basic");
+
+  const basic = (password: string) => `Basic ${Buffer.from(`phone:${password}`).toString("base64")}`;
+  const wrong = await client.fetch(`${server}${collection}`, {
+    method: "PROPFIND",
+    headers: { authorization: basic("guess") },
+  });
+  expect(wrong.status()).toBe(401);
+  const signedIn = await client.fetch(`${server}${collection}`, {
+    method: "PROPFIND",
+    headers: { authorization: basic(process.env.E2E_TOKEN!), depth: "0" },
+  });
+  expect(signedIn.status()).toBe(207);
+  await client.dispose();
+});
+
+test("clients discover the calendar", async ({ request }) => {
+  const options = await dav(request, "OPTIONS", collection);
+  expect(options.headers()["dav"]).toContain("calendar-access");
+
+  const wellKnown = await request.fetch(`${server}/.well-known/caldav`, { maxRedirects: 0 });
+  expect(wellKnown.headers()["location"]).toBe("/caldav/");
+
+  const principal = await dav(request, "PROPFIND", "/caldav/", "", { depth: "0" });
+  expect(principal.status()).toBe(207);
+  expect(await principal.text()).toContain("<c:calendar-home-set><d:href>/caldav/</d:href>");
+
+  const home = await dav(request, "PROPFIND", "/caldav/", "", { depth: "1" });
+  const text = await home.text();
+  expect(text).toContain(`<d:href>${collection}</d:href>`);
+  expect(text).toContain('<c:comp name="VTODO"/>');
+
+  const invalid = await dav(request, "PROPFIND", "/caldav/", "", { depth: "2" });
+  expect(invalid.status()).toBe(400);
+});
+
+test("todos are listed and fetched with their etags", async ({ request }) => {
+  await reset(request, [
+    { task: "call mom", priority: "high", due: "2024-04-01", tags: ["family", "phone"] },
+    { task: "pay rent", done: true },
+  ]);
+
+  const listing = await dav(request, "PROPFIND", collection, "", { depth: "1" });
+  const listed = resources(await listing.text());
+  expect(listed).toHaveLength(2);
+
+  const query = await dav(
+    request,
+    "REPORT",
+    collection,
+    '<c:calendar-query xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav"><d:prop><d:getetag/><c:calendar-data/></d:prop></c:calendar-query>',
+    { depth: "1" },
+  );
+  const data = await query.text();
+  expect(data).toContain("SUMMARY:call mom\r\n");
+  expect(data).toContain("PRIORITY:1");
+  expect(data).toContain("DUE;VALUE=DATE:20240401");
+  expect(data).toContain("CATEGORIES:family,phone");
+  expect(data).toContain("STATUS:COMPLETED");
+
+  // filters are applied, here to the todos not done
+  const open = await dav(
+    request,
+    "REPORT",
+    collection,
+    `<c:calendar-query xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav"><d:prop><d:getetag/></d:prop><c:filter><c:comp-filter name="VCALENDAR"><c:comp-filter name="VTODO"><c:prop-filter name="STATUS"><c:text-match negate-condition="yes">COMPLETED</c:text-match></c:prop-filter></c:comp-filter></c:comp-filter></c:filter></c:calendar-query>`,
+    { depth: "1" },
+  );
+  expect(resources(await open.text()).map(({ href }) => href)).toEqual([listed[0].href]);
+
+  const multiget = await dav(
+    request,
+    "REPORT",
+    collection,
+    `<c:calendar-multiget xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav"><d:prop><d:getetag/></d:prop><d:href>${listed[1].href}</d:href><d:href>${collection}missing.ics</d:href></c:calendar-multiget>`,
+  );
+  const got = await multiget.text();
+  expect(got).toContain("SUMMARY:pay rent");
+  expect(got).toContain("404 Not Found");
+
+  const resource = await request.get(`${server}${listed[0].href}`);
+  expect(resource.headers()["etag"]).toBe(listed[0].etag);
+  expect(await resource.text()).toContain("BEGIN:VTODO");
+});
+
+test("client edits show up in the app, guarded by etags", async ({ request }) => {
+  await reset(request, [{ task: "call mom", priority: "high", due: "2024-04-01" }]);
+  const [{ href, etag }] = resources(
+    await (await dav(request, "PROPFIND", collection, "", { depth: "1" })).text(),
+  );
+
+  const updated = await dav(
+    request,
+    "PUT",
+    href,
+    vtodo(
+      "todo@test",
+      "call mom and dad",
+      "STATUS:COMPLETED\r\nPRIORITY:6\r\nDUE;TZID=Europe/Paris:20240402T090000\r\nCATEGORIES:Family,home\\, too",
+    ),
+    { "content-type": "text/calendar", "if-match": etag },
+  );
+  expect(updated.status()).toBe(204);
+  expect(updated.headers()["etag"]).not.toBe(etag);
+  expect(await exported(request)).toEqual([
+    { task: "call mom and dad", done: true, priority: "low", due: "2024-04-02", tags: ["family", "home", "too"] },
+  ]);
+
+  // the etag is now stale
+  const stale = await dav(request, "PUT", href, vtodo("todo@test", "lost"), {
+    "content-type": "text/calendar",
+    "if-match": etag,
+  });
+  expect(stale.status()).toBe(412);
+  const staleDelete = await dav(request, "DELETE", href, "", { "if-match": etag });
+  expect(staleDelete.status()).toBe(412);
+  expect(await exported(request)).toEqual([
+    { task: "call mom and dad", done: true, priority: "low", due: "2024-04-02", tags: ["family", "home", "too"] },
+  ]);
+});
+
+test("client created todos keep their names, and can be deleted", async ({ request }) => {
+  await reset(request, [{ task: "existing" }]);
+  const href = `${collection}0b0e6a2c-client-made.ics`;
+
+  const created = await dav(request, "PUT", href, vtodo("client-made", "from the phone\\, quickly"), {
+    "content-type": "text/calendar",
+    "if-none-match": "*",
+  });
+  expect(created.status()).toBe(201);
+  const etag = created.headers()["etag"];
+  expect((await exported(request)).map((todo) => todo.task)).toEqual(["existing", "from the phone, quickly"]);
+
+  // creating it again fails, it exists
+  const again = await dav(request, "PUT", href, vtodo("client-made", "twice"), {
+    "content-type": "text/calendar",
+    "if-none-match": "*",
+  });
+  expect(again.status()).toBe(412);
+
+  // names like the app's own todos' are refused, they could clash later
+  const taken = await dav(request, "PUT", `${collection}99.ics`, vtodo("client-99", "mine"), {
+    "content-type": "text/calendar",
+  });
+  expect(taken.status()).toBe(403);
+
+  const fetched = await request.get(`${server}${href}`);
+  expect(await fetched.text()).toContain("UID:client-made");
+
+  const deleted = await dav(request, "DELETE", href, "", { "if-match": etag });
+  expect(deleted.status()).toBe(204);
+  expect((await request.get(`${server}${href}`)).status()).toBe(404);
+  expect((await exported(request)).map((todo) => todo.task)).toEqual(["existing"]);
+});
+
+test("resources without a VTODO are rejected", async ({ request }) => {
+  const response = await dav(request, "PUT", `${collection}event.ics`, "BEGIN:VCALENDAR\r\nEND:VCALENDAR\r\n", {
+    "content-type": "text/calendar",
+  });
+  expect(response.status()).toBe(400);
+  expect(await response.text()).toBe("no VTODO in the resource");
+});
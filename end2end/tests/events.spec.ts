@@ -0,0 +1,34 @@
+import { test, expect } from "@playwright/test";
+
+const server = "http://localhost:3000";
+
+test("the event stream tells of changes made elsewhere", async ({ page, request }) => {
+  await page.goto(server);
+
+  // counts the events the browser receives, from when the stream opens
+  await page.evaluate(() => {
+    const events = new EventSource("/api/v1/events");
+    (window as any).changes = 0;
+    events.addEventListener("changed", () => (window as any).changes++);
+  });
+  const changes = () => page.evaluate(() => (window as any).changes as number);
+  await expect.poll(changes).toBeGreaterThan(0);
+
+  // other tests change todos too, so only more events are certain
+  const before = await changes();
+  const todo = await (await request.post(`${server}/api/v1/todos`, { data: { task: "sent as an event" } })).json();
+  await expect.poll(changes).toBeGreaterThan(before);
+
+  const added = await changes();
+  await request.delete(`${server}/api/v1/todos/${todo.id}?version=${todo.version}`);
+  await expect.poll(changes).toBeGreaterThan(added);
+});
+
+test("saved searches are listed", async ({ request }) => {
+  const response = await request.get(`${server}/api/v1/lists`);
+  expect(response.ok()).toBeTruthy();
+  const { lists } = await response.json();
+  for (const list of lists) {
+    expect(Object.keys(list).sort()).toEqual(["filter", "id", "name"]);
+  }
+});
@@ -0,0 +1,129 @@
+import { test, expect, APIRequestContext } from "@playwright/test";
+
+const server = "http://localhost:3000";
+
+async function importFrom(request: APIRequestContext, source: string, body: string, query = "mode=replace") {
+  return request.post(`${server}/api/import/${source}?${query}`, {
+    data: body,
+    headers: { "content-type": "text/plain" },
+  });
+}
+
+// the todos as the app has them, with subtasks pointing at the task of their todo
+async function exported(request: APIRequestContext) {
+  const backup = await (await request.get(`${server}/api/export.json`)).json();
+  const task = (id: number) => backup.todos.find((todo) => todo.id === id).task;
+  return backup.todos.map(({ task: title, done, priority, due, tags, list, parent }) => ({
+    task: title,
+    done,
+    priority,
+    due,
+    tags,
+    list,
+    parent: parent && task(parent),
+  }));
+}
+
+const todo = { done: false, priority: null, due: null, tags: [], list: null, parent: null };
+
+test("Todoist CSV exports map sections, labels, priorities, dates and subtasks", async ({ request }) => {
+  const file = [
+    "TYPE,CONTENT,DESCRIPTION,PRIORITY,INDENT,AUTHOR,RESPONSIBLE,DATE,DATE_LANG,TIMEZONE,DURATION,DURATION_UNIT",
+    "task,Buy milk @errands,,1,1,Me (1),,2024-03-01,en,Europe/Paris,,",
+    "note,oat if there's none,,,,,,,,,,",
+    ",,,,,,,,,,,",
+    "section,Home Stuff,,,,,,,,,,",
+    "task,Fix sink,call a plumber,4,1,,,every monday,en,,,",
+    "task,Buy washer,,2,2,,Sam (2),,en,,30,minute",
+  ].join("\r\n");
+  // an export is one project, imported into a list
+  const response = await importFrom(request, "todoist", file, "mode=replace&list=Chores");
+  expect(response.ok()).toBeTruthy();
+  expect((await response.json()).unmapped).toEqual([
+    "line 3: comment of `Buy milk @errands`",
+    "line 6: due date `every monday` of `Fix sink`",
+    "line 6: description of `Fix sink`",
+    "line 7: assignee of `Buy washer`",
+    "line 7: duration of `Buy washer`",
+  ]);
+  expect(await exported(request)).toEqual([
+    { ...todo, task: "Buy milk", priority: "high", due: "2024-03-01", tags: ["errands"], list: "Chores" },
+    { ...todo, task: "Fix sink", tags: ["home_stuff"], list: "Chores" },
+    { ...todo, task: "Buy washer", priority: "medium", tags: ["home_stuff"], list: "Chores", parent: "Fix sink" },
+  ]);
+});
+
+test("Microsoft To Do lists map importance, categories and steps", async ({ request }) => {
+  const file = JSON.stringify([
+    {
+      displayName: "Work Items",
+      tasks: [
+        {
+          title: "Plan",
+          status: "completed",
+          importance: "high",
+          dueDateTime: { dateTime: "2024-03-05T00:00:00.0000000", timeZone: "UTC" },
+          categories: ["Blue category"],
+          body: { content: "", contentType: "text" },
+          checklistItems: [{ displayName: "step one", isChecked: false }],
+          isReminderOn: true,
+        },
+        { title: "Low", status: "notStarted", importance: "low", recurrence: { pattern: {} } },
+      ],
+    },
+  ]);
+  const response = await importFrom(request, "microsoft_to_do", file);
+  expect((await response.json()).unmapped).toEqual([
+    "task 1 of `Work Items`: reminder of `Plan`",
+    "task 2 of `Work Items`: repeat of `Low`",
+  ]);
+  expect(await exported(request)).toEqual([
+    {
+      ...todo,
+      task: "Plan",
+      done: true,
+      priority: "high",
+      due: "2024-03-05",
+      tags: ["blue_category"],
+      list: "Work Items",
+    },
+    { ...todo, task: "step one", list: "Work Items", parent: "Plan" },
+    { ...todo, task: "Low", priority: "low", list: "Work Items" },
+  ]);
+});
+
+test("Google Takeout tasks map lists and subtasks, and skip deleted ones", async ({ request }) => {
+  const file = JSON.stringify({
+    kind: "tasks#taskLists",
+    items: [
+      {
+        kind: "tasks#taskList",
+        title: "My Tasks",
+        items: [
+          { id: "p", title: "Call mom", status: "needsAction", due: "2024-03-01T00:00:00.000Z", notes: "about the trip" },
+          { title: "gone", status: "completed", deleted: true },
+          { id: "c", title: "child", status: "completed", parent: "p" },
+          { title: "lost", status: "needsAction", parent: "gone" },
+          { title: "", status: "needsAction" },
+        ],
+      },
+    ],
+  });
+  const response = await importFrom(request, "google_tasks", file);
+  expect((await response.json()).unmapped).toEqual([
+    "task 1 of `My Tasks`: notes of `Call mom`",
+    "task 5 of `My Tasks`: skipped a task without a title",
+    "task 4 of `My Tasks`: parent task of `lost`",
+  ]);
+  expect(await exported(request)).toEqual([
+    { ...todo, task: "Call mom", due: "2024-03-01", list: "My Tasks" },
+    { ...todo, task: "child", done: true, list: "My Tasks", parent: "Call mom" },
+    { ...todo, task: "lost", list: "My Tasks" },
+  ]);
+});
+
+test("files of the wrong app are rejected", async ({ request }) => {
+  const response = await importFrom(request, "todoist", "task,title\n1,2");
+  expect(response.status()).toBe(400);
+  expect(await response.text()).toContain("not a Todoist export");
+});
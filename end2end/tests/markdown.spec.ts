@@ -0,0 +1,79 @@
+import { test, expect, APIRequestContext } from "@playwright/test";
+
+const importUrl = "http://localhost:3000/api/import.md";
+const exportUrl = "http://localhost:3000/api/export.md";
+
+async function importMarkdown(request: APIRequestContext, body: string, query = "mode=replace") {
+  return request.post(`${importUrl}?${query}`, {
+    data: body,
+    headers: { "content-type": "text/markdown" },
+  });
+}
+
+test("checklists pasted from issues import and export", async ({ request }) => {
+  const issue = [
+    "## Release checklist",
+    "",
+    "Before tagging:",
+    "- [ ] write notes +release",
+    "  - [x] nested items are subtasks",
+    "    * [X] however deep",
+    "1. [ ] numbered items too",
+    "- plain items and prose are skipped",
+    "```markdown",
+    "- [ ] so are examples in code blocks",
+    "```",
+    "- [x]   extra spaces   ",
+  ].join("\r\n");
+  const imported = await importMarkdown(request, issue);
+  expect(await imported.json()).toMatchObject({ added: 5, dry_run: false });
+
+  const exported = await request.get(exportUrl);
+  expect(exported.headers()["content-type"]).toContain("text/markdown");
+  const checklist = [
+    "- [ ] write notes +release",
+    "  - [x] nested items are subtasks",
+    "    - [x] however deep",
+    "- [ ] numbered items too",
+    "- [x] extra spaces",
+    "",
+  ].join("\n");
+  expect(await exported.text()).toBe(checklist);
+
+  // exported checklists import as the same todos
+  const reimported = await importMarkdown(request, checklist, "mode=merge");
+  expect(await reimported.json()).toMatchObject({ added: 0, skipped: 5 });
+
+  // deleting a todo deletes its subtasks
+  const backup = await (await request.get("http://localhost:3000/api/export.json")).json();
+  const notes = backup.todos.find((todo) => todo.task === "write notes +release");
+  expect(backup.todos.filter((todo) => todo.parent === notes.id)).toHaveLength(1);
+  await request.delete(`http://localhost:3000/api/v1/todos/${notes.id}?version=${notes.version}`);
+  expect(await (await request.get(exportUrl)).text()).toBe("- [ ] numbered items too\n- [x] extra spaces\n");
+});
+
+test("checklists import into a list, and export by list", async ({ request }) => {
+  await importMarkdown(request, "- [ ] unlisted");
+  const imported = await importMarkdown(request, "- [ ] ship it\n  - [ ] tag", "mode=merge&list=Release");
+  expect(await imported.json()).toMatchObject({ added: 2 });
+
+  const release = await request.get(`${exportUrl}?q=${encodeURIComponent("list:release")}`);
+  expect(await release.text()).toBe("- [ ] ship it\n  - [ ] tag\n");
+});
+
+test("exports can be limited to a filter", async ({ request }) => {
+  await importMarkdown(request, "- [ ] ship it +release\n- [x] shipped +release\n- [ ] unrelated");
+  const open = await request.get(`${exportUrl}?q=${encodeURIComponent("+release is:open")}`);
+  expect(await open.text()).toBe("- [ ] ship it +release\n");
+
+  const invalid = await request.get(`${exportUrl}?q=${encodeURIComponent("is:")}`);
+  expect(invalid.status()).toBe(400);
+});
+
+test("checklist items without a task are rejected with their line", async ({ request }) => {
+  const response = await importMarkdown(request, "- [ ] fine\n- [ ]\n- [x]   ");
+  expect(response.status()).toBe(400);
+  const message = await response.text();
+  expect(message).toContain("line 2: task can't be empty");
+  expect(message).toContain("line 3: task can't be empty");
+});
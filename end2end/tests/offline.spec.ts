@@ -0,0 +1,22 @@
+import { test, expect } from "@playwright/test";
+
+const server = "http://localhost:3000";
+
+test("pages opened offline show the todos as last seen, not as cached", async ({ page, context, browserName }) => {
+  test.skip(browserName !== "chromium", "only Chromium lets tests go offline under a service worker");
+
+  // the service worker caches the page as it is now
+  await page.goto(server);
+  await page.waitForFunction(() => navigator.serviceWorker.controller !== null);
+
+  const task = `added after caching ${Date.now()}`;
+  await page.locator("#floatingTodo").fill(task);
+  await page.locator("#floatingTodo").press("Enter");
+  await expect(page.getByText(task)).toBeVisible();
+
+  // the cached page doesn't have the todo, the browser's local copy does
+  await context.setOffline(true);
+  await page.reload();
+  await expect(page.getByText(task)).toBeVisible();
+  await context.setOffline(false);
+});
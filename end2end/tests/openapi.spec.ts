@@ -0,0 +1,79 @@
+import { test, expect } from "@playwright/test";
+
+const server = "http://localhost:3000";
+const methods = ["get", "post", "put", "patch", "delete"];
+
+// a todo that doesn't exist, for operations on one
+const missingId = "999999999";
+
+type Operation = { requestBody?: unknown; responses: Record<string, { content?: Record<string, unknown> }> };
+
+// streams stay open, so they're only checked to be documented
+function isStream(operation: Operation) {
+  return Object.values(operation.responses).some((response) => "text/event-stream" in (response.content ?? {}));
+}
+
+// fails when the document and the routes drift apart, by calling every documented
+// operation and checking it answers with a documented status, and that methods
+// that aren't documented aren't routed
+test("the OpenAPI document matches the routes", async ({ request }) => {
+  const response = await request.get(`${server}/api/openapi.json`);
+  expect(response.ok()).toBeTruthy();
+  const spec = await response.json();
+  expect(spec.openapi).toMatch(/^3\./);
+
+  const paths: [string, Record<string, Operation>][] = Object.entries(spec.paths);
+  expect(paths.length).toBeGreaterThan(0);
+
+  for (const [path, operations] of paths) {
+    const url = server + path.replace("{id}", missingId);
+
+    for (const method of methods) {
+      // an empty body is invalid for every operation taking one, so nothing changes
+      const operation = operations[method];
+      if (operation && isStream(operation)) {
+        continue;
+      }
+      const answer = await request.fetch(url, {
+        method,
+        ...(operation?.requestBody ? { data: {} } : {}),
+      });
+
+      if (!operation) {
+        expect(answer.status(), `${method.toUpperCase()} ${path} isn't documented`).toBe(405);
+        continue;
+      }
+
+      const documented = Object.keys(operation.responses);
+      expect(documented, `${method.toUpperCase()} ${path} answered ${answer.status()}`).toContain(
+        String(answer.status()),
+      );
+      // a 404 from the router instead of the handler would be the app's error page
+      if (answer.status() !== 204) {
+        expect(answer.headers()["content-type"]).toContain("application/json");
+      }
+    }
+  }
+});
+
+test("documented schemas cover the todos the API returns", async ({ request }) => {
+  const spec = await (await request.get(`${server}/api/openapi.json`)).json();
+  const todo = spec.components.schemas.TodoItem;
+  expect(Object.keys(todo.properties).sort()).toEqual([
+    "completed",
+    "created",
+    "done",
+    "due",
+    "id",
+    "list",
+    "parent",
+    "priority",
+    "tags",
+    "task",
+    "version",
+  ]);
+
+  const created = await (await request.post(`${server}/api/v1/todos`, { data: { task: "schema check" } })).json();
+  expect(Object.keys(created).sort()).toEqual(Object.keys(todo.properties).sort());
+  await request.delete(`${server}/api/v1/todos/${created.id}?version=${created.version}`);
+});
@@ -0,0 +1,175 @@
+import { test, expect, APIRequestContext } from "@playwright/test";
+
+const api = "http://localhost:3000/api/v1/todos";
+
+async function reset(request: APIRequestContext, todoTxt: string) {
+  const response = await request.post("http://localhost:3000/api/import.txt?mode=replace", {
+    data: todoTxt,
+    headers: { "content-type": "text/plain" },
+  });
+  expect(response.ok()).toBeTruthy();
+}
+
+async function tasks(request: APIRequestContext, query = "") {
+  const page = await (await request.get(`${api}${query}`)).json();
+  return page.todos.map((todo: { task: string; done: boolean }) => [todo.task, todo.done]);
+}
+
+test("todos are created, changed and deleted", async ({ request }) => {
+  await reset(request, "");
+
+  const created = await request.post(api, { data: { task: "  write the API  " } });
+  expect(created.status()).toBe(201);
+  const todo = await created.json();
+  expect(todo).toMatchObject({ task: "write the API", done: false });
+  expect(created.headers()["location"]).toBe(`/api/v1/todos/${todo.id}`);
+  expect(await (await request.get(`${api}/${todo.id}`)).json()).toEqual(todo);
+
+  const patched = await request.patch(`${api}/${todo.id}`, {
+    data: { task: "ship the API", done: true, version: todo.version },
+  });
+  expect(patched.status()).toBe(200);
+  const changed = await patched.json();
+  expect(changed).toMatchObject({ id: todo.id, task: "ship the API", done: true });
+  // both changes are one
+  expect(changed.version).toBe(todo.version + 1);
+
+  // sending the same changes again passes
+  const retried = await request.patch(`${api}/${todo.id}`, {
+    data: { task: "ship the API", done: true, version: todo.version },
+  });
+  expect(await retried.json()).toEqual(changed);
+
+  // the version the first change was made against is now stale
+  const stale = await request.patch(`${api}/${todo.id}`, { data: { task: "lost", version: todo.version } });
+  expect(stale.status()).toBe(409);
+  expect(await stale.json()).toEqual({
+    error: { code: "conflict", message: "todo was changed by someone else" },
+  });
+  expect((await request.delete(`${api}/${todo.id}?version=${todo.version}`)).status()).toBe(409);
+
+  expect((await request.delete(`${api}/${todo.id}?version=${changed.version}`)).status()).toBe(204);
+  const missing = await request.get(`${api}/${todo.id}`);
+  expect(missing.status()).toBe(404);
+  expect((await missing.json()).error.code).toBe("not_found");
+  // deleting again passes, so retries don't fail, but a version is required
+  expect((await request.delete(`${api}/${todo.id}?version=${changed.version}`)).status()).toBe(204);
+  expect((await request.delete(`${api}/${todo.id}`)).status()).toBe(400);
+});
+
+test("todos are created and changed with their details", async ({ request }) => {
+  await reset(request, "");
+
+  const created = await request.post(api, {
+    data: { task: "plan", priority: "high", due: "2030-01-02", tags: ["#Work", "home"], list: " Errands " },
+  });
+  expect(created.status()).toBe(201);
+  const todo = await created.json();
+  expect(todo).toMatchObject({ priority: "high", due: "2030-01-02", tags: ["work", "home"], list: "Errands" });
+
+  // `null` clears a detail, and what isn't there stays as it is
+  const patched = await request.patch(`${api}/${todo.id}`, {
+    data: { priority: null, due: null, tags: ["home"], version: todo.version },
+  });
+  expect(patched.status()).toBe(200);
+  expect(await patched.json()).toMatchObject({
+    task: "plan",
+    priority: null,
+    due: null,
+    tags: ["home"],
+    list: "Errands",
+    version: todo.version + 1,
+  });
+
+  const badDue = await request.post(api, { data: { task: "plan", due: "soon" } });
+  expect(badDue.status()).toBe(422);
+  expect((await badDue.json()).error.code).toBe("invalid");
+  const badPriority = await request.post(api, { data: { task: "plan", priority: "urgent" } });
+  expect(badPriority.status()).toBe(422);
+});
+
+test("lists are filtered and paged", async ({ request }) => {
+  await reset(request, "one +work\nx two +work\nthree\nfour +work");
+
+  expect(await tasks(request, `?q=${encodeURIComponent("+work is:open")}`)).toEqual([
+    ["one +work", false],
+    ["four +work", false],
+  ]);
+
+  const first = await (await request.get(`${api}?limit=2`)).json();
+  expect(first.todos).toHaveLength(2);
+  const second = await (await request.get(`${api}?limit=2&after=${first.next}`)).json();
+  expect(second.todos.map((todo: { task: string }) => todo.task)).toEqual(["three", "four +work"]);
+  expect(second.next).toBeNull();
+});
+
+test("bulk endpoints take todos at their versions or a filter", async ({ request }) => {
+  await reset(request, "one +work\ntwo +work\nthree");
+
+  const done = await request.post(`${api}/bulk/done`, { data: { filter: "+work" } });
+  expect((await done.json()).todos).toHaveLength(2);
+
+  const todos = (await (await request.get(api)).json()).todos.map(
+    (todo: { id: number; version: number }) => ({ id: todo.id, version: todo.version }),
+  );
+  const undone = await request.post(`${api}/bulk/undone`, { data: { todos: [todos[0]] } });
+  expect(await undone.json()).toMatchObject({ conflicts: [] });
+  expect(await tasks(request)).toEqual([
+    ["one +work", false],
+    ["two +work", true],
+    ["three", false],
+  ]);
+
+  // the first todo changed since its version was read, so it's left alone
+  const deleted = await request.post(`${api}/bulk/delete`, { data: { todos } });
+  expect(await deleted.json()).toEqual({ deleted: [todos[1].id, todos[2].id], conflicts: [todos[0].id] });
+  expect(await tasks(request)).toEqual([["one +work", false]]);
+
+  const both = await request.post(`${api}/bulk/delete`, { data: { todos: [], filter: "" } });
+  expect(both.status()).toBe(422);
+});
+
+test("bulk endpoints tag, move and prioritize todos", async ({ request }) => {
+  await reset(request, "one\ntwo +work");
+
+  const todos = (await (await request.get(api)).json()).todos.map(
+    (todo: { id: number; version: number }) => ({ id: todo.id, version: todo.version }),
+  );
+  // the second todo already has the tag, so only the first changes
+  const tagged = await (await request.post(`${api}/bulk/tag`, { data: { todos, tags: ["work"] } })).json();
+  expect(tagged.todos.map((todo: { id: number }) => todo.id)).toEqual([todos[0].id]);
+  expect(tagged.conflicts).toEqual([]);
+
+  const latest = (await (await request.get(api)).json()).todos.map(
+    (todo: { id: number; version: number }) => ({ id: todo.id, version: todo.version }),
+  );
+  const moved = await (await request.post(`${api}/bulk/move`, { data: { todos: latest, list: "Errands" } })).json();
+  expect(moved.todos.map((todo: { list: string }) => todo.list)).toEqual(["Errands", "Errands"]);
+
+  // the versions read before the move are stale
+  const prioritized = await (
+    await request.post(`${api}/bulk/priority`, { data: { todos: latest, priority: "low" } })
+  ).json();
+  expect(prioritized).toEqual({ todos: [], conflicts: latest.map((todo: { id: number }) => todo.id) });
+
+  const noTags = await request.post(`${api}/bulk/tag`, { data: { todos: latest, tags: [] } });
+  expect(noTags.status()).toBe(422);
+});
+
+test("invalid input is a 422 with an error object", async ({ request }) => {
+  const empty = await request.post(api, { data: { task: "   " } });
+  expect(empty.status()).toBe(422);
+  expect(await empty.json()).toEqual({ error: { code: "invalid", message: "task can't be empty" } });
+
+  const missingVersion = await request.patch(`${api}/1`, { data: { task: "no version" } });
+  expect(missingVersion.status()).toBe(422);
+  expect((await missingVersion.json()).error.code).toBe("invalid");
+
+  const badFilter = await request.get(`${api}?q=is:`);
+  expect(badFilter.status()).toBe(422);
+  expect((await badFilter.json()).error.message).toContain("invalid filter");
+
+  const notJson = await request.post(api, { data: "task=form", headers: { "content-type": "text/plain" } });
+  expect(notJson.status()).toBe(415);
+  expect((await notJson.json()).error.code).toBe("unsupported_media_type");
+});
@@ -0,0 +1,74 @@
+import { test, expect, APIRequestContext } from "@playwright/test";
+
+const importUrl = "http://localhost:3000/api/import.txt";
+const exportUrl = "http://localhost:3000/api/export.txt";
+
+// each line imported, and how it's exported again
+const corpus: [string, string][] = [
+  ["buy milk", "buy milk"],
+  ["x buy bread", "x buy bread"],
+  // completion and creation dates are kept
+  ["x 2024-03-02 2024-03-01 pay rent +home", "x 2024-03-02 2024-03-01 pay rent +home"],
+  ["x 2024-03-02 water plants", "x 2024-03-02 water plants"],
+  // +project tags, the @context list and due: are fields, written after the task
+  [
+    "2024-03-01 plan trip due:2024-04-01 +Travel @laptop",
+    "2024-03-01 plan trip +travel @laptop due:2024-04-01",
+  ],
+  // only the first @context is the list, the rest stay in the task
+  ["call @work_phone @home", "call @home @work_phone"],
+  ["(A) call mom @phone", "(A) call mom @phone"],
+  ["(B) 2024-03-01 book flights", "(B) 2024-03-01 book flights"],
+  // done todos keep their priority as a pri: tag, even after their dates
+  ["x (C) file taxes", "x file taxes pri:C"],
+  ["x 2024-01-02 2024-01-01 (A) ship it", "x 2024-01-02 2024-01-01 ship it pri:A"],
+  ["x 2024-03-02 renew passport pri:D", "x 2024-03-02 renew passport pri:D"],
+  // not done, priority or dates
+  ["X uppercase isn't done", "X uppercase isn't done"],
+  ["xylophone lessons", "xylophone lessons"],
+  ["(a) lowercase isn't a priority", "(a) lowercase isn't a priority"],
+  ["(E)needs a space", "(E)needs a space"],
+  ["2024-13-45 isn't a date", "2024-13-45 isn't a date"],
+  ["x x marks the spot", "x x marks the spot"],
+  ["   surrounding spaces   ", "surrounding spaces"],
+];
+
+async function importTodoTxt(request: APIRequestContext, body: string, query = "mode=replace") {
+  return request.post(`${importUrl}?${query}`, {
+    data: body,
+    headers: { "content-type": "text/plain" },
+  });
+}
+
+test("todo.txt round-trips through import and export", async ({ request }) => {
+  // a BOM, CRLF line endings and blank lines are all fine
+  const file = "\uFEFF" + corpus.map(([line]) => line).join("\r\n") + "\r\n\r\n";
+  const imported = await importTodoTxt(request, file);
+  expect(imported.ok()).toBeTruthy();
+  expect(await imported.json()).toMatchObject({ added: corpus.length, dry_run: false });
+
+  const exported = await request.get(exportUrl);
+  expect(exported.ok()).toBeTruthy();
+  expect((await exported.text()).split("\n").filter((line) => line !== "")).toEqual(
+    corpus.map(([, line]) => line),
+  );
+
+  // exported files import as the same todos
+  const reimported = await importTodoTxt(request, await exported.text(), "mode=merge");
+  expect(await reimported.json()).toMatchObject({ added: 0, skipped: corpus.length });
+});
+
+test("todo.txt dry runs change nothing", async ({ request }) => {
+  await importTodoTxt(request, "before");
+  const dryRun = await importTodoTxt(request, "one\ntwo", "mode=replace&dry_run=true");
+  expect(await dryRun.json()).toMatchObject({ added: 2, deleted: 1, dry_run: true });
+  expect(await (await request.get(exportUrl)).text()).toBe("before\n");
+});
+
+test("todo.txt lines without a task are rejected with their line", async ({ request }) => {
+  const response = await importTodoTxt(request, "fine\nx 2024-03-02 \n(A) ");
+  expect(response.status()).toBe(400);
+  const message = await response.text();
+  expect(message).toContain("line 2: task can't be empty");
+  expect(message).toContain("line 3: task can't be empty");
+});
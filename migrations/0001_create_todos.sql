@@ -0,0 +1,5 @@
+CREATE TABLE IF NOT EXISTS todos (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    done BOOLEAN DEFAULT false,
+    task TEXT NOT NULL
+);
@@ -0,0 +1,2 @@
+-- bumped on every change, so stale updates can be detected and rejected
+ALTER TABLE todos ADD COLUMN version INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,23 @@
+-- full-text index over todos, kept in sync by the triggers below
+-- unicode61 folds case, remove_diacritics folds accents, so "cafe" finds "Café"
+CREATE VIRTUAL TABLE todos_fts USING fts5(
+    task,
+    content = 'todos',
+    content_rowid = 'id',
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+
+INSERT INTO todos_fts (todos_fts) VALUES ('rebuild');
+
+CREATE TRIGGER todos_fts_insert AFTER INSERT ON todos BEGIN
+    INSERT INTO todos_fts (rowid, task) VALUES (new.id, new.task);
+END;
+
+CREATE TRIGGER todos_fts_delete AFTER DELETE ON todos BEGIN
+    INSERT INTO todos_fts (todos_fts, rowid, task) VALUES ('delete', old.id, old.task);
+END;
+
+CREATE TRIGGER todos_fts_update AFTER UPDATE OF task ON todos BEGIN
+    INSERT INTO todos_fts (todos_fts, rowid, task) VALUES ('delete', old.id, old.task);
+    INSERT INTO todos_fts (rowid, task) VALUES (new.id, new.task);
+END;
@@ -0,0 +1,8 @@
+-- filters saved under a name, listed by position
+CREATE TABLE IF NOT EXISTS saved_searches (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    query TEXT NOT NULL,
+    sort TEXT NOT NULL,
+    position INTEGER NOT NULL
+);
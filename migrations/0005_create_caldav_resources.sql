@@ -0,0 +1,7 @@
+-- resource names and uids CalDAV clients gave the todos they created, other todos
+-- are `<id>.ics` with a uid made from their id
+CREATE TABLE IF NOT EXISTS caldav_resources (
+    todo_id INTEGER PRIMARY KEY REFERENCES todos (id) ON DELETE CASCADE,
+    name TEXT NOT NULL UNIQUE,
+    uid TEXT NOT NULL
+);
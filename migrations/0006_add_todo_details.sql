@@ -0,0 +1,8 @@
+-- priority 1 is low, 2 medium and 3 high, null for none
+ALTER TABLE todos ADD COLUMN priority INTEGER;
+-- YYYY-MM-DD
+ALTER TABLE todos ADD COLUMN due TEXT;
+-- a JSON array of lowercase tags
+ALTER TABLE todos ADD COLUMN tags TEXT NOT NULL DEFAULT '[]';
+
+CREATE INDEX todos_due ON todos (due);
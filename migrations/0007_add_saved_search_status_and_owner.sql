@@ -0,0 +1,6 @@
+-- the status tab, all, open or done
+ALTER TABLE saved_searches ADD COLUMN status TEXT NOT NULL DEFAULT 'all';
+-- the user who saved it, by their name in API_TOKENS, null for everyone's
+ALTER TABLE saved_searches ADD COLUMN owner TEXT;
+
+CREATE INDEX saved_searches_owner ON saved_searches (owner, position);
@@ -0,0 +1,4 @@
+-- the list a todo is in, by name, null for none
+ALTER TABLE todos ADD COLUMN list TEXT;
+
+CREATE INDEX todos_list ON todos (list COLLATE NOCASE);
@@ -0,0 +1,4 @@
+-- the todo a subtask is part of, null for todos that aren't subtasks
+ALTER TABLE todos ADD COLUMN parent INTEGER REFERENCES todos (id) ON DELETE CASCADE;
+
+CREATE INDEX todos_parent ON todos (parent);
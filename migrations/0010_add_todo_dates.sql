@@ -0,0 +1,4 @@
+-- YYYY-MM-DD a todo was added, null for todos added before it was kept
+ALTER TABLE todos ADD COLUMN created TEXT;
+-- YYYY-MM-DD a todo was last done, null while it isn't
+ALTER TABLE todos ADD COLUMN completed TEXT;
@@ -0,0 +1,6 @@
+-- the server's users, by name, with the SHA-256 of their token as lowercase hex
+CREATE TABLE IF NOT EXISTS users (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE,
+    token_hash TEXT NOT NULL UNIQUE
+);
@@ -0,0 +1,72 @@
+// Served by `service_worker` in fileserv.rs, which fills in the placeholders from
+// the build output under site-pkg-dir, so every new build gets a new cache.
+const CACHE_VERSION = "__CACHE_VERSION__";
+const PKG_URLS = __PKG_URLS__;
+
+const CACHE_NAME = `leptos-todo-${CACHE_VERSION}`;
+const PRECACHE_URLS = [
+  "/",
+  "/manifest.webmanifest",
+  "/icon.svg",
+  "/favicon.ico",
+  "/css/bootstrap.min.css",
+  "/css/bootstrap-icons.min.css",
+  "/css/fonts/bootstrap-icons.woff2",
+  "/css/fonts/bootstrap-icons.woff",
+  "/js/bootstrap.bundle.min.js",
+  ...PKG_URLS,
+];
+
+self.addEventListener("install", (event) => {
+  event.waitUntil(
+    caches
+      .open(CACHE_NAME)
+      .then((cache) => cache.addAll(PRECACHE_URLS))
+      .then(() => self.skipWaiting()),
+  );
+});
+
+// drop the caches of earlier builds
+self.addEventListener("activate", (event) => {
+  event.waitUntil(
+    caches
+      .keys()
+      .then((keys) =>
+        Promise.all(
+          keys
+            .filter((key) => key.startsWith("leptos-todo-") && key !== CACHE_NAME)
+            .map((key) => caches.delete(key)),
+        ),
+      )
+      .then(() => self.clients.claim()),
+  );
+});
+
+self.addEventListener("fetch", (event) => {
+  const request = event.request;
+  const url = new URL(request.url);
+
+  // server functions always go to the network, the app queues them when offline
+  if (
+    request.method !== "GET" ||
+    url.origin !== self.location.origin ||
+    url.pathname.startsWith("/api/")
+  ) {
+    return;
+  }
+
+  // pages are rendered by the server, fall back to the cached app shell when offline.
+  // Its todos are the ones it was cached with, the app renders its local copy instead.
+  if (request.mode === "navigate") {
+    event.respondWith(
+      fetch(request).catch(() => caches.match("/", { cacheName: CACHE_NAME })),
+    );
+    return;
+  }
+
+  event.respondWith(
+    caches
+      .match(request, { cacheName: CACHE_NAME })
+      .then((cached) => cached || fetch(request)),
+  );
+});
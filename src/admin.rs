@@ -0,0 +1,94 @@
+//! Maintenance of the database, for the commands of the server binary.
+
+use cfg_if::cfg_if;
+
+cfg_if! { if #[cfg(feature = "ssr")] {
+    use crate::app::{database_file, db};
+    use leptos::ServerFnError;
+    use sqlx::{
+        migrate::MigrateDatabase,
+        sqlite::{SqliteConnectOptions, SqlitePool},
+        Sqlite,
+    };
+    use std::str::FromStr;
+
+    /// Fails unless the database exists, for commands that shouldn't create one.
+    pub async fn require_database() -> Result<(), ServerFnError> {
+        let file = database_file();
+        if Sqlite::database_exists(&file).await? {
+            Ok(())
+        } else {
+            Err(ServerFnError::ServerError(format!("there is no database at {file}")))
+        }
+    }
+
+    /// Creates the database when it's missing and applies the migrations it's
+    /// missing, like the server does as it starts, returning the latest one.
+    pub async fn migrate() -> Result<String, ServerFnError> {
+        let pool = db().await?;
+
+        let (version, description) = sqlx::query_as::<_, (i64, String)>(
+            "SELECT version, description FROM _sqlx_migrations
+            WHERE success ORDER BY version DESC LIMIT 1",
+        )
+        .fetch_one(&pool)
+        .await?;
+
+        Ok(format!("{version} {description}"))
+    }
+
+    /// Merges the search index and rebuilds the file without its free pages,
+    /// returning its size before and after.
+    pub async fn vacuum() -> Result<(u64, u64), ServerFnError> {
+        require_database().await?;
+        let pool = db().await?;
+        let before = std::fs::metadata(database_file())?.len();
+
+        sqlx::query("INSERT INTO todos_fts (todos_fts) VALUES ('optimize')")
+            .execute(&pool)
+            .await?;
+        sqlx::query("VACUUM").execute(&pool).await?;
+
+        Ok((before, std::fs::metadata(database_file())?.len()))
+    }
+
+    /// Problems with the database, none when it's sound. It's opened without
+    /// migrating it, and isn't changed.
+    pub async fn check() -> Result<Vec<String>, ServerFnError> {
+        require_database().await?;
+        let options = SqliteConnectOptions::from_str(&database_file())?;
+        let pool = SqlitePool::connect_with(options).await?;
+
+        let mut problems: Vec<String> = sqlx::query_scalar("PRAGMA integrity_check")
+            .fetch_all(&pool)
+            .await?
+            .into_iter()
+            .filter(|result: &String| result != "ok")
+            .collect();
+
+        let orphans = sqlx::query_as::<_, (String, Option<i64>, String)>(
+            "SELECT \"table\", rowid, parent FROM pragma_foreign_key_check",
+        )
+        .fetch_all(&pool)
+        .await?;
+        for (table, rowid, parent) in orphans {
+            let row = rowid.map_or_else(|| "a row".into(), |rowid| format!("row {rowid}"));
+            problems.push(format!("{row} of {table} refers to a missing row of {parent}"));
+        }
+
+        // fails with the index out of step with the todos
+        if let Err(e) = sqlx::query("INSERT INTO todos_fts (todos_fts) VALUES ('integrity-check')")
+            .execute(&pool)
+            .await
+        {
+            problems.push(format!("the search index doesn't match the todos: {e}"));
+        }
+
+        let empty: Vec<u32> = sqlx::query_scalar("SELECT id FROM todos WHERE trim(task) = ''")
+            .fetch_all(&pool)
+            .await?;
+        problems.extend(empty.into_iter().map(|id| format!("todo {id} has an empty task")));
+
+        Ok(problems)
+    }
+}}
@@ -1,66 +1,577 @@
+use crate::backup::{Backups, CopyMarkdown, ImportSummary, ImportTodos};
+use crate::dates;
 use crate::error_template::{AppError, ErrorTemplate};
+use crate::offline;
+use crate::query::{ParseError, Query, Sort, Status};
+use crate::saved_search::SavedSearches;
+use crate::shortcuts::{on_shortcut, Command, Keymap, Shortcuts};
 use cfg_if::cfg_if;
 use leptos::*;
 use leptos_meta::*;
 use leptos_router::*;
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 
-#[cfg_attr(feature = "ssr", derive(sqlx::FromRow))]
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Hash)]
+#[cfg_attr(feature = "ssr", derive(sqlx::FromRow, utoipa::ToSchema))]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, Hash)]
 pub struct TodoItem {
-    id: u32,
-    done: bool,
-    task: String,
+    pub(crate) id: u32,
+    pub(crate) done: bool,
+    pub(crate) task: String,
+    /// Bumped on every change, mutations must pass the version they last saw.
+    pub(crate) version: u32,
+    #[serde(default)]
+    pub(crate) priority: Option<Priority>,
+    /// `YYYY-MM-DD`.
+    #[serde(default)]
+    pub(crate) due: Option<String>,
+    /// Lowercase, see [`parse_tags`].
+    #[serde(default)]
+    #[cfg_attr(feature = "ssr", sqlx(json))]
+    pub(crate) tags: Vec<String>,
+    /// Name of the list the todo is in, see [`list_name`].
+    #[serde(default)]
+    pub(crate) list: Option<String>,
+    /// The todo this one is a subtask of, deleted along with it.
+    #[serde(default)]
+    pub(crate) parent: Option<u32>,
+    /// `YYYY-MM-DD` it was added, none for todos from before it was kept.
+    #[serde(default)]
+    pub(crate) created: Option<String>,
+    /// `YYYY-MM-DD` it was last done, none when it isn't.
+    #[serde(default)]
+    pub(crate) completed: Option<String>,
+}
+
+/// Read-only access for clients outside the crate, like the command-line client.
+impl TodoItem {
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn done(&self) -> bool {
+        self.done
+    }
+
+    pub fn task(&self) -> &str {
+        &self.task
+    }
+
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    pub fn priority(&self) -> Option<Priority> {
+        self.priority
+    }
+
+    pub fn due(&self) -> Option<&str> {
+        self.due.as_deref()
+    }
+
+    pub fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    pub fn list(&self) -> Option<&str> {
+        self.list.as_deref()
+    }
+
+    pub fn parent(&self) -> Option<u32> {
+        self.parent
+    }
+
+    pub fn created(&self) -> Option<&str> {
+        self.created.as_deref()
+    }
+
+    pub fn completed(&self) -> Option<&str> {
+        self.completed.as_deref()
+    }
+}
+
+/// How urgent a todo is, kept as 1 to 3 so it sorts.
+#[cfg_attr(feature = "ssr", derive(sqlx::Type, utoipa::ToSchema))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[repr(i32)]
+pub enum Priority {
+    Low = 1,
+    Medium = 2,
+    High = 3,
+}
+
+impl Priority {
+    pub const ALL: [Priority; 3] = [Priority::High, Priority::Medium, Priority::Low];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Priority::Low => "low",
+            Priority::Medium => "medium",
+            Priority::High => "high",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Priority::Low => "Low",
+            Priority::Medium => "Medium",
+            Priority::High => "High",
+        }
+    }
+}
+
+impl std::str::FromStr for Priority {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Priority::ALL
+            .into_iter()
+            .find(|priority| priority.as_str() == s)
+            .ok_or_else(|| format!("unknown priority `{s}`, expected high, medium or low"))
+    }
+}
+
+/// Tags as typed, separated by spaces or commas and with an optional leading `#`,
+/// lowercased and without repeats.
+pub fn parse_tags(text: &str) -> Vec<String> {
+    let mut tags: Vec<String> = vec![];
+    for tag in text.split(|c: char| c.is_whitespace() || c == ',') {
+        let tag = tag.trim_start_matches('#').to_lowercase();
+        if !tag.is_empty() && !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    tags
+}
+
+/// A list name as typed, trimmed, or `None` for no list when it's blank.
+pub fn list_name(text: &str) -> Option<String> {
+    Some(text.trim())
+        .filter(|name| !name.is_empty())
+        .map(String::from)
+}
+
+/// Server error message of mutations made against an outdated todo version.
+pub const CONFLICT: &str = "todo was changed by someone else";
+
+/// Server error message of mutations on a todo that doesn't exist.
+pub const NOT_FOUND: &str = "todo not found";
+
+pub fn is_conflict(err: &ServerFnError) -> bool {
+    matches!(err, ServerFnError::ServerError(message) if message == CONFLICT)
+}
+
+/// A todo as a bulk change expects it to be, one at another version is a conflict.
+#[cfg_attr(feature = "ssr", derive(utoipa::ToSchema))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, Hash)]
+pub struct Versioned {
+    pub id: u32,
+    pub version: u32,
+}
+
+impl From<&TodoItem> for Versioned {
+    fn from(todo: &TodoItem) -> Self {
+        Versioned {
+            id: todo.id,
+            version: todo.version,
+        }
+    }
+}
+
+/// What a bulk change did: the todos it changed, or the ids of those it deleted, and
+/// the ids of those it left alone as they changed since the versions it was given.
+/// Todos already as asked, or already gone, are in neither.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Bulk<T> {
+    pub changed: Vec<T>,
+    pub conflicts: Vec<u32>,
+}
+
+impl<T> Default for Bulk<T> {
+    fn default() -> Self {
+        Bulk {
+            changed: vec![],
+            conflicts: vec![],
+        }
+    }
+}
+
+/// Marks where a search match starts in [`SearchResult::highlighted`].
+pub const MATCH_START: &str = "\u{2}";
+
+/// Marks where a search match ends in [`SearchResult::highlighted`].
+pub const MATCH_END: &str = "\u{3}";
+
+#[cfg_attr(feature = "ssr", derive(sqlx::FromRow))]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchResult {
+    #[cfg_attr(feature = "ssr", sqlx(flatten))]
+    pub(crate) todo: TodoItem,
+    /// The task with every matched term between [`MATCH_START`] and [`MATCH_END`].
+    pub(crate) highlighted: String,
 }
 
 cfg_if! {
     if #[cfg(feature = "ssr")] {
         use sqlx::{migrate::MigrateDatabase, Sqlite, SqlitePool};
+        use std::sync::OnceLock;
+        use tokio::sync::{watch, OnceCell};
+
+        static POOL: OnceCell<SqlitePool> = OnceCell::const_new();
+
+        /// Environment variable with the SQLite file todos are kept in, `Todos.db` in
+        /// the working directory without it.
+        pub const TODOS_DB: &str = "TODOS_DB";
+
+        pub fn database_file() -> String {
+            std::env::var(TODOS_DB).unwrap_or_else(|_| "Todos.db".into())
+        }
 
         pub async fn db() -> Result<SqlitePool, ServerFnError> {
-            let filename = "Todos.db";
-            let mut created = false;
-            if !Sqlite::database_exists(&filename).await? {
-                Sqlite::create_database(&filename).await?;
-                created = true;
+            let pool = POOL
+                .get_or_try_init(|| async {
+                    let filename = database_file();
+                    if !Sqlite::database_exists(&filename).await? {
+                        Sqlite::create_database(&filename).await?;
+                    }
+
+                    let pool = SqlitePool::connect(&filename).await?;
+                    sqlx::migrate!().run(&pool).await?;
+
+                    Ok::<_, ServerFnError>(pool)
+                })
+                .await?;
+
+            Ok(pool.clone())
+        }
+
+        /// Tells apart a todo that's gone from one at another version, for mutations
+        /// that matched no rows.
+        async fn mismatch(pool: &SqlitePool, id: u32) -> ServerFnError {
+            match sqlx::query("SELECT id FROM todos WHERE id = ?")
+                .bind(id)
+                .fetch_optional(pool)
+                .await
+            {
+                Ok(Some(_)) => ServerFnError::ServerError(CONFLICT.into()),
+                Ok(None) => ServerFnError::ServerError(NOT_FOUND.into()),
+                Err(e) => ServerFnError::ServerError(e.to_string()),
+            }
+        }
+
+        /// Trims a task, rejecting empty ones, for every way todos are added or changed.
+        pub(crate) fn check_task(task: &str) -> Result<String, ServerFnError> {
+            match task.trim() {
+                "" => Err(ServerFnError::Args("task can't be empty".into())),
+                task => Ok(task.to_string()),
             }
+        }
+
+        /// Checks a due date, with an empty one for none.
+        pub(crate) fn check_due(due: Option<&str>) -> Result<Option<String>, ServerFnError> {
+            match due.map(str::trim) {
+                None | Some("") => Ok(None),
+                Some(due) if dates::is_date(due) => Ok(Some(due.to_string())),
+                Some(due) => Err(ServerFnError::Args(format!(
+                    "due date `{due}` isn't a YYYY-MM-DD date"
+                ))),
+            }
+        }
+
+        /// The SQL assignment keeping `completed` as todos' `done` becomes `done_sql`:
+        /// today when they're done now, kept when they already were, none when not.
+        pub(crate) fn set_completed(done_sql: &str) -> String {
+            format!(
+                "completed = CASE WHEN NOT ({done_sql}) THEN NULL \
+                WHEN done THEN completed ELSE date('now') END"
+            )
+        }
+
+        /// Compiles the filter of a bulk action, see [`Query::to_sql`].
+        fn filter_sql(filter: &str) -> Result<(String, Vec<String>), ServerFnError> {
+            match Query::parse(filter) {
+                Ok(query) => Ok(query.to_sql()),
+                Err(e) => Err(ServerFnError::Args(format!("invalid filter: {e}"))),
+            }
+        }
+
+        /// Up to `limit` todos matching `filter` with ids after `after`, in id order,
+        /// for [`get_todos`] and the REST API alike.
+        pub async fn list_todos(
+            filter: &str,
+            after: Option<u32>,
+            limit: u32,
+        ) -> Result<TodoPage, ServerFnError> {
+            let (conditions, params) = filter_sql(filter)?;
+            let limit = limit.clamp(1, MAX_PAGE_SIZE);
+
+            let pool = db().await?;
+
+            // one more than asked for, to tell if there's another page
+            let sql = format!(
+                "SELECT * FROM todos WHERE id > ? AND {conditions} ORDER BY id LIMIT ?"
+            );
+            let mut todos = params
+                .into_iter()
+                .fold(
+                    sqlx::query_as::<_, TodoItem>(&sql).bind(after.unwrap_or(0)),
+                    |select, param| select.bind(param),
+                )
+                .bind(limit + 1)
+                .fetch_all(&pool)
+                .await?;
+
+            let next = if todos.len() > limit as usize {
+                todos.truncate(limit as usize);
+                todos.last().map(|todo| todo.id)
+            } else {
+                None
+            };
+
+            Ok(TodoPage { todos, next })
+        }
 
-            let pool = SqlitePool::connect(&filename).await?;
+        /// Changes whenever a todo is added, changed or deleted, for clients to tell
+        /// when to sync.
+        pub async fn todos_tag() -> Result<String, ServerFnError> {
+            let pool = db().await?;
 
-            if created {
-                sqlx::query(
-                    "CREATE TABLE IF NOT EXISTS todos (
-                        id INTEGER PRIMARY KEY AUTOINCREMENT,
-                        done BOOLEAN DEFAULT false,
-                        task TEXT NOT NULL
-                    );",
-                ).execute(&pool).await?;
+            let tag = sqlx::query_scalar::<_, String>(
+                "SELECT COUNT(*) || '-' || COALESCE(MAX(id), 0) || '-'
+                || COALESCE(SUM(version), 0) FROM todos",
+            )
+            .fetch_one(&pool)
+            .await?;
+
+            Ok(tag)
+        }
+
+        static CHANGES: OnceLock<watch::Sender<()>> = OnceLock::new();
+
+        fn changes() -> &'static watch::Sender<()> {
+            CHANGES.get_or_init(|| watch::channel(()).0)
+        }
+
+        /// Tells those waiting in [`watch_todos`] that todos were added, changed or
+        /// deleted. Everything changing todos in the server calls it.
+        pub(crate) fn todos_changed() {
+            notify(changes());
+        }
+
+        fn notify(changes: &watch::Sender<()>) {
+            changes.send_replace(());
+        }
+
+        /// Hears of every [`todos_changed`] after it's called. Changes made to the
+        /// database outside the server, like by `leptos-todo import`, aren't heard of.
+        pub(crate) fn watch_todos() -> watch::Receiver<()> {
+            changes().subscribe()
+        }
+
+        /// Changes each of `todos` still at its version in one transaction. `set` are the
+        /// assignments and `unchanged` is true of todos already as asked, both in SQL
+        /// with `?1` for `value`.
+        async fn change_many<V>(
+            todos: &[Versioned],
+            set: &str,
+            unchanged: &str,
+            value: V,
+        ) -> Result<Bulk<TodoItem>, ServerFnError>
+        where
+            V: for<'q> sqlx::Encode<'q, Sqlite> + sqlx::Type<Sqlite> + Clone + Send + 'static,
+        {
+            let pool = db().await?;
+            let mut tx = pool.begin().await?;
+
+            let update = format!(
+                "UPDATE todos SET {set}, version = version + 1
+                WHERE id = ?2 AND version = ?3 AND NOT ({unchanged}) RETURNING *"
+            );
+            let check = format!("SELECT {unchanged} FROM todos WHERE id = ?2");
+
+            let mut bulk = Bulk::default();
+            for todo in todos {
+                let changed = sqlx::query_as::<_, TodoItem>(&update)
+                    .bind(value.clone())
+                    .bind(todo.id)
+                    .bind(todo.version)
+                    .fetch_optional(&mut *tx)
+                    .await?;
+                match changed {
+                    Some(changed) => bulk.changed.push(changed),
+                    None => {
+                        let unchanged: Option<bool> = sqlx::query_scalar(&check)
+                            .bind(value.clone())
+                            .bind(todo.id)
+                            .fetch_optional(&mut *tx)
+                            .await?;
+                        if unchanged == Some(false) {
+                            bulk.conflicts.push(todo.id);
+                        }
+                    }
+                }
             }
 
-            Ok(pool)
+            tx.commit().await?;
+            todos_changed();
+            Ok(bulk)
+        }
+
+        async fn mark_matching(filter: &str, done: bool) -> Result<Vec<TodoItem>, ServerFnError> {
+            let (conditions, params) = filter_sql(filter)?;
+
+            let pool = db().await?;
+
+            let sql = format!(
+                "UPDATE todos SET done = ?, {}, version = version + 1
+                WHERE todos.done != ? AND {conditions} RETURNING *",
+                set_completed("?")
+            );
+            let update = sqlx::query_as::<_, TodoItem>(&sql).bind(done).bind(done).bind(done);
+            match params
+                .into_iter()
+                .fold(update, |update, param| update.bind(param))
+                .fetch_all(&pool)
+                .await
+            {
+                Ok(todos) => {
+                    todos_changed();
+                    Ok(todos)
+                }
+                Err(e) => Err(ServerFnError::ServerError(e.to_string())),
+            }
         }
     }
 }
 
+/// Most todos [`get_todos`] returns at once.
+pub const MAX_PAGE_SIZE: u32 = 500;
+
+/// Todos the home page loads at a time.
+const PAGE_SIZE: u32 = 100;
+
+/// A page of todos in id order.
+#[cfg_attr(feature = "ssr", derive(utoipa::ToSchema))]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TodoPage {
+    pub(crate) todos: Vec<TodoItem>,
+    /// Cursor to pass as `after` for the next page, `None` on the last page.
+    pub(crate) next: Option<u32>,
+}
+
+impl TodoPage {
+    pub fn into_parts(self) -> (Vec<TodoItem>, Option<u32>) {
+        (self.todos, self.next)
+    }
+}
+
+/// Up to `limit` todos with ids after `after`, or from the start without one.
 #[server(GetTodos, "/api")]
-pub async fn get_todos() -> Result<Vec<TodoItem>, ServerFnError> {
+pub async fn get_todos(after: Option<u32>, limit: u32) -> Result<TodoPage, ServerFnError> {
     // fake API error
     // return Err(ServerFnError::ServerError(
     //     "Testing error getting todos".into(),
     // ));
 
-    let pool = db().await?;
-
     // fake API delay
     // std::thread::sleep(std::time::Duration::from_millis(1000));
 
-    let todos = sqlx::query_as::<_, TodoItem>("SELECT * FROM todos")
+    list_todos("", after, limit).await
+}
+
+#[server(GetTodo, "/api")]
+pub async fn get_todo(id: u32) -> Result<Option<TodoItem>, ServerFnError> {
+    let pool = db().await?;
+
+    let todo = sqlx::query_as::<_, TodoItem>("SELECT * FROM todos WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&pool)
+        .await?;
+
+    Ok(todo)
+}
+
+/// Those of `ids` that still exist, in id order, for up to [`MAX_PAGE_SIZE`] ids.
+#[server(GetTodosByIds, "/api")]
+pub async fn get_todos_by_ids(ids: Vec<u32>) -> Result<Vec<TodoItem>, ServerFnError> {
+    if ids.len() > MAX_PAGE_SIZE as usize {
+        return Err(ServerFnError::Args(format!(
+            "{} ids, at most {MAX_PAGE_SIZE} at once",
+            ids.len()
+        )));
+    }
+    if ids.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let pool = db().await?;
+
+    let sql = format!(
+        "SELECT * FROM todos WHERE id IN ({}) ORDER BY id",
+        vec!["?"; ids.len()].join(", ")
+    );
+    let todos = ids
+        .iter()
+        .fold(sqlx::query_as::<_, TodoItem>(&sql), |query, id| {
+            query.bind(id)
+        })
         .fetch_all(&pool)
         .await?;
 
     Ok(todos)
 }
 
+/// Todos matching a filter in the [`Query`] syntax, best text matches first.
+#[server(SearchTodos, "/api")]
+pub async fn search_todos(query: String) -> Result<Vec<SearchResult>, ServerFnError> {
+    let query = match Query::parse(&query) {
+        Ok(query) => query,
+        Err(e) => return Err(ServerFnError::Args(e.to_string())),
+    };
+    if query.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let pool = db().await?;
+
+    let (conditions, params) = query.to_sql();
+    let results = match query.fts_match() {
+        Some(fts_match) => {
+            let sql = format!(
+                "SELECT todos.*, highlight(todos_fts, 0, ?, ?) AS highlighted
+                FROM todos_fts JOIN todos ON todos.id = todos_fts.rowid
+                WHERE todos_fts MATCH ? AND {conditions} ORDER BY rank"
+            );
+            let search = sqlx::query_as::<_, SearchResult>(&sql)
+                .bind(MATCH_START)
+                .bind(MATCH_END)
+                .bind(fts_match);
+            params
+                .into_iter()
+                .fold(search, |search, param| search.bind(param))
+                .fetch_all(&pool)
+                .await?
+        }
+        // nothing to highlight, such as `is:open`
+        None => {
+            let sql = format!(
+                "SELECT todos.*, todos.task AS highlighted FROM todos
+                WHERE {conditions} ORDER BY todos.id"
+            );
+            params
+                .into_iter()
+                .fold(sqlx::query_as::<_, SearchResult>(&sql), |search, param| {
+                    search.bind(param)
+                })
+                .fetch_all(&pool)
+                .await?
+        }
+    };
+
+    Ok(results)
+}
+
 #[server(AddTodo, "/api")]
 pub async fn add_todo(todo: String) -> Result<TodoItem, ServerFnError> {
     // fake API error
@@ -68,89 +579,305 @@ pub async fn add_todo(todo: String) -> Result<TodoItem, ServerFnError> {
     //     "Testing error adding todo: {todo}"
     // )));
 
+    let todo = check_task(&todo)?;
+
     let pool = db().await?;
 
     // fake API delay
     // std::thread::sleep(std::time::Duration::from_millis(1000));
 
     match sqlx::query_as::<_, TodoItem>(
-        "INSERT INTO todos (task, done) VALUES (?, false) RETURNING *",
+        "INSERT INTO todos (task, done, created) VALUES (?, false, date('now')) RETURNING *",
     )
     .bind(todo)
     .fetch_one(&pool)
     .await
     {
-        Ok(todo) => Ok(todo),
+        Ok(todo) => {
+            todos_changed();
+            Ok(todo)
+        }
         Err(e) => Err(ServerFnError::ServerError(e.to_string())),
     }
 }
 
 #[server(DeleteTodo, "/api")]
-pub async fn delete_todo(id: u32) -> Result<u32, ServerFnError> {
+pub async fn delete_todo(id: u32, version: u32) -> Result<u32, ServerFnError> {
     let pool = db().await?;
 
-    match sqlx::query("DELETE FROM todos WHERE id = ?")
+    match sqlx::query("DELETE FROM todos WHERE id = ? AND version = ?")
         .bind(id)
+        .bind(version)
         .execute(&pool)
         .await
     {
-        Ok(_) => Ok(id),
+        Ok(result) if result.rows_affected() > 0 => {
+            todos_changed();
+            Ok(id)
+        }
+        // deleting what's already gone is fine, so retries don't fail
+        Ok(_) => match mismatch(&pool, id).await {
+            ServerFnError::ServerError(message) if message == NOT_FOUND => Ok(id),
+            e => Err(e),
+        },
         Err(e) => Err(ServerFnError::ServerError(e.to_string())),
     }
 }
 
+/// Deletes the todos matching `filter`, all of them when it's empty, returning the
+/// ids of those deleted.
 #[server(DeleteAll, "/api")]
-pub async fn delete_all() -> Result<(), ServerFnError> {
+pub async fn delete_all(filter: String) -> Result<Vec<u32>, ServerFnError> {
+    let (conditions, params) = filter_sql(&filter)?;
+
     let pool = db().await?;
 
-    match sqlx::query("DELETE FROM todos").execute(&pool).await {
-        Ok(_) => Ok(()),
+    let sql = format!("DELETE FROM todos WHERE {conditions} RETURNING id");
+    match params
+        .into_iter()
+        .fold(sqlx::query_scalar::<_, u32>(&sql), |delete, param| {
+            delete.bind(param)
+        })
+        .fetch_all(&pool)
+        .await
+    {
+        Ok(deleted) => {
+            todos_changed();
+            Ok(deleted)
+        }
         Err(e) => Err(ServerFnError::ServerError(e.to_string())),
     }
 }
 
 #[server(ToggleTodo, "/api")]
-pub async fn toggle_todo(id: u32) -> Result<u32, ServerFnError> {
+pub async fn toggle_todo(id: u32, version: u32) -> Result<TodoItem, ServerFnError> {
     let pool = db().await?;
 
-    match sqlx::query(
-        "UPDATE todos SET done = (CASE WHEN done = false THEN true ELSE false END) WHERE id = ?",
-    )
-    .bind(id)
-    .execute(&pool)
-    .await
+    let sql = format!(
+        "UPDATE todos SET done = NOT done, {}, version = version + 1
+        WHERE id = ? AND version = ? RETURNING *",
+        set_completed("NOT done")
+    );
+    match sqlx::query_as::<_, TodoItem>(&sql)
+        .bind(id)
+        .bind(version)
+        .fetch_optional(&pool)
+        .await
     {
-        Ok(_) => Ok(id),
+        Ok(Some(todo)) => {
+            todos_changed();
+            Ok(todo)
+        }
+        Ok(None) => Err(mismatch(&pool, id).await),
         Err(e) => Err(ServerFnError::ServerError(e.to_string())),
     }
 }
 
-#[server(MarkAllDone, "/api")]
-pub async fn mark_all_done() -> Result<(), ServerFnError> {
+/// Unlike [`toggle_todo`], sending the same request twice leaves the todo as after the first.
+#[server(SetDone, "/api")]
+pub async fn set_done(id: u32, done: bool, version: u32) -> Result<TodoItem, ServerFnError> {
     let pool = db().await?;
 
-    match sqlx::query("UPDATE todos SET done = true")
-        .execute(&pool)
+    let sql = format!(
+        "UPDATE todos SET done = ?1, {}, version = version + 1
+        WHERE id = ?2 AND version = ?3 AND done != ?1 RETURNING *",
+        set_completed("?1")
+    );
+    match sqlx::query_as::<_, TodoItem>(&sql)
+        .bind(done)
+        .bind(id)
+        .bind(version)
+        .fetch_optional(&pool)
         .await
     {
-        Ok(_) => Ok(()),
+        Ok(Some(todo)) => {
+            todos_changed();
+            Ok(todo)
+        }
+        Ok(None) => match get_todo(id).await? {
+            Some(todo) if todo.done == done => Ok(todo),
+            Some(_) => Err(ServerFnError::ServerError(CONFLICT.into())),
+            None => Err(ServerFnError::ServerError(NOT_FOUND.into())),
+        },
         Err(e) => Err(ServerFnError::ServerError(e.to_string())),
     }
 }
 
-#[server(MarkAllUndone, "/api")]
-pub async fn mark_all_undone() -> Result<(), ServerFnError> {
+/// Changes a todo's task, passing when it already has that task, so retries don't fail.
+#[server(EditTodo, "/api")]
+pub async fn edit_todo(id: u32, task: String, version: u32) -> Result<TodoItem, ServerFnError> {
+    let task = check_task(&task)?;
+
     let pool = db().await?;
 
-    match sqlx::query("UPDATE todos SET done = false")
-        .execute(&pool)
-        .await
+    match sqlx::query_as::<_, TodoItem>(
+        "UPDATE todos SET task = ?, version = version + 1
+        WHERE id = ? AND version = ? AND task != ? RETURNING *",
+    )
+    .bind(&task)
+    .bind(id)
+    .bind(version)
+    .bind(&task)
+    .fetch_optional(&pool)
+    .await
+    {
+        Ok(Some(todo)) => {
+            todos_changed();
+            Ok(todo)
+        }
+        Ok(None) => match get_todo(id).await? {
+            Some(todo) if todo.task == task => Ok(todo),
+            Some(_) => Err(ServerFnError::ServerError(CONFLICT.into())),
+            None => Err(ServerFnError::ServerError(NOT_FOUND.into())),
+        },
+        Err(e) => Err(ServerFnError::ServerError(e.to_string())),
+    }
+}
+
+/// Changes a todo's priority, due date and tags, given as typed, see [`parse_tags`].
+/// Passes when it already has them, so retries don't fail.
+#[server(EditDetails, "/api")]
+pub async fn edit_details(
+    id: u32,
+    priority: Option<Priority>,
+    due: Option<String>,
+    tags: String,
+    version: u32,
+) -> Result<TodoItem, ServerFnError> {
+    let due = check_due(due.as_deref())?;
+    let tags = parse_tags(&tags);
+
+    let pool = db().await?;
+
+    match sqlx::query_as::<_, TodoItem>(
+        "UPDATE todos SET priority = ?1, due = ?2, tags = ?3, version = version + 1
+        WHERE id = ?4 AND version = ?5
+        AND (priority IS NOT ?1 OR due IS NOT ?2 OR tags != ?3) RETURNING *",
+    )
+    .bind(priority)
+    .bind(&due)
+    .bind(sqlx::types::Json(&tags))
+    .bind(id)
+    .bind(version)
+    .fetch_optional(&pool)
+    .await
     {
-        Ok(_) => Ok(()),
+        Ok(Some(todo)) => {
+            todos_changed();
+            Ok(todo)
+        }
+        Ok(None) => match get_todo(id).await? {
+            Some(todo) if (todo.priority, &todo.due, &todo.tags) == (priority, &due, &tags) => {
+                Ok(todo)
+            }
+            Some(_) => Err(ServerFnError::ServerError(CONFLICT.into())),
+            None => Err(ServerFnError::ServerError(NOT_FOUND.into())),
+        },
         Err(e) => Err(ServerFnError::ServerError(e.to_string())),
     }
 }
 
+/// Marks `todos` done or not in one transaction, see [`Bulk`].
+#[server(SetDoneMany, "/api")]
+pub async fn set_done_many(
+    todos: Vec<Versioned>,
+    done: bool,
+) -> Result<Bulk<TodoItem>, ServerFnError> {
+    let set = format!("done = ?1, {}", set_completed("?1"));
+    change_many(&todos, &set, "done = ?1", done).await
+}
+
+/// Adds tags, as typed, to `todos` in one transaction, see [`Bulk`] and [`parse_tags`].
+#[server(TagMany, "/api")]
+pub async fn tag_many(
+    todos: Vec<Versioned>,
+    tags: String,
+) -> Result<Bulk<TodoItem>, ServerFnError> {
+    let tags = parse_tags(&tags);
+    if tags.is_empty() {
+        return Err(ServerFnError::Args("no tags to add".into()));
+    }
+
+    // new tags go after the todo's own, in the order given
+    change_many(
+        &todos,
+        "tags = (SELECT json_group_array(value) FROM (
+            SELECT value FROM json_each(todos.tags)
+            UNION ALL
+            SELECT value FROM json_each(?1)
+            WHERE value NOT IN (SELECT value FROM json_each(todos.tags))
+        ))",
+        "NOT EXISTS (SELECT 1 FROM json_each(?1)
+            WHERE value NOT IN (SELECT value FROM json_each(todos.tags)))",
+        sqlx::types::Json(tags),
+    )
+    .await
+}
+
+/// Moves `todos` to a list, by name as typed, out of any with a blank one, in one
+/// transaction, see [`Bulk`] and [`list_name`].
+#[server(MoveMany, "/api")]
+pub async fn move_many(
+    todos: Vec<Versioned>,
+    list: String,
+) -> Result<Bulk<TodoItem>, ServerFnError> {
+    change_many(&todos, "list = ?1", "list IS ?1", list_name(&list)).await
+}
+
+/// Sets the priority of `todos` in one transaction, see [`Bulk`].
+#[server(SetPriorityMany, "/api")]
+pub async fn set_priority_many(
+    todos: Vec<Versioned>,
+    priority: Option<Priority>,
+) -> Result<Bulk<TodoItem>, ServerFnError> {
+    change_many(&todos, "priority = ?1", "priority IS ?1", priority).await
+}
+
+/// Deletes `todos` in one transaction, with the ids of those deleted as changed, see
+/// [`Bulk`].
+#[server(DeleteMany, "/api")]
+pub async fn delete_many(todos: Vec<Versioned>) -> Result<Bulk<u32>, ServerFnError> {
+    let pool = db().await?;
+    let mut tx = pool.begin().await?;
+
+    let mut bulk = Bulk::default();
+    for todo in todos {
+        let result = sqlx::query("DELETE FROM todos WHERE id = ? AND version = ?")
+            .bind(todo.id)
+            .bind(todo.version)
+            .execute(&mut *tx)
+            .await?;
+        if result.rows_affected() > 0 {
+            bulk.changed.push(todo.id);
+        } else if sqlx::query("SELECT id FROM todos WHERE id = ?")
+            .bind(todo.id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .is_some()
+        {
+            bulk.conflicts.push(todo.id);
+        }
+    }
+
+    tx.commit().await?;
+    todos_changed();
+    Ok(bulk)
+}
+
+/// Marks the todos matching `filter` done, all of them when it's empty, returning
+/// those changed.
+#[server(MarkAllDone, "/api")]
+pub async fn mark_all_done(filter: String) -> Result<Vec<TodoItem>, ServerFnError> {
+    mark_matching(&filter, true).await
+}
+
+/// Marks the todos matching `filter` undone, all of them when it's empty, returning
+/// those changed.
+#[server(MarkAllUndone, "/api")]
+pub async fn mark_all_undone(filter: String) -> Result<Vec<TodoItem>, ServerFnError> {
+    mark_matching(&filter, false).await
+}
+
 #[component]
 pub fn App() -> impl IntoView {
     // Provides context that manages stylesheets, titles, meta tags, etc.
@@ -171,6 +898,14 @@ pub fn App() -> impl IntoView {
         // sets the document title
         <Title text="Todo"/>
 
+        // installable as a web app, and opens offline through the service worker
+        <Link rel="manifest" href="/manifest.webmanifest"/>
+        <Link rel="icon" href="/icon.svg" type_="image/svg+xml"/>
+        <Meta name="theme-color" content="#301934"/>
+        <Script>
+            "if ('serviceWorker' in navigator) navigator.serviceWorker.register('/sw.js');"
+        </Script>
+
         // content for this welcome page
         <Router fallback=|| {
             let mut outside_errors = Errors::default();
@@ -192,32 +927,87 @@ pub fn App() -> impl IntoView {
 /// Renders the home page of your application.
 #[component]
 fn HomePage() -> impl IntoView {
-    // filter input
+    // filter input, kept in the url
     let filter = create_rw_signal(String::new());
+    let sort = create_rw_signal(Sort::default());
+    let status = create_rw_signal(Status::default());
+    sync_with_url(filter, sort, status);
 
     // list of todos
     let owner = Owner::current().expect("there should be an owner");
     let todos = create_rw_signal::<Vec<RwSignal<TodoItem>>>(vec![]);
 
-    // get todos
+    // get todos, a page at a time
+    let next_page = create_rw_signal::<Option<u32>>(None);
+    let loaded = create_rw_signal(false);
+    let page_error = create_rw_signal::<Option<ServerFnError>>(None);
+    let load_page = move |result: Option<Result<TodoPage, ServerFnError>>| {
+        let (existing_todos, next) = match result {
+            Some(Ok(page)) if !offline::has_pending() && offline::is_online() => {
+                (page.todos, page.next)
+            }
+            // the local copy also has the changes that are still to be replayed, and
+            // offline the page is the service worker's, rendered when it was cached
+            Some(Ok(page)) => (offline::load_todos(), page.next),
+            Some(Err(ServerFnError::Request(_))) => (offline::load_todos(), None),
+            Some(Err(e)) => {
+                page_error.set(Some(e));
+                return;
+            }
+            None => return,
+        };
+
+        page_error.set(None);
+        next_page.set(next);
+        merge_todos(todos, owner, existing_todos);
+        loaded.set(true);
+
+        if offline::has_pending() && offline::is_online() {
+            spawn_local(offline::replay(todos));
+        }
+    };
+    // the first page is loaded while rendering, on the server too, the rest on demand
+    let first_page = create_resource(|| (), |_| get_todos(None, PAGE_SIZE));
     let get_todos = create_server_action::<GetTodos>();
-    get_todos.dispatch(GetTodos {});
+    create_effect(move |_| load_page(get_todos.value().get()));
+
+    // keep a local copy to render from when offline, once there's a list to keep
     create_effect(move |_| {
-        if let Some(Ok(existing_todos)) = get_todos.value().get() {
-            todos.update(|todos| {
-                todos.extend(
-                    existing_todos
-                        .into_iter()
-                        // signals are owned by closest closure (this effect), which means
-                        // it's disposed when it reruns, manually set owner to parent
-                        .map(|todo| with_owner(owner, || create_rw_signal(todo))),
-                );
-            });
+        if loaded.get() {
+            offline::save_todos(
+                &todos.with(|todos| todos.iter().map(|todo| todo.get()).collect::<Vec<_>>()),
+            );
         }
     });
 
+    // replay changes made offline once the connection is back
+    let online = create_rw_signal(offline::is_online());
+    let offline_handle = window_event_listener(ev::offline, move |_| online.set(false));
+    let online_handle = window_event_listener(ev::online, move |_| {
+        online.set(true);
+        spawn_local(offline::replay(todos));
+    });
+    on_cleanup(move || {
+        offline_handle.remove();
+        online_handle.remove();
+    });
+
+    // mutations made against an outdated version, for the user to resolve
+    let conflict = create_rw_signal::<Option<Conflict>>(None);
+
     // add
-    let add_todo = create_server_action::<AddTodo>();
+    let add_todo = create_action(move |input: &AddTodo| {
+        let task = input.todo.clone();
+        offline::call(todos, add_todo(task.clone()), move || {
+            let id = offline::next_local_id();
+            let todo = TodoItem {
+                id,
+                task: task.clone(),
+                ..TodoItem::default()
+            };
+            (offline::Mutation::Add { id, task }, todo)
+        })
+    });
     create_effect(move |_| {
         if let Some(Ok(todo)) = add_todo.value().get() {
             // signals are owned by closest closure (this effect), which means
@@ -226,79 +1016,512 @@ fn HomePage() -> impl IntoView {
         };
     });
 
-    // toggle
-    let toggle_todo = create_server_action::<ToggleTodo>();
-    create_effect(move |_| {
-        if let Some(Ok(id)) = toggle_todo.value().get() {
-            todos.with_untracked(|todos| {
-                for todo in todos.iter() {
-                    if todo.with_untracked(|todo| todo.id == id) {
-                        todo.update(|todo| todo.done = !todo.done);
-                        break;
-                    }
+    // done/undone
+    let set_done = create_optimistic_action(
+        move |input: &SetDone| {
+            let SetDone { id, done, version } = input.clone();
+            offline::call(todos, set_done(id, done, version), move || {
+                // already changed locally, the server's version follows once replayed
+                let todo = find_todo(todos, id)
+                    .map(|todo| todo.get_untracked())
+                    .unwrap_or_else(|| TodoItem {
+                        id,
+                        done,
+                        version,
+                        ..TodoItem::default()
+                    });
+                (offline::Mutation::SetDone { id, done, version }, todo)
+            })
+        },
+        move |input| set_todo_done(todos, input.id, input.done),
+        move |input, _, result| match result {
+            Ok(todo) => replace_todo(todos, todo.clone()),
+            Err(e) => {
+                set_todo_done(todos, input.id, !input.done);
+                if is_conflict(e) {
+                    conflict.set(Some(Conflict::SetDone {
+                        id: input.id,
+                        done: input.done,
+                    }));
                 }
-            });
-        };
-    });
+            }
+        },
+    );
 
-    // delete
-    let delete_todo = create_server_action::<DeleteTodo>();
-    create_effect(move |_| {
-        if let Some(Ok(id)) = delete_todo.value().get() {
-            todos.update(|todos| {
-                if let Some(index) = todos
-                    .iter()
-                    .position(|todo| todo.with_untracked(|todo| todo.id == id))
-                {
-                    // signal created using with_owner, must be manually disposed
-                    todos[index].dispose();
-                    todos.remove(index);
+    // edit
+    // the previous task is kept until the server confirms, so it can be restored
+    let edit_todo = create_optimistic_action(
+        move |input: &EditTodo| {
+            let EditTodo { id, task, version } = input.clone();
+            offline::call(todos, edit_todo(id, task.clone(), version), move || {
+                // already changed locally, the server's version follows once replayed
+                let todo = find_todo(todos, id)
+                    .map(|todo| todo.get_untracked())
+                    .unwrap_or_else(|| TodoItem {
+                        id,
+                        task: task.clone(),
+                        version,
+                        ..TodoItem::default()
+                    });
+                (offline::Mutation::Edit { id, task, version }, todo)
+            })
+        },
+        move |input| {
+            let todo = find_todo(todos, input.id)?;
+            let previous = todo.with_untracked(|todo| todo.task.clone());
+            todo.update(|todo| todo.task = input.task.clone());
+            Some(previous)
+        },
+        move |input, previous, result| match result {
+            Ok(todo) => replace_todo(todos, todo.clone()),
+            Err(e) => {
+                if let (Some(todo), Some(previous)) = (find_todo(todos, input.id), previous) {
+                    todo.update(|todo| todo.task = previous);
                 }
-            });
-        }
-    });
+                if is_conflict(e) {
+                    conflict.set(Some(Conflict::Edit {
+                        id: input.id,
+                        task: input.task.clone(),
+                    }));
+                }
+            }
+        },
+    );
 
-    // all done
-    let mark_all_done = create_server_action::<MarkAllDone>();
-    create_effect(move |_| {
-        if let Some(Ok(())) = mark_all_done.value().get() {
-            todos.with_untracked(|todos| {
-                todos
-                    .iter()
-                    .for_each(|todo| todo.update(|todo| todo.done = true))
+    // priority, due date and tags
+    // the previous ones are kept until the server confirms, so they can be restored
+    let edit_details = create_optimistic_action(
+        move |input: &EditDetails| {
+            let input = input.clone();
+            let EditDetails { id, version, .. } = input;
+            offline::call(
+                todos,
+                edit_details(
+                    id,
+                    input.priority,
+                    input.due.clone(),
+                    input.tags.clone(),
+                    version,
+                ),
+                move || {
+                    // already changed locally, the server's version follows once replayed
+                    let todo = find_todo(todos, id)
+                        .map(|todo| todo.get_untracked())
+                        .unwrap_or_else(|| TodoItem {
+                            id,
+                            version,
+                            ..TodoItem::default()
+                        });
+                    let mutation = offline::Mutation::Details {
+                        id,
+                        priority: input.priority,
+                        due: input.due,
+                        tags: input.tags,
+                        version,
+                    };
+                    (mutation, todo)
+                },
+            )
+        },
+        move |input| {
+            let todo = find_todo(todos, input.id)?;
+            let previous =
+                todo.with_untracked(|todo| (todo.priority, todo.due.clone(), todo.tags.clone()));
+            todo.update(|todo| {
+                todo.priority = input.priority;
+                todo.due = input.due.clone().filter(|due| !due.is_empty());
+                todo.tags = parse_tags(&input.tags);
             });
-        };
-    });
+            Some(previous)
+        },
+        move |input, previous, result| match result {
+            Ok(todo) => replace_todo(todos, todo.clone()),
+            Err(e) => {
+                if let (Some(todo), Some((priority, due, tags))) =
+                    (find_todo(todos, input.id), previous)
+                {
+                    todo.update(|todo| {
+                        todo.priority = priority;
+                        todo.due = due;
+                        todo.tags = tags;
+                    });
+                }
+                if is_conflict(e) {
+                    conflict.set(Some(Conflict::Details {
+                        id: input.id,
+                        priority: input.priority,
+                        due: input.due.clone(),
+                        tags: input.tags.clone(),
+                    }));
+                }
+            }
+        },
+    );
+
+    // delete
+    // the removed todo is kept aside until the server confirms, so it can be put back
+    let delete_todo = create_optimistic_action(
+        move |input: &DeleteTodo| {
+            let DeleteTodo { id, version } = input.clone();
+            offline::call(todos, delete_todo(id, version), move || {
+                (offline::Mutation::Delete { id, version }, id)
+            })
+        },
+        move |input| {
+            todos
+                .try_update(|todos| {
+                    let index = todos
+                        .iter()
+                        .position(|todo| todo.with_untracked(|todo| todo.id == input.id))?;
+                    Some((index, todos.remove(index)))
+                })
+                .flatten()
+        },
+        move |input, removed, result| {
+            let Some((index, todo)) = removed else {
+                return;
+            };
+
+            match result {
+                // signal created using with_owner, must be manually disposed
+                Ok(_) => todo.dispose(),
+                Err(e) => {
+                    todos.update(|todos| todos.insert(index.min(todos.len()), todo));
+                    if is_conflict(e) {
+                        conflict.set(Some(Conflict::Delete { id: input.id }));
+                    }
+                }
+            }
+        },
+    );
+
+    // filtered, all or selected done/undone
+    // previous states are kept until the server confirms, so they can be restored
+    let mark = move |include: &dyn Fn(&TodoItem) -> bool, done: bool| {
+        let previous: Vec<_> = todos.with_untracked(|todos| {
+            todos
+                .iter()
+                .filter(|todo| todo.with_untracked(include))
+                .map(|todo| (*todo, todo.with_untracked(|todo| todo.done)))
+                .collect()
+        });
+        previous
+            .iter()
+            .for_each(|(todo, _)| todo.update(|todo| todo.done = done));
+        previous
+    };
+    let unmark = move |previous: Vec<(RwSignal<TodoItem>, bool)>,
+                       result: Result<&Vec<TodoItem>, &ServerFnError>| {
+        let changed = result.map_or(&[][..], |changed| changed.as_slice());
+        let changed_ids: HashSet<_> = changed.iter().map(|todo| todo.id).collect();
+        // the server's filter can match differently than the one evaluated here
+        previous
+            .into_iter()
+            .filter(|(todo, _)| !changed_ids.contains(&todo.with_untracked(|todo| todo.id)))
+            .for_each(|(todo, done)| todo.update(|todo| todo.done = done));
+        // changed todos come back with their new versions
+        replace_todos(todos, changed);
+    };
+
+    // how many todos the last bulk action changed
+    let report = create_rw_signal::<Option<String>>(None);
+
+    let mark_all_done = create_optimistic_action(
+        |input: &MarkAllDone| mark_all_done(input.filter.clone()),
+        move |input| mark(&filter_matches(&input.filter), true),
+        move |_, previous, result| {
+            unmark(previous, result);
+            if let Ok(changed) = result {
+                report.set(Some(format!("Marked {} done", todo_count(changed.len()))));
+            }
+        },
+    );
+
+    let mark_all_undone = create_optimistic_action(
+        |input: &MarkAllUndone| mark_all_undone(input.filter.clone()),
+        move |input| mark(&filter_matches(&input.filter), false),
+        move |_, previous, result| {
+            unmark(previous, result);
+            if let Ok(changed) = result {
+                report.set(Some(format!("Marked {} undone", todo_count(changed.len()))));
+            }
+        },
+    );
+
+    // selected done/undone, tag, move and priority
+    // like above, but the todos are restored whole, and those in conflict reloaded
+    let change = move |selected: &[Versioned], apply: &dyn Fn(&mut TodoItem)| {
+        let by_id = todos.with_untracked(|todos| todos_by_id(todos));
+        let previous: Vec<_> = selected
+            .iter()
+            .filter_map(|selected| by_id.get(&selected.id).copied())
+            .map(|todo| (todo, todo.get_untracked()))
+            .collect();
+        previous.iter().for_each(|(todo, _)| todo.update(apply));
+        previous
+    };
+    let unchange = move |previous: Vec<(RwSignal<TodoItem>, TodoItem)>,
+                         result: Result<&Bulk<TodoItem>, &ServerFnError>,
+                         done: fn(String) -> String| {
+        let changed = result.map_or(&[][..], |bulk| bulk.changed.as_slice());
+        let changed_ids: HashSet<_> = changed.iter().map(|todo| todo.id).collect();
+        previous
+            .into_iter()
+            .filter(|(_, before)| !changed_ids.contains(&before.id))
+            .for_each(|(todo, before)| todo.set(before));
+        replace_todos(todos, changed);
+        if let Ok(bulk) = result {
+            reload_todos(todos, &bulk.conflicts);
+            report.set(Some(bulk_report(done, bulk)));
+        }
+    };
+
+    let set_done_many = create_optimistic_action(
+        |input: &SetDoneMany| set_done_many(input.todos.clone(), input.done),
+        move |input| change(&input.todos, &|todo| todo.done = input.done),
+        move |input, previous, result| {
+            if input.done {
+                unchange(previous, result, |todos| format!("Marked {todos} done"))
+            } else {
+                unchange(previous, result, |todos| format!("Marked {todos} undone"))
+            }
+        },
+    );
+
+    let tag_many = create_optimistic_action(
+        |input: &TagMany| tag_many(input.todos.clone(), input.tags.clone()),
+        move |input| {
+            let tags = parse_tags(&input.tags);
+            change(&input.todos, &|todo| {
+                let new: Vec<_> = tags.iter().filter(|tag| !todo.tags.contains(tag)).collect();
+                todo.tags.extend(new.into_iter().cloned());
+            })
+        },
+        move |_, previous, result| unchange(previous, result, |todos| format!("Tagged {todos}")),
+    );
+
+    let move_many = create_optimistic_action(
+        |input: &MoveMany| move_many(input.todos.clone(), input.list.clone()),
+        move |input| {
+            let list = list_name(&input.list);
+            change(&input.todos, &|todo| todo.list = list.clone())
+        },
+        move |_, previous, result| unchange(previous, result, |todos| format!("Moved {todos}")),
+    );
+
+    let set_priority_many = create_optimistic_action(
+        |input: &SetPriorityMany| set_priority_many(input.todos.clone(), input.priority),
+        move |input| change(&input.todos, &|todo| todo.priority = input.priority),
+        move |_, previous, result| {
+            unchange(previous, result, |todos| {
+                format!("Changed the priority of {todos}")
+            })
+        },
+    );
+
+    // filtered, all or selected delete
+    // removed todos are kept with their indexes until the server confirms
+    let remove = move |include: &dyn Fn(&TodoItem) -> bool| {
+        let mut taken = vec![];
+        todos.update(|todos| {
+            let mut index = 0;
+            todos.retain(|todo| {
+                index += 1;
+                let keep = !todo.with_untracked(include);
+                if !keep {
+                    taken.push((index - 1, *todo));
+                }
+                keep
+            });
+        });
+        taken
+    };
+    let unremove = move |taken: Vec<(usize, RwSignal<TodoItem>)>,
+                         result: Result<&Vec<u32>, &ServerFnError>| {
+        let deleted = result.map_or(&[][..], |deleted| deleted.as_slice());
+        let deleted_ids: HashSet<_> = deleted.iter().copied().collect();
+        // back where they were, in order so earlier indexes are already in place
+        todos.update(|todos| {
+            for (index, todo) in taken {
+                if deleted_ids.contains(&todo.with_untracked(|todo| todo.id)) {
+                    // signal created using with_owner, must be manually disposed
+                    todo.dispose();
+                } else {
+                    todos.insert(index.min(todos.len()), todo);
+                }
+            }
+        });
+        // the server's filter can match differently than the one evaluated here
+        remove_todos(todos, &deleted_ids);
+        if result.is_ok() {
+            report.set(Some(format!("Deleted {}", todo_count(deleted.len()))));
+        }
+    };
+
+    let delete_all = create_optimistic_action(
+        |input: &DeleteAll| delete_all(input.filter.clone()),
+        move |input| remove(&filter_matches(&input.filter)),
+        move |_, taken, result| unremove(taken, result),
+    );
+
+    let delete_many = create_optimistic_action(
+        |input: &DeleteMany| delete_many(input.todos.clone()),
+        move |input| {
+            let selected: HashSet<_> = input.todos.iter().map(|selected| selected.id).collect();
+            remove(&|todo| selected.contains(&todo.id))
+        },
+        move |_, taken, result| {
+            unremove(taken, result.map(|bulk| &bulk.changed));
+            if let Ok(bulk) = result {
+                reload_todos(todos, &bulk.conflicts);
+                report.set(Some(bulk_report(|todos| format!("Deleted {todos}"), bulk)));
+            }
+        },
+    );
 
-    // all undone
-    let mark_all_undone = create_server_action::<MarkAllUndone>();
+    // imports change todos that may not be loaded, so load the list again
+    let import_todos = create_server_action::<ImportTodos>();
     create_effect(move |_| {
-        if let Some(Ok(())) = mark_all_undone.value().get() {
-            todos.with_untracked(|todos| {
-                todos
-                    .iter()
-                    .for_each(|todo| todo.update(|todo| todo.done = false))
+        if let Some(Ok(summary)) = import_todos.value().get() {
+            if !summary.dry_run {
+                todos.update(|todos| {
+                    // signal created using with_owner, must be manually disposed
+                    todos.drain(..).for_each(|todo| todo.dispose());
+                });
+                get_todos.dispatch(GetTodos {
+                    after: None,
+                    limit: PAGE_SIZE,
+                });
+            }
+        }
+    });
+
+    // search, filter is only set once typing pauses
+    let query = create_memo(move |_| Query::parse(&filter.get()));
+    // matches of the filter in the url are loaded while rendering too, so the list
+    // is filtered on first paint
+    let first_search = create_resource(
+        || (),
+        move |_| {
+            let filter = filter.get_untracked();
+            let searched = Query::parse(&filter).is_ok_and(|query| !query.is_empty());
+            async move {
+                if searched {
+                    Some(search_todos(filter).await)
+                } else {
+                    None
+                }
+            }
+        },
+    );
+
+    // what bulk actions apply to, the filter with the status tab as `is:`, none if invalid
+    let scope = create_memo(move |_| {
+        query.with(Result::is_ok).then(|| {
+            let filter = filter.get();
+            match status.get().clause() {
+                Some(clause) if filter.trim().is_empty() => clause.to_string(),
+                Some(clause) => format!("{} {clause}", filter.trim()),
+                None => filter,
+            }
+        })
+    });
+    // only the latest search's results are kept, earlier ones can be answered after it
+    let latest_search = store_value(0_usize);
+    let search_results = create_rw_signal::<Option<Result<Vec<SearchResult>, ServerFnError>>>(None);
+    let search_todos = create_action(move |input: &SearchTodos| {
+        let search = latest_search
+            .try_update_value(|latest| {
+                *latest += 1;
+                *latest
+            })
+            .unwrap_or_default();
+        let request = search_todos(input.query.clone());
+        async move {
+            let result = request.await;
+            if latest_search.get_value() == search {
+                search_results.set(Some(result.clone()));
+            }
+            result
+        }
+    });
+    create_effect(move |searched: Option<()>| {
+        query.track();
+        // saved changes can change what matches, so search again after each
+        for version in [
+            add_todo.version(),
+            set_done.version(),
+            edit_todo.version(),
+            edit_details.version(),
+            mark_all_done.version(),
+            mark_all_undone.version(),
+            set_done_many.version(),
+            tag_many.version(),
+            move_many.version(),
+            set_priority_many.version(),
+            import_todos.version(),
+        ] {
+            version.track();
+        }
+
+        // the filter in the url was searched while rendering
+        let first = searched.is_none()
+            && untrack(|| first_search.with(|first| matches!(first, Some(Some(_)))));
+        if !first && query.with_untracked(|query| matches!(query, Ok(query) if !query.is_empty())) {
+            search_todos.dispatch(SearchTodos {
+                query: filter.get_untracked(),
             });
-        };
+        }
     });
 
-    // all delete
-    let delete_all = create_server_action::<DeleteAll>();
+    // matches can be on pages not loaded yet
     create_effect(move |_| {
-        if let Some(Ok(())) = delete_all.value().get() {
-            todos.update(|todos| {
-                // signal created using with_owner, must be manually disposed
-                todos.iter().for_each(|todo| todo.dispose());
-                todos.clear();
-            });
+        if let Some(Ok(results)) = search_results.get() {
+            merge_todos(
+                todos,
+                owner,
+                results.into_iter().map(|result| result.todo).collect(),
+            );
+        }
+    });
+
+    // ids of the todos matching the filter, best first, with their highlighted task
+    let matches = create_memo(move |_| {
+        let query = match query.get() {
+            Ok(query) if !query.is_empty() => query,
+            // errors are shown by the topbar
+            _ => return None,
         };
+
+        search_results.with(|value| match value {
+            Some(Ok(results)) => Some(
+                results
+                    .iter()
+                    .map(|result| (result.todo.id, result.highlighted.clone()))
+                    .collect::<Vec<_>>(),
+            ),
+            // not answered yet or server unreachable, match the loaded todos instead
+            None | Some(Err(ServerFnError::Request(_))) => Some(todos.with(|todos| {
+                todos
+                    .iter()
+                    .filter_map(|todo| {
+                        todo.with(|todo| query.matches(todo).then(|| (todo.id, todo.task.clone())))
+                    })
+                    .collect()
+            })),
+            _ => None,
+        })
     });
 
+    // keyboard shortcuts, keys can be rebound from the cheat sheet
+    let keymap = create_rw_signal(Keymap::load());
+
     view! {
-        <Topbar filter/>
+        <Topbar filter query sort status import_todos/>
+        <Shortcuts keymap/>
 
         <div class="container mb-3">
-            <AllTodosAction mark_all_done mark_all_undone delete_all/>
+            <AllTodosAction scope mark_all_done mark_all_undone delete_all/>
         </div>
 
         <div class="container mb-3">
@@ -306,14 +1529,331 @@ fn HomePage() -> impl IntoView {
         </div>
 
         <div class="container mb-3">
-            <ShowMessages todos get_todos add_todo/>
-            <Todolist todos delete_todo toggle_todo filter add_todo/>
+            <StatusTabs status/>
+            <ConflictPrompt conflict todos set_done edit_todo edit_details delete_todo/>
+            <ShowMessages todos online get_todos page_error add_todo set_done edit_todo
+                edit_details delete_todo mark_all_done mark_all_undone delete_all set_done_many
+                tag_many move_many set_priority_many delete_many search_todos report/>
+            <Transition fallback=move || view! {
+                <div class="spinner-border spinner-border-sm" role="status"></div>
+            }>
+                {move || {
+                    // once, rendering on the server and again when hydrating
+                    if let Some(page) = first_page.get() {
+                        if !loaded.get_untracked() {
+                            load_page(Some(page));
+                        }
+                    }
+                    if let Some(Some(result)) = first_search.get() {
+                        if search_results.with_untracked(Option::is_none) {
+                            if let Ok(results) = &result {
+                                let matched = results.iter().map(|result| result.todo.clone());
+                                merge_todos(todos, owner, matched.collect());
+                            }
+                            search_results.set(Some(result));
+                        }
+                    }
+                    view! {
+                        <Todolist todos delete_todo set_done edit_todo edit_details set_done_many
+                            tag_many move_many set_priority_many delete_many matches sort status
+                            add_todo keymap/>
+                    }
+                }}
+            </Transition>
+            <LoadMore get_todos next_page matches/>
         </div>
     }
 }
 
+/// Keeps the filter, sort and status in the query string, `?q=..&sort=..&status=..`,
+/// so views can be shared and reloaded, and back and forward restore earlier ones.
+/// Read before rendering, so they also apply to the server-rendered page.
+fn sync_with_url(filter: RwSignal<String>, sort: RwSignal<Sort>, status: RwSignal<Status>) {
+    let query_map = use_query_map();
+    let location = use_location();
+
+    let from_url = move |params: &ParamsMap| {
+        let param = |key: &str| params.get(key).cloned().unwrap_or_default();
+        (
+            param("q"),
+            param("sort").parse::<Sort>().unwrap_or_default(),
+            param("status").parse::<Status>().unwrap_or_default(),
+        )
+    };
+    let (url_filter, url_sort, url_status) = query_map.with_untracked(from_url);
+    filter.set(url_filter);
+    sort.set(url_sort);
+    status.set(url_status);
+
+    // back and forward
+    create_effect(move |_| {
+        let (url_filter, url_sort, url_status) = query_map.with(from_url);
+        if filter.get_untracked() != url_filter {
+            filter.set(url_filter);
+        }
+        if sort.get_untracked() != url_sort {
+            sort.set(url_sort);
+        }
+        if status.get_untracked() != url_status {
+            status.set(url_status);
+        }
+    });
+
+    let navigate = use_navigate();
+    create_effect(move |_| {
+        // defaults are left out, so the plain page has a plain url
+        let mut params = ParamsMap::new();
+        if !filter.with(String::is_empty) {
+            params.insert("q".into(), filter.get());
+        }
+        if sort.get() != Sort::default() {
+            params.insert("sort".into(), sort.get().as_str().into());
+        }
+        if status.get() != Status::default() {
+            params.insert("status".into(), status.get().as_str().into());
+        }
+
+        let (current, wanted) =
+            query_map.with_untracked(|current| (from_url(current), from_url(&params)));
+        if current != wanted {
+            // typing replaces the entry, so back leaves the filter rather than undoing a word
+            let typed = (current.1, current.2) == (wanted.1, wanted.2);
+            let path = location.pathname.get_untracked();
+            navigate(
+                &format!("{path}{}", params.to_query_string()),
+                NavigateOptions {
+                    replace: typed,
+                    scroll: false,
+                    ..Default::default()
+                },
+            );
+        }
+    });
+}
+
+/// An action that runs `apply` as soon as it's dispatched, without waiting for the
+/// server, and `settle` with the server's answer. Each dispatch settles with its own
+/// input and with what its `apply` returned, like the state to roll back to, even when
+/// dispatches overlap or their answers arrive out of order.
+fn create_optimistic_action<I, O, R, F>(
+    call: impl Fn(&I) -> F + 'static,
+    apply: impl Fn(&I) -> R + 'static,
+    settle: impl Fn(&I, R, Result<&O, &ServerFnError>) + 'static,
+) -> Action<I, Result<O, ServerFnError>>
+where
+    I: Clone + 'static,
+    O: 'static,
+    R: 'static,
+    F: std::future::Future<Output = Result<O, ServerFnError>> + 'static,
+{
+    let settle = std::rc::Rc::new(settle);
+    create_action(move |input: &I| {
+        let applied = apply(input);
+        let request = call(input);
+        let (input, settle) = (input.clone(), settle.clone());
+        async move {
+            let result = request.await;
+            settle(&input, applied, result.as_ref());
+            result
+        }
+    })
+}
+
+/// Dispatches `action` with the form's fields, for optimistic actions, which an
+/// ActionForm would post without running.
+fn dispatch_on_submit<I, O>(
+    action: Action<I, Result<O, ServerFnError>>,
+) -> impl Fn(ev::SubmitEvent) + 'static
+where
+    I: FromFormData + 'static,
+    O: 'static,
+{
+    move |ev| {
+        ev.prevent_default();
+        match I::from_event(&ev) {
+            Ok(input) => action.dispatch(input),
+            Err(e) => log::error!("{e}"),
+        }
+    }
+}
+
+/// Adds the todos not in the list yet, in id order, like a page loaded after todos
+/// were added to the end of the list.
+pub(crate) fn merge_todos(
+    todos: RwSignal<Vec<RwSignal<TodoItem>>>,
+    owner: Owner,
+    new_todos: Vec<TodoItem>,
+) {
+    todos.update(|todos| {
+        let mut ids: HashSet<_> = todos
+            .iter()
+            .map(|todo| todo.with_untracked(|todo| todo.id))
+            .collect();
+        for new_todo in new_todos {
+            if !ids.insert(new_todo.id) {
+                continue;
+            }
+
+            let index =
+                todos.partition_point(|todo| todo.with_untracked(|todo| todo.id < new_todo.id));
+            // signals are owned by closest closure (the calling effect), which means
+            // it's disposed when it reruns, manually set owner to parent
+            todos.insert(index, with_owner(owner, || create_rw_signal(new_todo)));
+        }
+    });
+}
+
+/// The todos by id, to look up many at once.
+fn todos_by_id(todos: &[RwSignal<TodoItem>]) -> HashMap<u32, RwSignal<TodoItem>> {
+    todos
+        .iter()
+        .map(|todo| (todo.with_untracked(|todo| todo.id), *todo))
+        .collect()
+}
+
+fn find_todo(todos: RwSignal<Vec<RwSignal<TodoItem>>>, id: u32) -> Option<RwSignal<TodoItem>> {
+    todos.with_untracked(|todos| {
+        todos
+            .iter()
+            .find(|todo| todo.with_untracked(|todo| todo.id == id))
+            .copied()
+    })
+}
+
+fn set_todo_done(todos: RwSignal<Vec<RwSignal<TodoItem>>>, id: u32, done: bool) {
+    if let Some(todo) = find_todo(todos, id) {
+        todo.update(|todo| todo.done = done);
+    }
+}
+
+fn replace_todo(todos: RwSignal<Vec<RwSignal<TodoItem>>>, new_todo: TodoItem) {
+    if let Some(todo) = find_todo(todos, new_todo.id) {
+        todo.set(new_todo);
+    }
+}
+
+/// Like [`replace_todo`], for many todos.
+pub(crate) fn replace_todos(todos: RwSignal<Vec<RwSignal<TodoItem>>>, new_todos: &[TodoItem]) {
+    let by_id = todos.with_untracked(|todos| todos_by_id(todos));
+    for new_todo in new_todos {
+        if let Some(todo) = by_id.get(&new_todo.id) {
+            todo.set(new_todo.clone());
+        }
+    }
+}
+
+/// The filter of a bulk action evaluated on loaded todos, matching none if invalid.
+fn filter_matches(filter: &str) -> impl Fn(&TodoItem) -> bool {
+    let query = Query::parse(filter).ok();
+    move |todo| query.as_ref().is_some_and(|query| query.matches(todo))
+}
+
+/// Tells how many todos a bulk change changed, with `done` saying what it did to them,
+/// and how many it left alone.
+fn bulk_report<T>(done: fn(String) -> String, bulk: &Bulk<T>) -> String {
+    let report = done(todo_count(bulk.changed.len()));
+    match bulk.conflicts.len() {
+        0 => report,
+        1 => format!("{report}, 1 was changed by someone else and left as it was"),
+        conflicts => {
+            format!("{report}, {conflicts} were changed by someone else and left as they were")
+        }
+    }
+}
+
+/// Loads the todos with `ids` again, after a bulk change found them at other versions.
+fn reload_todos(todos: RwSignal<Vec<RwSignal<TodoItem>>>, ids: &[u32]) {
+    for id in ids.iter().copied() {
+        spawn_local(async move {
+            match get_todo(id).await {
+                Ok(Some(todo)) => replace_todo(todos, todo),
+                Ok(None) => remove_todo(todos, id),
+                Err(e) => log::error!("couldn't reload todo {id}: {e}"),
+            }
+        });
+    }
+}
+
+pub(crate) fn todo_count(count: usize) -> String {
+    match count {
+        1 => "1 todo".into(),
+        count => format!("{count} todos"),
+    }
+}
+
+fn remove_todo(todos: RwSignal<Vec<RwSignal<TodoItem>>>, id: u32) {
+    todos.update(|todos| {
+        if let Some(index) = todos
+            .iter()
+            .position(|todo| todo.with_untracked(|todo| todo.id == id))
+        {
+            // signal created using with_owner, must be manually disposed
+            todos.remove(index).dispose();
+        }
+    });
+}
+
+/// Like [`remove_todo`], for many todos.
+pub(crate) fn remove_todos(todos: RwSignal<Vec<RwSignal<TodoItem>>>, ids: &HashSet<u32>) {
+    todos.update(|todos| {
+        todos.retain(|todo| {
+            let keep = !ids.contains(&todo.with_untracked(|todo| todo.id));
+            if !keep {
+                // signal created using with_owner, must be manually disposed
+                todo.dispose();
+            }
+            keep
+        });
+    });
+}
+
+/// A mutation rejected because the todo changed since it was loaded, which the user
+/// can apply again on top of the current version.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Conflict {
+    SetDone {
+        id: u32,
+        done: bool,
+    },
+    Edit {
+        id: u32,
+        task: String,
+    },
+    Details {
+        id: u32,
+        priority: Option<Priority>,
+        due: Option<String>,
+        tags: String,
+    },
+    Delete {
+        id: u32,
+    },
+}
+
+impl Conflict {
+    fn id(&self) -> u32 {
+        match self {
+            Conflict::SetDone { id, .. }
+            | Conflict::Edit { id, .. }
+            | Conflict::Details { id, .. }
+            | Conflict::Delete { id } => *id,
+        }
+    }
+}
+
 #[component]
-fn Topbar(filter: RwSignal<String>) -> impl IntoView {
+fn Topbar(
+    filter: RwSignal<String>,
+    query: Memo<Result<Query, ParseError>>,
+    sort: RwSignal<Sort>,
+    status: RwSignal<Status>,
+    import_todos: Action<ImportTodos, Result<ImportSummary, leptos::ServerFnError>>,
+) -> impl IntoView {
+    let mut set_filter = leptos_dom::helpers::debounce(
+        std::time::Duration::from_millis(250),
+        move |value: String| filter.set(value),
+    );
+
     view! {
         <nav class="navbar navbar-expand-md bg-main mb-3">
             <div class="container-fluid">
@@ -329,19 +1869,53 @@ fn Topbar(filter: RwSignal<String>) -> impl IntoView {
 
                 <div class="collapse navbar-collapse" id="navbarSupportedContent">
                     <ul class="navbar-nav me-auto mb-2 mb-lg-0">
+                        <SavedSearches filter sort status/>
+                        <Backups import_todos/>
                     </ul>
 
                     <div class="d-flex" role="search">
-                        <div class="input-group flex-nowrap">
+                        <select class="form-select me-2" aria-label="Sort"
+                            prop:value=move || sort.get().as_str()
+                            on:change=move |ev| {
+                                if let Ok(value) = event_target_value(&ev).parse() {
+                                    sort.set(value);
+                                }
+                            }>
+                            {Sort::ALL.map(|option| view! {
+                                <option value=option.as_str()
+                                    selected=move || sort.get() == option>
+                                    {option.label()}
+                                </option>
+                            }).collect_view()}
+                        </select>
+
+                        <div class="input-group flex-nowrap has-validation">
                             <span class="input-group-text" id="addon-wrapping">
                                <i class="bi bi-search"></i>
                             </span>
 
-                            <input class="form-control me-2" type="search"
-                                placeholder="Filter"
-                                prop:value=""
-                                on:input=move |ev| filter.set(event_target_value(&ev))
+                            <input class="form-control me-2" type="search" id="filter"
+                                placeholder="Filter, e.g. is:open milk"
+                                value=filter.get_untracked()
+                                prop:value=filter
+                                class:is-invalid=move || query.with(Result::is_err)
+                                on:input=move |ev| set_filter(event_target_value(&ev))
                             />
+
+                            {move || query.with(|query| query.as_ref().err().map(|e| {
+                                // point out where in the filter it went wrong
+                                let filter = filter.get();
+                                let start = e.start.min(filter.len());
+                                let end = e.end.min(filter.len());
+                                view! {
+                                    <div class="invalid-tooltip">
+                                        {e.message.clone()}": "
+                                        <code>{filter[..start].to_string()}
+                                        <mark class="px-0">{filter[start..end].to_string()}</mark>
+                                        {filter[end..].to_string()}</code>
+                                    </div>
+                                }
+                            }))}
                         </div>
                     </div>
                 </div>
@@ -353,10 +1927,19 @@ fn Topbar(filter: RwSignal<String>) -> impl IntoView {
 #[component]
 fn Todoadd(
     add_todo: Action<AddTodo, Result<TodoItem, leptos::ServerFnError>>,
-    get_todos: Action<GetTodos, Result<Vec<TodoItem>, leptos::ServerFnError>>,
+    get_todos: Action<GetTodos, Result<TodoPage, leptos::ServerFnError>>,
 ) -> impl IntoView {
+    // dispatched rather than posted by an ActionForm, so it can be queued while offline
+    let on_submit = move |ev: ev::SubmitEvent| {
+        ev.prevent_default();
+        match AddTodo::from_event(&ev) {
+            Ok(input) => add_todo.dispatch(input),
+            Err(e) => log::error!("{e}"),
+        }
+    };
+
     view! {
-        <ActionForm action=add_todo>
+        <form on:submit=on_submit>
             <div class="input-group">
                 <div class="form-floating" class:placeholder-glow=move || add_todo.pending().get()>
                     <input type="text" name="todo" id="floatingTodo" class="form-control"
@@ -379,46 +1962,80 @@ fn Todoadd(
                     <div hidden=move || !add_todo.pending().get() class="spinner-border spinner-border-sm" role="status"></div>
                 </button>
             </div>
-        </ActionForm>
+        </form>
     }
 }
 
+/// Marks or deletes every todo matching the filter and status tab, including ones
+/// not loaded yet, or every todo when neither is set.
 #[component]
 fn AllTodosAction(
-    mark_all_done: Action<MarkAllDone, Result<(), leptos::ServerFnError>>,
-    mark_all_undone: Action<MarkAllUndone, Result<(), leptos::ServerFnError>>,
-    delete_all: Action<DeleteAll, Result<(), leptos::ServerFnError>>,
+    scope: Memo<Option<String>>,
+    mark_all_done: Action<MarkAllDone, Result<Vec<TodoItem>, leptos::ServerFnError>>,
+    mark_all_undone: Action<MarkAllUndone, Result<Vec<TodoItem>, leptos::ServerFnError>>,
+    delete_all: Action<DeleteAll, Result<Vec<u32>, leptos::ServerFnError>>,
 ) -> impl IntoView {
+    let filtered =
+        move || scope.with(|scope| scope.as_ref().is_some_and(|scope| !scope.is_empty()));
+    let invalid = move || scope.with(Option::is_none);
+    let scope_input = move || {
+        view! {
+            <input type="hidden" name="filter" value=move || scope.get().unwrap_or_default()/>
+        }
+    };
+
     view! {
         <div class="d-flex justify-content-center">
-            <ActionForm action=mark_all_done>
-                <input type="submit" value="All Done" class="btn btn-outline-success mx-3"/>
-            </ActionForm>
+            <form on:submit=dispatch_on_submit(mark_all_done)>
+                {scope_input}
+                <input type="submit" class="btn btn-outline-success mx-3" disabled=invalid
+                    value=move || if filtered() { "Matching Done" } else { "All Done" }/>
+            </form>
 
-            <ActionForm action=mark_all_undone>
-                <input type="submit" value="All Undone" class="btn btn-outline-warning mx-3"/>
-            </ActionForm>
+            <form on:submit=dispatch_on_submit(mark_all_undone)>
+                {scope_input}
+                <input type="submit" class="btn btn-outline-warning mx-3" disabled=invalid
+                    value=move || if filtered() { "Matching Undone" } else { "All Undone" }/>
+            </form>
 
-            <input type="button" value="Delete All" class="btn btn-outline-danger mx-3" data-bs-toggle="modal" data-bs-target="#confirm-delete"/>
+            <input type="button" class="btn btn-outline-danger mx-3" disabled=invalid
+                value=move || if filtered() { "Delete Matching" } else { "Delete All" }
+                data-bs-toggle="modal" data-bs-target="#confirm-delete"/>
+
+            <CopyMarkdown scope/>
         </div>
 
         <div class="modal" tabindex="-1" id="confirm-delete">
             <div class="modal-dialog">
                 <div class="modal-content">
                     <div class="modal-header">
-                        <h5 class="modal-title text-danger">Delete All</h5>
+                        <h5 class="modal-title text-danger">
+                            {move || if filtered() { "Delete Matching" } else { "Delete All" }}
+                        </h5>
                         <button type="button" class="btn-close" data-bs-dismiss="modal" aria-label="Close"></button>
                     </div>
 
                     <div class="modal-body text-start">
-                        <p>This will delete all todos, are you sure?</p>
+                        {move || if filtered() {
+                            view! {
+                                <p>
+                                    "This will delete all todos matching "
+                                    <code>{scope.get()}</code>
+                                    ", including ones not loaded yet, are you sure?"
+                                </p>
+                            }
+                        } else {
+                            view! { <p>This will delete all todos, are you sure?</p> }
+                        }}
                     </div>
 
                     <div class="modal-footer">
                         <button type="button" class="btn btn-secondary" data-bs-dismiss="modal">Close</button>
-                        <ActionForm action=delete_all>
-                            <input type="submit" value="Delete All" class="btn btn-danger" data-bs-dismiss="modal"/>
-                        </ActionForm>
+                        <form on:submit=dispatch_on_submit(delete_all)>
+                            {scope_input}
+                            <input type="submit" class="btn btn-danger" data-bs-dismiss="modal"
+                                value=move || if filtered() { "Delete Matching" } else { "Delete All" }/>
+                        </form>
                     </div>
                 </div>
             </div>
@@ -426,19 +2043,139 @@ fn AllTodosAction(
     }
 }
 
+#[component]
+fn ConflictPrompt(
+    conflict: RwSignal<Option<Conflict>>,
+    todos: RwSignal<Vec<RwSignal<TodoItem>>>,
+    set_done: Action<SetDone, Result<TodoItem, leptos::ServerFnError>>,
+    edit_todo: Action<EditTodo, Result<TodoItem, leptos::ServerFnError>>,
+    edit_details: Action<EditDetails, Result<TodoItem, leptos::ServerFnError>>,
+    delete_todo: Action<DeleteTodo, Result<u32, leptos::ServerFnError>>,
+) -> impl IntoView {
+    // either way the todo is reloaded, overwriting then applies the change again on top
+    let resolve = move |overwrite: bool| {
+        let Some(rejected) = conflict.get_untracked() else {
+            return;
+        };
+        conflict.set(None);
+
+        let id = rejected.id();
+        spawn_local(async move {
+            match get_todo(id).await {
+                Ok(Some(current)) => {
+                    let version = current.version;
+                    let current_done = current.done;
+                    let current_task = current.task.clone();
+                    let current_details =
+                        (current.priority, current.due.clone(), current.tags.clone());
+                    replace_todo(todos, current);
+
+                    if overwrite {
+                        match rejected {
+                            Conflict::SetDone { id, done } if done != current_done => {
+                                set_done.dispatch(SetDone { id, done, version })
+                            }
+                            Conflict::SetDone { .. } => {}
+                            Conflict::Edit { id, task } if task != current_task => {
+                                edit_todo.dispatch(EditTodo { id, task, version })
+                            }
+                            Conflict::Edit { .. } => {}
+                            Conflict::Details {
+                                id,
+                                priority,
+                                due,
+                                tags,
+                            } if (priority, due.clone(), parse_tags(&tags)) != current_details => {
+                                edit_details.dispatch(EditDetails {
+                                    id,
+                                    priority,
+                                    due,
+                                    tags,
+                                    version,
+                                })
+                            }
+                            Conflict::Details { .. } => {}
+                            Conflict::Delete { id } => {
+                                delete_todo.dispatch(DeleteTodo { id, version })
+                            }
+                        }
+                    }
+                }
+                // deleted by someone else, nothing left to overwrite
+                Ok(None) => remove_todo(todos, id),
+                Err(e) => log::error!("couldn't reload todo {id}: {e}"),
+            }
+        });
+    };
+
+    let task = move || {
+        conflict
+            .with(|conflict| conflict.as_ref().map(Conflict::id))
+            .and_then(|id| find_todo(todos, id))
+            .map(|todo| todo.with(|todo| todo.task.clone()))
+    };
+
+    view! {
+        <div class="alert alert-danger col-6 mx-auto" role="alert"
+            hidden=move || conflict.with(Option::is_none)>
+            <div>Conflict</div>
+            <div class="text-muted mb-2">
+                <q>{task}</q> " was changed by someone else since it was loaded."
+            </div>
+            <button type="button" class="btn btn-sm btn-outline-secondary mx-1"
+                on:click=move |_| resolve(false)>Keep theirs</button>
+            <button type="button" class="btn btn-sm btn-outline-danger mx-1"
+                on:click=move |_| resolve(true)>Apply mine</button>
+        </div>
+    }
+}
+
 #[component]
 fn ShowMessages(
     todos: RwSignal<Vec<RwSignal<TodoItem>>>,
-    get_todos: Action<GetTodos, Result<Vec<TodoItem>, leptos::ServerFnError>>,
+    online: RwSignal<bool>,
+    get_todos: Action<GetTodos, Result<TodoPage, leptos::ServerFnError>>,
+    /// Why the last page couldn't be loaded.
+    page_error: RwSignal<Option<ServerFnError>>,
     add_todo: Action<AddTodo, Result<TodoItem, leptos::ServerFnError>>,
+    set_done: Action<SetDone, Result<TodoItem, leptos::ServerFnError>>,
+    edit_todo: Action<EditTodo, Result<TodoItem, leptos::ServerFnError>>,
+    edit_details: Action<EditDetails, Result<TodoItem, leptos::ServerFnError>>,
+    delete_todo: Action<DeleteTodo, Result<u32, leptos::ServerFnError>>,
+    mark_all_done: Action<MarkAllDone, Result<Vec<TodoItem>, leptos::ServerFnError>>,
+    mark_all_undone: Action<MarkAllUndone, Result<Vec<TodoItem>, leptos::ServerFnError>>,
+    delete_all: Action<DeleteAll, Result<Vec<u32>, leptos::ServerFnError>>,
+    set_done_many: Action<SetDoneMany, Result<Bulk<TodoItem>, leptos::ServerFnError>>,
+    tag_many: Action<TagMany, Result<Bulk<TodoItem>, leptos::ServerFnError>>,
+    move_many: Action<MoveMany, Result<Bulk<TodoItem>, leptos::ServerFnError>>,
+    set_priority_many: Action<SetPriorityMany, Result<Bulk<TodoItem>, leptos::ServerFnError>>,
+    delete_many: Action<DeleteMany, Result<Bulk<u32>, leptos::ServerFnError>>,
+    search_todos: Action<SearchTodos, Result<Vec<SearchResult>, leptos::ServerFnError>>,
+    report: RwSignal<Option<String>>,
 ) -> impl IntoView {
     view! {
+        {move || report.get().map(|message| view! {
+            <div class="alert alert-success alert-dismissible col-6 mx-auto" role="status">
+                {message}
+                <button type="button" class="btn-close" aria-label="Close"
+                    on:click=move |_| report.set(None)></button>
+            </div>
+        })}
         {move || {
             if get_todos.pending().get() {
                 view! {
                     <div class="spinner-border spinner-border-sm" role="status"></div>
                 }
-            } else if let Some(Err(err)) = get_todos.value().get() {
+            } else if !online.get() {
+                view! {
+                    <div class="alert alert-secondary col-6 mx-auto" role="status">
+                        <div><i class="bi bi-wifi-off me-1"></i> Offline</div>
+                        <div class="text-muted mb-0">
+                            Changes are saved locally and synced when back online
+                        </div>
+                    </div>
+                }
+            } else if let Some(err) = page_error.get() {
                 view! {
                     <div class="alert alert-warning col-6 mx-auto" role="alert">
                         <div>Error Getting Todos</div>
@@ -455,12 +2192,43 @@ fn ShowMessages(
                 view! {<div></div>}
             }
         }}
+        <ActionError action=add_todo title="Error Adding Todo"/>
+        <ActionError action=set_done title="Error Updating Todo"/>
+        <ActionError action=edit_todo title="Error Editing Todo"/>
+        <ActionError action=edit_details title="Error Editing Todo Details"/>
+        <ActionError action=delete_todo title="Error Deleting Todo"/>
+        <ActionError action=mark_all_done title="Error Marking All Done"/>
+        <ActionError action=mark_all_undone title="Error Marking All Undone"/>
+        <ActionError action=delete_all title="Error Deleting All Todos"/>
+        <ActionError action=set_done_many title="Error Updating Selected Todos"/>
+        <ActionError action=tag_many title="Error Tagging Selected Todos"/>
+        <ActionError action=move_many title="Error Moving Selected Todos"/>
+        <ActionError action=set_priority_many title="Error Changing Priority of Selected Todos"/>
+        <ActionError action=delete_many title="Error Deleting Selected Todos"/>
+        <ActionError action=search_todos title="Error Searching Todos"/>
+    }
+}
+
+#[component]
+fn ActionError<I, O>(
+    action: Action<I, Result<O, leptos::ServerFnError>>,
+    title: &'static str,
+) -> impl IntoView
+where
+    I: 'static,
+    O: 'static,
+{
+    view! {
         {move || {
-            if let Some(Err(err)) = add_todo.value().get() {
+            // conflicts are shown by ConflictPrompt instead
+            if let Some(err) = action.value().with(|value| match value {
+                Some(Err(err)) if !is_conflict(err) => Some(err.to_string()),
+                _ => None,
+            }) {
                 view! {
                     <div class="alert alert-warning col-6 mx-auto" role="alert">
-                        <div>Error Adding Todo</div>
-                        <div class="text-muted mb-0">{err.to_string()}</div>
+                        <div>{title}</div>
+                        <div class="text-muted mb-0">{err}</div>
                     </div>
                 }
             } else {
@@ -470,15 +2238,473 @@ fn ShowMessages(
     }
 }
 
+#[component]
+fn StatusTabs(status: RwSignal<Status>) -> impl IntoView {
+    view! {
+        <ul class="nav nav-pills mb-3">
+            {Status::ALL.map(|option| view! {
+                <li class="nav-item">
+                    <button type="button" class="nav-link"
+                        class:active=move || status.get() == option
+                        on:click=move |_| status.set(option)>
+                        {option.label()}
+                    </button>
+                </li>
+            }).collect_view()}
+        </ul>
+    }
+}
+
 #[component]
 fn Todolist(
     todos: RwSignal<Vec<RwSignal<TodoItem>>>,
     delete_todo: Action<DeleteTodo, Result<u32, leptos::ServerFnError>>,
-    toggle_todo: Action<ToggleTodo, Result<u32, leptos::ServerFnError>>,
-    filter: RwSignal<String>,
+    set_done: Action<SetDone, Result<TodoItem, leptos::ServerFnError>>,
+    edit_todo: Action<EditTodo, Result<TodoItem, leptos::ServerFnError>>,
+    edit_details: Action<EditDetails, Result<TodoItem, leptos::ServerFnError>>,
+    set_done_many: Action<SetDoneMany, Result<Bulk<TodoItem>, leptos::ServerFnError>>,
+    tag_many: Action<TagMany, Result<Bulk<TodoItem>, leptos::ServerFnError>>,
+    move_many: Action<MoveMany, Result<Bulk<TodoItem>, leptos::ServerFnError>>,
+    set_priority_many: Action<SetPriorityMany, Result<Bulk<TodoItem>, leptos::ServerFnError>>,
+    delete_many: Action<DeleteMany, Result<Bulk<u32>, leptos::ServerFnError>>,
+    matches: Memo<Option<Vec<(u32, String)>>>,
+    sort: RwSignal<Sort>,
+    status: RwSignal<Status>,
     add_todo: Action<AddTodo, Result<TodoItem, leptos::ServerFnError>>,
+    keymap: RwSignal<Keymap>,
+) -> impl IntoView {
+    // while searching, only the matching todos, best match first
+    let filtered = move || match matches.get() {
+        None => todos.get(),
+        Some(matches) => todos.with(|todos| {
+            let by_id = todos_by_id(todos);
+            matches
+                .iter()
+                .filter_map(|(id, _)| by_id.get(id).copied())
+                .collect()
+        }),
+    };
+    // highlighted tasks by id, so cards find theirs without going through every match
+    let highlights = create_memo(move |_| {
+        matches.with(|matches| matches.iter().flatten().cloned().collect::<HashMap<_, _>>())
+    });
+    // deleted from the keyboard, hidden until it's too late to undo, it stays in the
+    // list meanwhile so pages and search results loaded in the meantime don't add it back
+    let undoable = create_rw_signal::<Option<Undoable>>(None);
+    let visible = create_memo(move |_| {
+        let (sort, status) = (sort.get(), status.get());
+        let hidden = undoable.with(|undoable| undoable.as_ref().map(|undoable| undoable.todo));
+        let mut visible: Vec<_> = filtered()
+            .into_iter()
+            .filter(|todo| Some(*todo) != hidden && todo.with(|todo| status.matches(todo)))
+            .collect();
+        visible.sort_by(|a, b| a.with(|a| b.with(|b| sort.compare(a, b))));
+        visible
+    });
+
+    // the todo just added, until its card has flashed, so it doesn't again when remounted
+    let flash = create_rw_signal::<Option<u32>>(None);
+    create_effect(move |_| {
+        if let Some(Ok(added)) = add_todo.value().get() {
+            flash.set(Some(added.id));
+        }
+    });
+
+    // ids of the todo selected from the keyboard and the one being edited
+    let selected = create_rw_signal::<Option<u32>>(None);
+    let editing = create_rw_signal::<Option<u32>>(None);
+
+    let select = move |offset: isize| {
+        let ids: Vec<_> = visible.with_untracked(|visible| {
+            visible
+                .iter()
+                .map(|todo| todo.with_untracked(|todo| todo.id))
+                .collect()
+        });
+        let current = selected
+            .get_untracked()
+            .and_then(|id| ids.iter().position(|selected_id| *selected_id == id));
+        let index = match current {
+            Some(index) => index.saturating_add_signed(offset).min(ids.len() - 1),
+            None if offset > 0 => 0,
+            None => ids.len().saturating_sub(1),
+        };
+        selected.set(ids.get(index).copied());
+    };
+    let selected_todo = move || {
+        let id = selected.get_untracked()?;
+        visible.with_untracked(|visible| {
+            visible
+                .iter()
+                .find(|todo| todo.with_untracked(|todo| todo.id == id))
+                .copied()
+        })
+    };
+
+    let finish_delete = move |undo: bool| {
+        let Some(Undoable { todo, timeout }) = undoable.try_update(Option::take).flatten() else {
+            return;
+        };
+
+        if let Some(timeout) = timeout {
+            timeout.clear();
+        }
+        // gone already if deleted elsewhere meanwhile
+        let delete = todo.try_with_untracked(|todo| DeleteTodo {
+            id: todo.id,
+            version: todo.version,
+        });
+        if let (false, Some(delete)) = (undo, delete) {
+            delete_todo.dispatch(delete);
+        }
+    };
+    let delete_later = move |todo: RwSignal<TodoItem>| {
+        // one undo at a time, earlier deletes go through
+        finish_delete(false);
+
+        select(1);
+        if selected.get_untracked() == Some(todo.with_untracked(|todo| todo.id)) {
+            select(-1);
+        }
+
+        let timeout = set_timeout_with_handle(move || finish_delete(false), UNDO_DELAY).ok();
+        undoable.set(Some(Undoable { todo, timeout }));
+    };
+    on_shortcut(keymap, move |command| {
+        match (command, selected_todo()) {
+            (Command::Next, _) => select(1),
+            (Command::Previous, _) => select(-1),
+            (Command::Toggle, Some(todo)) => {
+                set_done.dispatch(todo.with_untracked(|todo| SetDone {
+                    id: todo.id,
+                    done: !todo.done,
+                    version: todo.version,
+                }))
+            }
+            (Command::Edit, Some(todo)) => editing.set(Some(todo.with_untracked(|todo| todo.id))),
+            (Command::Delete, Some(todo)) => delete_later(todo),
+            _ => return false,
+        }
+        true
+    });
+
+    // ids checked for bulk actions, shift-click checks the range from the last one clicked
+    let checked = create_rw_signal(HashSet::<u32>::new());
+    let last_checked = store_value::<Option<u32>>(None);
+    let visible_ids = move || {
+        visible.with_untracked(|visible| {
+            visible
+                .iter()
+                .map(|todo| todo.with_untracked(|todo| todo.id))
+                .collect::<Vec<_>>()
+        })
+    };
+    let check = Callback::new(move |(id, shift): (u32, bool)| {
+        let range = last_checked.get_value().filter(|_| shift).and_then(|last| {
+            let ids = visible_ids();
+            let from = ids.iter().position(|id| *id == last)?;
+            let to = ids.iter().position(|other| *other == id)?;
+            Some(ids[from.min(to)..=from.max(to)].to_vec())
+        });
+        checked.update(|checked| {
+            let check = !checked.contains(&id);
+            for id in range.unwrap_or_else(|| vec![id]) {
+                if check {
+                    checked.insert(id);
+                } else {
+                    checked.remove(&id);
+                }
+            }
+        });
+        last_checked.set_value(Some(id));
+    });
+    // hidden todos stay checked, but bulk actions only apply to those in view
+    // with the versions they're shown at, changed since is a conflict
+    let checked_visible = move || {
+        checked.with(|checked| {
+            visible.with_untracked(|visible| {
+                visible
+                    .iter()
+                    .filter_map(|todo| {
+                        todo.with_untracked(|todo| {
+                            checked.contains(&todo.id).then(|| Versioned::from(todo))
+                        })
+                    })
+                    .collect::<Vec<_>>()
+            })
+        })
+    };
+    let prompt = |message: &str| window().prompt_with_message(message).ok().flatten();
+    let count = create_memo(move |_| {
+        checked.with(|checked| {
+            visible.with(|visible| {
+                visible
+                    .iter()
+                    .filter(|todo| todo.with_untracked(|todo| checked.contains(&todo.id)))
+                    .count()
+            })
+        })
+    });
+
+    // long lists only mount the cards in view
+    let virtualized = create_memo(move |_| visible.with(Vec::len) > VIRTUALIZE_AFTER);
+
+    let list = move || {
+        if virtualized.get() {
+            view! {
+                <VirtualTodolist visible delete_todo set_done edit_todo edit_details highlights flash
+                    selected editing checked check/>
+            }
+        } else {
+            view! {
+                <For
+                    each=move || visible.get()
+                    key=|todo| todo.with_untracked(|todo| todo.id)
+                    children=move |todo| view! {
+                        <TodoCard todo delete_todo set_done edit_todo edit_details highlights flash
+                            selected editing checked check fixed_height=false/>
+                    }
+                />
+            }
+        }
+    };
+
+    view! {
+        <Show when=move || { count.get() > 0 }>
+            <div class="sticky-top d-flex align-items-center bg-body py-2 mb-3">
+                <span class="me-auto">{count} " selected"</span>
+                <button type="button" class="btn btn-sm btn-outline-success mx-1"
+                    on:click=move |_| set_done_many.dispatch(SetDoneMany {
+                        todos: checked_visible(),
+                        done: true,
+                    })>Done</button>
+                <button type="button" class="btn btn-sm btn-outline-warning mx-1"
+                    on:click=move |_| set_done_many.dispatch(SetDoneMany {
+                        todos: checked_visible(),
+                        done: false,
+                    })>Undone</button>
+                <button type="button" class="btn btn-sm btn-outline-secondary mx-1"
+                    on:click=move |_| {
+                        if let Some(tags) = prompt("Add tags, separated by spaces") {
+                            tag_many.dispatch(TagMany { todos: checked_visible(), tags });
+                        }
+                    }>Tag</button>
+                <button type="button" class="btn btn-sm btn-outline-secondary mx-1"
+                    on:click=move |_| {
+                        if let Some(list) = prompt("Move to list, none when blank") {
+                            move_many.dispatch(MoveMany { todos: checked_visible(), list });
+                        }
+                    }>Move</button>
+                <div class="dropdown mx-1">
+                    <button type="button" class="btn btn-sm btn-outline-secondary dropdown-toggle"
+                        data-bs-toggle="dropdown" aria-expanded="false">Priority</button>
+                    <ul class="dropdown-menu">
+                        {Priority::ALL.map(Some).into_iter().chain([None]).map(|priority| view! {
+                            <li>
+                                <button type="button" class="dropdown-item"
+                                    on:click=move |_| set_priority_many.dispatch(SetPriorityMany {
+                                        todos: checked_visible(),
+                                        priority,
+                                    })>
+                                    {priority.map_or("None", Priority::label)}
+                                </button>
+                            </li>
+                        }).collect_view()}
+                    </ul>
+                </div>
+                <button type="button" class="btn btn-sm btn-outline-danger mx-1"
+                    on:click=move |_| {
+                        let todos = checked_visible();
+                        checked.update(|checked| todos.iter().for_each(|todo| {
+                            checked.remove(&todo.id);
+                        }));
+                        delete_many.dispatch(DeleteMany { todos });
+                    }>Delete</button>
+                <button type="button" class="btn btn-sm btn-outline-secondary mx-1"
+                    on:click=move |_| checked.update(|checked| {
+                        checked.extend(visible_ids());
+                    })>Select all</button>
+                <button type="button" class="btn btn-sm btn-outline-secondary mx-1"
+                    on:click=move |_| checked.update(HashSet::clear)>Clear</button>
+            </div>
+        </Show>
+
+        {list}
+
+        {move || undoable.with(|undoable| undoable.as_ref().map(|undoable| {
+            let task = undoable.todo.try_with_untracked(|todo| todo.task.clone());
+            view! {
+                <div class="toast show position-fixed bottom-0 end-0 m-3" role="status">
+                    <div class="toast-body d-flex align-items-center">
+                        <span class="me-auto text-truncate">"Deleted " <q>{task}</q></span>
+                        <button type="button" class="btn btn-sm btn-outline-warning ms-2"
+                            on:click=move |_| finish_delete(true)>Undo</button>
+                    </div>
+                </div>
+            }
+        }))}
+    }
+}
+
+/// How long a todo deleted from the keyboard can be brought back.
+const UNDO_DELAY: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// A todo deleted from the keyboard, and the timeout that deletes it for good.
+#[derive(Clone, Copy)]
+struct Undoable {
+    todo: RwSignal<TodoItem>,
+    timeout: Option<leptos_dom::helpers::TimeoutHandle>,
+}
+
+/// Scrolls the window as little as needed to show `height` pixels from `top` of the page.
+fn keep_in_view(top: f64, height: f64) {
+    let window = window();
+    let scroll_x = window.scroll_x().unwrap_or_default();
+    let scroll_y = window.scroll_y().unwrap_or_default();
+    let view_height = window
+        .inner_height()
+        .ok()
+        .and_then(|height| height.as_f64())
+        .unwrap_or_default();
+
+    if top < scroll_y {
+        window.scroll_to_with_x_and_y(scroll_x, top);
+    } else if top + height > scroll_y + view_height {
+        window.scroll_to_with_x_and_y(scroll_x, top + height - view_height);
+    }
+}
+
+/// Lists longer than this are virtualized.
+const VIRTUALIZE_AFTER: usize = 200;
+
+/// Height of a card in a virtualized list plus the margin below it, in pixels, see
+/// `.fixed-height` in main.scss.
+const ROW_HEIGHT: f64 = 80.0;
+
+/// Cards mounted above and below the ones in view, so they're ready when scrolling.
+const OVERSCAN: usize = 10;
+
+/// Mounts only the cards in view of the page, with fixed-height cards and a spacer as
+/// tall as the whole list, so scrolling and the scrollbar behave as with every card.
+#[component]
+fn VirtualTodolist(
+    visible: Memo<Vec<RwSignal<TodoItem>>>,
+    delete_todo: Action<DeleteTodo, Result<u32, leptos::ServerFnError>>,
+    set_done: Action<SetDone, Result<TodoItem, leptos::ServerFnError>>,
+    edit_todo: Action<EditTodo, Result<TodoItem, leptos::ServerFnError>>,
+    edit_details: Action<EditDetails, Result<TodoItem, leptos::ServerFnError>>,
+    /// Highlighted tasks of the todos matching the search, by id.
+    highlights: Memo<HashMap<u32, String>>,
+    flash: RwSignal<Option<u32>>,
+    selected: RwSignal<Option<u32>>,
+    editing: RwSignal<Option<u32>>,
+    checked: RwSignal<HashSet<u32>>,
+    check: Callback<(u32, bool)>,
+) -> impl IntoView {
+    let list_ref = create_node_ref::<html::Div>();
+
+    // top of the list relative to the top of the window, and the window's height
+    let viewport = create_rw_signal((0.0, 0.0));
+    let measure = move || {
+        if let Some(list) = list_ref.get_untracked() {
+            let window = window();
+            let scroll_y = window.scroll_y().unwrap_or_default();
+            let height = window
+                .inner_height()
+                .ok()
+                .and_then(|height| height.as_f64())
+                .unwrap_or_default();
+            viewport.set((list.offset_top() as f64 - scroll_y, height));
+        }
+    };
+    create_effect(move |_| {
+        if list_ref.get().is_some() {
+            measure();
+        }
+    });
+    let scroll_handle = window_event_listener(ev::scroll, move |_| measure());
+    let resize_handle = window_event_listener(ev::resize, move |_| measure());
+    on_cleanup(move || {
+        scroll_handle.remove();
+        resize_handle.remove();
+    });
+
+    let range = create_memo(move |_| {
+        let (top, height) = viewport.get();
+        let len = visible.with(Vec::len);
+        let first = (-top / ROW_HEIGHT).floor().max(0.0) as usize;
+        let last = ((height - top) / ROW_HEIGHT).ceil().max(0.0) as usize;
+        (
+            first.saturating_sub(OVERSCAN).min(len),
+            (last + OVERSCAN).min(len),
+        )
+    });
+    let in_view = move || {
+        let (first, last) = range.get();
+        visible.with(|visible| visible[first..last].to_vec())
+    };
+
+    // the selected card may not be mounted, scrolling to where it goes mounts it
+    create_effect(move |_| {
+        let Some(id) = selected.get() else {
+            return;
+        };
+        let index = visible.with_untracked(|visible| {
+            visible
+                .iter()
+                .position(|todo| todo.with_untracked(|todo| todo.id == id))
+        });
+        if let (Some(index), Some(list)) = (index, list_ref.get_untracked()) {
+            keep_in_view(
+                list.offset_top() as f64 + index as f64 * ROW_HEIGHT,
+                ROW_HEIGHT,
+            );
+        }
+    });
+
+    view! {
+        <div node_ref=list_ref
+            style=move || format!("height: {}px", visible.with(Vec::len) as f64 * ROW_HEIGHT)>
+            <div style=move || format!("transform: translateY({}px)", range.get().0 as f64 * ROW_HEIGHT)>
+                <For
+                    each=in_view
+                    key=|todo| todo.with_untracked(|todo| todo.id)
+                    children=move |todo| view! {
+                        <TodoCard todo delete_todo set_done edit_todo edit_details highlights flash
+                            selected editing checked check fixed_height=true/>
+                    }
+                />
+            </div>
+        </div>
+    }
+}
+
+#[component]
+fn TodoCard(
+    todo: RwSignal<TodoItem>,
+    delete_todo: Action<DeleteTodo, Result<u32, leptos::ServerFnError>>,
+    set_done: Action<SetDone, Result<TodoItem, leptos::ServerFnError>>,
+    edit_todo: Action<EditTodo, Result<TodoItem, leptos::ServerFnError>>,
+    edit_details: Action<EditDetails, Result<TodoItem, leptos::ServerFnError>>,
+    /// Highlighted tasks of the todos matching the search, by id.
+    highlights: Memo<HashMap<u32, String>>,
+    flash: RwSignal<Option<u32>>,
+    selected: RwSignal<Option<u32>>,
+    editing: RwSignal<Option<u32>>,
+    /// Checked for bulk actions.
+    checked: RwSignal<HashSet<u32>>,
+    /// Called with the id and whether shift was held when the checkbox is clicked.
+    check: Callback<(u32, bool)>,
+    /// One line of task, for virtualized lists.
+    fixed_height: bool,
 ) -> impl IntoView {
-    let toggle_class = move |todo: RwSignal<TodoItem>| {
+    let id = move || todo.with(|todo| todo.id);
+    let highlighted = move || {
+        let id = id();
+        highlights.with(|highlights| highlights.get(&id).cloned())
+    };
+
+    let toggle_class = move || {
         format!(
             "btn btn-sm border-0 bi {}",
             if todo.with(|todo| todo.done) {
@@ -489,35 +2715,336 @@ fn Todolist(
         )
     };
 
-    view! {<For
-        each=todos
-        key=|todo| todo.with_untracked(|todo| todo.id)
-        children=move |todo| { view! {
-            <div class="card mb-3 bg-main"
-                class:flash=add_todo.value().with_untracked(|data| data.is_some())
-                class:visually-hidden=move || !todo.with(
-                    |todo| todo.task.contains(&filter.get())
-                )>
-                <div class="card-body d-flex align-items-center">
-                    <ActionForm action=toggle_todo>
-                        <input type="hidden" name="id"
-                            value=todo.with_untracked(|todo| todo.id)/>
-                        <button type="submit" value=""
-                            class=move || toggle_class(todo)/>
-                    </ActionForm>
-
-                    <div class="text-start mx-3 flex-fill">
-                        {move || todo.with(|todo| todo.task.clone())}
-                    </div>
+    // virtualized lists scroll to the selected card themselves, it may not be mounted
+    let card_ref = create_node_ref::<html::Div>();
+    create_effect(move |_| {
+        if !fixed_height && selected.get() == Some(todo.with_untracked(|todo| todo.id)) {
+            if let Some(card) = card_ref.get_untracked() {
+                keep_in_view(card.offset_top() as f64, card.offset_height() as f64);
+            }
+        }
+    });
 
-                    <ActionForm action=delete_todo>
-                        <input type="hidden" name="id"
-                            value=todo.with_untracked(|todo| todo.id)/>
-                        <button type="submit" value=""
-                            class="btn btn-sm border-0 btn-outline-danger bi bi-trash-fill"/>
-                    </ActionForm>
-                </div>
+    // priority, due date and tags are edited in a dropdown
+    let details_open = create_rw_signal(false);
+
+    // edited in place, saved on enter or when leaving the input, escape cancels
+    let is_editing = move || editing.get() == Some(id());
+    let input_ref = create_node_ref::<html::Input>();
+    create_effect(move |_| {
+        if let Some(input) = input_ref.get() {
+            _ = input.focus();
+            input.select();
+        }
+    });
+    let save = move |task: String| {
+        if editing.get_untracked() != Some(todo.with_untracked(|todo| todo.id)) {
+            return;
+        }
+        editing.set(None);
+
+        let task = task.trim().to_string();
+        let (id, version, changed) =
+            todo.with_untracked(|todo| (todo.id, todo.version, todo.task != task));
+        if !task.is_empty() && changed {
+            edit_todo.dispatch(EditTodo { id, task, version });
+        }
+    };
+
+    view! {
+        <div class="card mb-3 bg-main" node_ref=card_ref
+            class:fixed-height=fixed_height
+            class:ms-4=move || todo.with(|todo| todo.parent.is_some())
+            class:border-warning=move || selected.get() == Some(id())
+            class:flash=move || flash.get() == Some(id())
+            on:animationend=move |_| flash.set(None)
+            on:click=move |_| selected.set(Some(id()))>
+            <div class="card-body d-flex align-items-center">
+                <input type="checkbox" class="form-check-input mt-0 me-2" aria-label="Select"
+                    prop:checked=move || checked.with(|checked| checked.contains(&id()))
+                    on:click=move |ev| check.call((id(), ev.shift_key()))/>
+
+                <button type="button" class=toggle_class
+                    on:click=move |_| set_done.dispatch(todo.with_untracked(|todo| SetDone {
+                        id: todo.id,
+                        done: !todo.done,
+                        version: todo.version,
+                    }))/>
+
+                {move || if is_editing() {
+                    view! {
+                        <input type="text" class="form-control form-control-sm mx-3"
+                            node_ref=input_ref aria-label="Task"
+                            value=todo.with_untracked(|todo| todo.task.clone())
+                            on:keydown=move |ev| match ev.key().as_str() {
+                                "Enter" => save(event_target_value(&ev)),
+                                "Escape" => editing.set(None),
+                                _ => {}
+                            }
+                            on:blur=move |ev| save(event_target_value(&ev))/>
+                    }.into_view()
+                } else {
+                    view! {
+                        <Show when=move || todo.with(|todo| todo.parent.is_some())>
+                            <i class="bi bi-arrow-return-right ms-3" title="Subtask"></i>
+                        </Show>
+                        <div class="text-start mx-3 flex-fill" class:text-truncate=fixed_height
+                            title=move || fixed_height.then(|| todo.with(|todo| todo.task.clone()))
+                            on:dblclick=move |_| editing.set(Some(id()))>
+                            {move || match highlighted() {
+                                Some(highlighted) => highlighted_view(&highlighted),
+                                None => todo.with(|todo| todo.task.clone()).into_view(),
+                            }}
+                        </div>
+                    }.into_view()
+                }}
+
+                {move || todo.with(details_view)}
+
+                <button type="button"
+                    class="btn btn-sm border-0 btn-outline-secondary bi bi-pencil"
+                    title="Edit" on:click=move |_| editing.set(Some(id()))/>
+
+                <button type="button"
+                    class="btn btn-sm border-0 btn-outline-secondary bi bi-tags"
+                    title="Priority, due date and tags"
+                    on:click=move |_| details_open.update(|open| *open = !*open)/>
+
+                <button type="button"
+                    class="btn btn-sm border-0 btn-outline-danger bi bi-trash-fill"
+                    on:click=move |_| delete_todo.dispatch(todo.with_untracked(|todo| DeleteTodo {
+                        id: todo.id,
+                        version: todo.version,
+                    }))/>
             </div>
-        }}
-    />}
+
+            <Show when=move || details_open.get()>
+                <DetailsEditor todo edit_details open=details_open/>
+            </Show>
+        </div>
+    }
+}
+
+/// A todo's priority, due date and tags as badges, the due date red once it's passed.
+fn details_view(todo: &TodoItem) -> View {
+    let priority = todo.priority.map(|priority| {
+        let class = match priority {
+            Priority::High => "badge text-bg-danger ms-1",
+            Priority::Medium => "badge text-bg-warning ms-1",
+            Priority::Low => "badge text-bg-secondary ms-1",
+        };
+        view! { <span class=class>{priority.label()}</span> }
+    });
+    let due = todo.due.clone().map(|due| {
+        let overdue = !todo.done && due < dates::today();
+        view! {
+            <span class="badge ms-1" class:text-bg-danger=overdue
+                class:text-bg-light=!overdue>
+                <i class="bi bi-calendar-event me-1"></i>{due}
+            </span>
+        }
+    });
+    let tags = todo
+        .tags
+        .iter()
+        .map(|tag| view! { <span class="badge text-bg-info ms-1">"#" {tag.clone()}</span> })
+        .collect_view();
+    let list = todo.list.clone().map(|list| {
+        view! {
+            <span class="badge text-bg-dark border ms-1">
+                <i class="bi bi-list-ul me-1"></i>{list}
+            </span>
+        }
+    });
+
+    view! { <span class="text-nowrap">{list} {priority} {due} {tags}</span> }.into_view()
+}
+
+/// Edits a todo's priority, due date and tags, in a dropdown under its card.
+#[component]
+fn DetailsEditor(
+    todo: RwSignal<TodoItem>,
+    edit_details: Action<EditDetails, Result<TodoItem, leptos::ServerFnError>>,
+    open: RwSignal<bool>,
+) -> impl IntoView {
+    let (priority, due, tags) = todo.with_untracked(|todo| {
+        (
+            create_rw_signal(todo.priority),
+            create_rw_signal(todo.due.clone().unwrap_or_default()),
+            create_rw_signal(todo.tags.join(" ")),
+        )
+    });
+
+    let save = move |ev: ev::SubmitEvent| {
+        ev.prevent_default();
+        open.set(false);
+
+        let (id, version) = todo.with_untracked(|todo| (todo.id, todo.version));
+        edit_details.dispatch(EditDetails {
+            id,
+            priority: priority.get_untracked(),
+            due: Some(due.get_untracked()).filter(|due| !due.is_empty()),
+            tags: tags.get_untracked(),
+            version,
+        });
+    };
+
+    view! {
+        <form class="dropdown-menu dropdown-menu-end show end-0 top-100 p-3" on:submit=save
+            on:click=|ev| ev.stop_propagation()>
+            <label class="form-label w-100">"Priority"
+                <select class="form-select form-select-sm"
+                    on:change=move |ev| priority.set(event_target_value(&ev).parse().ok())>
+                    <option value="" selected=move || priority.get().is_none()>"None"</option>
+                    {Priority::ALL.into_iter().map(|option| view! {
+                        <option value=option.as_str()
+                            selected=move || priority.get() == Some(option)>
+                            {option.label()}
+                        </option>
+                    }).collect_view()}
+                </select>
+            </label>
+            <label class="form-label w-100">"Due"
+                <input type="date" class="form-control form-control-sm" prop:value=due
+                    on:input=move |ev| due.set(event_target_value(&ev))/>
+            </label>
+            <label class="form-label w-100">"Tags"
+                <input type="text" class="form-control form-control-sm" placeholder="home, errands"
+                    prop:value=tags on:input=move |ev| tags.set(event_target_value(&ev))/>
+            </label>
+            <div class="d-flex justify-content-end">
+                <button type="button" class="btn btn-sm btn-outline-secondary me-1"
+                    on:click=move |_| open.set(false)>"Cancel"</button>
+                <button type="submit" class="btn btn-sm btn-primary">"Save"</button>
+            </div>
+        </form>
+    }
+}
+
+#[component]
+fn LoadMore(
+    get_todos: Action<GetTodos, Result<TodoPage, leptos::ServerFnError>>,
+    next_page: RwSignal<Option<u32>>,
+    matches: Memo<Option<Vec<(u32, String)>>>,
+) -> impl IntoView {
+    // while filtering, the search already covers the todos not loaded yet
+    let more = move || next_page.get().is_some() && matches.with(Option::is_none);
+
+    view! {
+        <Show when=more>
+            <button type="button" class="btn btn-outline-secondary"
+                disabled=move || get_todos.pending().get()
+                on:click=move |_| get_todos.dispatch(GetTodos {
+                    after: next_page.get_untracked(),
+                    limit: PAGE_SIZE,
+                })>
+                Load more
+            </button>
+        </Show>
+    }
+}
+
+/// Renders a task from search results, with the matched terms marked.
+fn highlighted_view(highlighted: &str) -> View {
+    let mut parts = highlighted.split(MATCH_START);
+    let before = parts.next().unwrap_or_default().to_string();
+
+    std::iter::once(before.into_view())
+        .chain(parts.flat_map(|part| {
+            let (matched, after) = part.split_once(MATCH_END).unwrap_or((part, ""));
+            [
+                view! { <mark class="px-0">{matched.to_string()}</mark> }.into_view(),
+                after.to_string().into_view(),
+            ]
+        }))
+        .collect_view()
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+    use super::*;
+    use futures::channel::oneshot;
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::rc::Rc;
+
+    type Answer = oneshot::Sender<Result<String, ServerFnError>>;
+
+    #[test]
+    fn watchers_hear_of_changes_made_after_they_start() {
+        // a channel of the test's own, others change todos meanwhile
+        let (changes, _) = watch::channel(());
+        notify(&changes);
+        let mut changed = changes.subscribe();
+        assert!(!changed.has_changed().unwrap());
+
+        notify(&changes);
+        notify(&changes);
+        assert!(changed.has_changed().unwrap());
+        changed.borrow_and_update();
+        assert!(!changed.has_changed().unwrap());
+    }
+
+    #[tokio::test]
+    async fn overlapping_dispatches_settle_with_their_own_input_and_answer() {
+        let runtime = create_runtime();
+        let local = tokio::task::LocalSet::new();
+        local
+            .run_until(async {
+                let answers = Rc::new(RefCell::new(HashMap::<u32, Answer>::new()));
+                let settled = Rc::new(RefCell::new(Vec::new()));
+                let action = create_optimistic_action(
+                    {
+                        let answers = answers.clone();
+                        move |input: &u32| {
+                            let (answer, answered) = oneshot::channel();
+                            answers.borrow_mut().insert(*input, answer);
+                            async move { answered.await.expect("every dispatch is answered") }
+                        }
+                    },
+                    |input| input * 10,
+                    {
+                        let settled = settled.clone();
+                        move |input: &u32, applied: u32, result: Result<&String, &ServerFnError>| {
+                            let result = result.cloned().map_err(|e| e.to_string());
+                            settled.borrow_mut().push((*input, applied, result));
+                        }
+                    },
+                );
+                let answer = |input: u32, result: Result<String, ServerFnError>| {
+                    let answer = answers.borrow_mut().remove(&input).expect("dispatched");
+                    answer.send(result).expect("still waiting");
+                };
+                let settle = |count: usize| {
+                    let settled = &settled;
+                    async move {
+                        while settled.borrow().len() < count {
+                            tokio::task::yield_now().await;
+                        }
+                    }
+                };
+
+                action.dispatch(1);
+                action.dispatch(2);
+                // the second dispatch is answered first, and the first one fails
+                answer(2, Ok("two".into()));
+                settle(1).await;
+                answer(1, Err(ServerFnError::ServerError(CONFLICT.into())));
+                settle(2).await;
+
+                assert_eq!(
+                    *settled.borrow(),
+                    [
+                        (2, 20, Ok("two".to_string())),
+                        (
+                            1,
+                            10,
+                            Err(ServerFnError::ServerError(CONFLICT.into()).to_string())
+                        ),
+                    ]
+                );
+            })
+            .await;
+        runtime.dispose();
+    }
 }
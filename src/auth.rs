@@ -0,0 +1,287 @@
+//! Who's asking. The server's users are in its `users` table, added with
+//! `leptos-todo create-user <name>`, each with a token, and every route that reads
+//! or changes todos needs one of them once there are any: programs pass theirs as
+//! `Authorization: Bearer <token>`, people sign in with theirs at `/sign-in`, which
+//! keeps it in a cookie.
+//!
+//! Without users the server is open to anyone who can reach it, and says so when it
+//! starts.
+
+use cfg_if::cfg_if;
+
+cfg_if! { if #[cfg(feature = "ssr")] {
+    use crate::app::db;
+    use axum::{
+        extract::Form,
+        http::{header, HeaderMap, Request, StatusCode},
+        middleware::Next,
+        response::{Html, IntoResponse, Redirect, Response},
+        routing::get,
+        Router,
+    };
+    use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+    use leptos::{use_context, ServerFnError};
+    use serde::Deserialize;
+    use sha2::{Digest, Sha256};
+
+    /// Environment variable the server's tokens used to be in, now only warned about.
+    const API_TOKENS: &str = "API_TOKENS";
+
+    /// Cookie `/sign-in` keeps the token in.
+    const TOKEN_COOKIE: &str = "todos_token";
+
+    /// A user, by their name in the `users` table.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct User(pub String);
+
+    /// Adds a user, returning their token. Only its hash is kept, so it can't be
+    /// shown again, only replaced with [`reset_token`].
+    pub async fn create_user(name: &str) -> Result<String, ServerFnError> {
+        let name = check_name(name)?;
+        let pool = db().await?;
+        let token = new_token();
+
+        let added = sqlx::query("INSERT INTO users (name, token_hash) VALUES (?, ?)")
+            .bind(&name)
+            .bind(token_hash(&token))
+            .execute(&pool)
+            .await;
+        match added {
+            Ok(_) => Ok(token),
+            Err(sqlx::Error::Database(e)) if e.is_unique_violation() => Err(
+                ServerFnError::Args(format!("there's already a user named `{name}`")),
+            ),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Gives a user a new token, returning it, and their old one stops working.
+    pub async fn reset_token(name: &str) -> Result<String, ServerFnError> {
+        let name = check_name(name)?;
+        let pool = db().await?;
+        let token = new_token();
+
+        let reset = sqlx::query("UPDATE users SET token_hash = ? WHERE name = ?")
+            .bind(token_hash(&token))
+            .bind(&name)
+            .execute(&pool)
+            .await?;
+        if reset.rows_affected() == 0 {
+            return Err(ServerFnError::Args(format!("there's no user named `{name}`")));
+        }
+        Ok(token)
+    }
+
+    fn check_name(name: &str) -> Result<String, ServerFnError> {
+        match name.trim() {
+            "" => Err(ServerFnError::Args("user name can't be empty".into())),
+            name => Ok(name.to_string()),
+        }
+    }
+
+    /// 256 random bits, as URL-safe base64 so it fits in headers, cookies and links.
+    fn new_token() -> String {
+        URL_SAFE_NO_PAD.encode(rand::random::<[u8; 32]>())
+    }
+
+    /// What's kept of a token. Tokens are random enough not to need a slow hash.
+    fn token_hash(token: &str) -> String {
+        format!("{:x}", Sha256::digest(token.as_bytes()))
+    }
+
+    /// Whether anyone can use the server, as it has no users. Isn't when the
+    /// database can't tell, so failing to read it doesn't open the server.
+    pub async fn is_open() -> bool {
+        let users = match db().await {
+            Ok(pool) => sqlx::query_scalar::<_, bool>("SELECT EXISTS (SELECT 1 FROM users)")
+                .fetch_one(&pool)
+                .await
+                .map_err(ServerFnError::from),
+            Err(e) => Err(e),
+        };
+        match users {
+            Ok(users) => !users,
+            Err(e) => {
+                log::error!("couldn't read the users: {e}");
+                false
+            }
+        }
+    }
+
+    /// Says loudly that the server is open, when it is, and that [`API_TOKENS`]
+    /// isn't read anymore, when it's still set.
+    pub async fn warn_if_open() {
+        if std::env::var_os(API_TOKENS).is_some() {
+            log::warn!(
+                "{API_TOKENS} isn't read anymore, add its users with \
+                 `leptos-todo create-user <name>`"
+            );
+        }
+        if is_open().await {
+            log::warn!(
+                "there are no users: anyone who can reach this server can read, change \
+                 and delete every todo, add one with `leptos-todo create-user <name>`"
+            );
+        }
+    }
+
+    /// The user whose token is `given`.
+    pub async fn user_with_token(given: &str) -> Option<User> {
+        let pool = db().await.ok()?;
+        let name = sqlx::query_scalar::<_, String>("SELECT name FROM users WHERE token_hash = ?")
+            .bind(token_hash(given))
+            .fetch_optional(&pool)
+            .await;
+        match name {
+            Ok(name) => name.map(User),
+            Err(e) => {
+                log::error!("couldn't read the users: {e}");
+                None
+            }
+        }
+    }
+
+    /// The token a request passes, as a bearer token or in the sign-in cookie.
+    fn request_token(headers: &HeaderMap) -> Option<&str> {
+        let bearer = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+        let cookie = || {
+            headers
+                .get_all(header::COOKIE)
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+                .flat_map(|cookies| cookies.split(';'))
+                .find_map(|cookie| cookie.trim().strip_prefix(TOKEN_COOKIE)?.strip_prefix('='))
+        };
+        bearer.or_else(cookie).map(str::trim)
+    }
+
+    /// The user making a request, `None` when its token is missing or wrong, or the
+    /// server is open.
+    pub async fn request_user(headers: &HeaderMap) -> Option<User> {
+        user_with_token(request_token(headers)?).await
+    }
+
+    /// Whether a request may go on: it has a user's token, or the server is open.
+    pub async fn allowed(headers: &HeaderMap) -> bool {
+        request_user(headers).await.is_some() || is_open().await
+    }
+
+    /// The user making the request a server function or page is running for, `None`
+    /// when the server is open or the function is called outside a request.
+    pub async fn current_user() -> Option<User> {
+        // leptos_axum gives server functions and pages the request's parts
+        let parts = use_context::<leptos_axum::RequestParts>()?;
+        request_user(&parts.headers).await
+    }
+
+    /// Middleware turning away requests without a user's token, server functions
+    /// and files with a 401, and pages to `/sign-in`.
+    pub async fn require_user<B>(request: Request<B>, next: Next<B>) -> Response {
+        if allowed(request.headers()).await {
+            next.run(request).await
+        } else if request.uri().path().starts_with("/api/") {
+            (
+                StatusCode::UNAUTHORIZED,
+                [(header::WWW_AUTHENTICATE, "Bearer")],
+                "missing or wrong token",
+            )
+                .into_response()
+        } else {
+            Redirect::to("/sign-in").into_response()
+        }
+    }
+
+    pub fn sign_in_routes<S: Clone + Send + Sync + 'static>() -> Router<S> {
+        Router::new()
+            .route("/sign-in", get(|| async { sign_in_page(false) }).post(sign_in))
+            .route("/sign-out", get(sign_out))
+    }
+
+    #[derive(Deserialize)]
+    struct SignIn {
+        token: String,
+    }
+
+    async fn sign_in(Form(form): Form<SignIn>) -> Response {
+        let token = form.token.trim();
+        if user_with_token(token).await.is_none() {
+            return (StatusCode::UNAUTHORIZED, sign_in_page(true)).into_response();
+        }
+        let cookie = format!("{TOKEN_COOKIE}={token}; Path=/; HttpOnly; SameSite=Strict");
+        ([(header::SET_COOKIE, cookie)], Redirect::to("/")).into_response()
+    }
+
+    async fn sign_out() -> Response {
+        let cookie = format!("{TOKEN_COOKIE}=; Path=/; HttpOnly; SameSite=Strict; Max-Age=0");
+        ([(header::SET_COOKIE, cookie)], Redirect::to("/sign-in")).into_response()
+    }
+
+    fn sign_in_page(wrong: bool) -> Html<String> {
+        let error = if wrong {
+            r#"<div class="alert alert-danger">That token isn't one of the server's users'.</div>"#
+        } else {
+            ""
+        };
+        Html(format!(
+            r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8"/>
+<meta name="viewport" content="width=device-width, initial-scale=1"/>
+<title>Sign in</title>
+<link rel="stylesheet" href="/css/bootstrap.min.css"/>
+</head>
+<body class="container py-5" style="max-width: 28rem">
+<h1 class="h3 mb-3">Sign in</h1>
+{error}
+<form method="post" action="/sign-in">
+<label class="form-label" for="token">Token</label>
+<input class="form-control mb-3" id="token" name="token" type="password" autofocus required/>
+<button class="btn btn-primary" type="submit">Sign in</button>
+</form>
+</body>
+</html>
+"#
+        ))
+    }
+}}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_the_token_in_the_header_or_the_cookie() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(request_token(&headers), None);
+
+        headers.insert(
+            header::COOKIE,
+            "theme=dark; todos_token=s3cret ".parse().unwrap(),
+        );
+        assert_eq!(request_token(&headers), Some("s3cret"));
+
+        headers.insert(header::AUTHORIZATION, "Bearer t0ken".parse().unwrap());
+        assert_eq!(request_token(&headers), Some("t0ken"));
+    }
+
+    #[test]
+    fn issues_tokens_that_fit_in_headers_and_keeps_their_hash() {
+        let token = new_token();
+        assert_eq!(token.len(), 43);
+        assert!(token
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_'));
+        assert_ne!(new_token(), token);
+
+        assert_eq!(
+            token_hash("e2e-token"),
+            format!("{:x}", Sha256::digest(b"e2e-token"))
+        );
+        assert_eq!(token_hash(&token).len(), 64);
+        assert_ne!(token_hash(&token), token_hash(&new_token()));
+    }
+}
@@ -0,0 +1,1278 @@
+use crate::app::{list_name, todo_count, TodoItem};
+use crate::importers::Source;
+use cfg_if::cfg_if;
+use leptos::*;
+use serde::{Deserialize, Serialize};
+
+/// Version of the [`Backup`] format, bumped when it changes incompatibly.
+pub const BACKUP_FORMAT: u32 = 1;
+
+/// Unmapped parts of an import listed in the dropdown, the rest are only counted.
+const MAX_UNMAPPED: usize = 10;
+
+/// Every todo, as exported from `/api/export.json` and imported by [`import_todos`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Backup {
+    pub(crate) format: u32,
+    pub(crate) todos: Vec<TodoItem>,
+}
+
+impl Backup {
+    /// A backup of `todos` in the current [`BACKUP_FORMAT`].
+    pub fn new(todos: Vec<TodoItem>) -> Backup {
+        Backup {
+            format: BACKUP_FORMAT,
+            todos,
+        }
+    }
+}
+
+/// The kinds of file todos are exported to and imported from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileType {
+    /// A [`Backup`].
+    #[default]
+    Json,
+    /// A header row naming the columns, then a todo per row.
+    Csv,
+    /// A todo per line, see [`crate::todo_txt`].
+    TodoTxt,
+    /// A checklist, see [`crate::markdown`].
+    Markdown,
+}
+
+impl FileType {
+    pub const ALL: [FileType; 4] = [
+        FileType::Json,
+        FileType::Csv,
+        FileType::TodoTxt,
+        FileType::Markdown,
+    ];
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            FileType::Json => "json",
+            FileType::Csv => "csv",
+            FileType::TodoTxt => "txt",
+            FileType::Markdown => "md",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            FileType::Json => "JSON",
+            FileType::Csv => "CSV",
+            FileType::TodoTxt => "todo.txt",
+            FileType::Markdown => "Markdown",
+        }
+    }
+
+    /// Tells the type of a file from the extension of its name.
+    pub fn from_name(name: &str) -> Option<FileType> {
+        let (_, extension) = name.rsplit_once('.')?;
+        FileType::ALL
+            .into_iter()
+            .find(|file_type| file_type.extension().eq_ignore_ascii_case(extension))
+    }
+}
+
+/// What an import does with the todos already there.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportMode {
+    /// Keeps them, skipping imported todos with the same task as one already there.
+    #[default]
+    Merge,
+    /// Deletes them first.
+    Replace,
+}
+
+impl ImportMode {
+    pub const ALL: [ImportMode; 2] = [ImportMode::Merge, ImportMode::Replace];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ImportMode::Merge => "merge",
+            ImportMode::Replace => "replace",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ImportMode::Merge => "Merge",
+            ImportMode::Replace => "Replace all",
+        }
+    }
+}
+
+/// What an import changed, or would change on a dry run.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportSummary {
+    pub(crate) added: usize,
+    pub(crate) skipped: usize,
+    pub(crate) deleted: usize,
+    pub(crate) dry_run: bool,
+    /// What of a file from another app couldn't be imported, see [`Source`].
+    #[serde(default)]
+    pub(crate) unmapped: Vec<String>,
+}
+
+impl ImportSummary {
+    pub fn describe(&self) -> String {
+        if self.dry_run {
+            format!(
+                "Would add {}, skip {} already there and delete {}",
+                self.added, self.skipped, self.deleted
+            )
+        } else {
+            format!(
+                "Added {}, skipped {} already there and deleted {}",
+                self.added, self.skipped, self.deleted
+            )
+        }
+    }
+
+    pub fn unmapped(&self) -> &[String] {
+        &self.unmapped
+    }
+}
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use crate::app::{check_due, check_task, db, parse_tags, todos_changed, Priority};
+        use crate::query::Query as Filter;
+        use crate::{csv, dates, markdown, todo_txt};
+        use axum::{
+            body::StreamBody,
+            extract::Query,
+            http::{header, StatusCode},
+            response::{IntoResponse, Response},
+            routing::{get, post},
+            Json, Router,
+        };
+        use futures::stream::{self, StreamExt};
+        use sqlx::SqlitePool;
+        use std::collections::{HashMap, HashSet};
+
+        /// Import errors listed before the rest are only counted.
+        const MAX_ERRORS: usize = 10;
+
+        /// Columns of exported CSV files, imports only need `task`. Tags are separated
+        /// by spaces.
+        const CSV_COLUMNS: [&str; 8] =
+            ["id", "task", "done", "version", "priority", "due", "tags", "list"];
+
+        /// Todos read from the database at a time while exporting.
+        const EXPORT_BATCH: u32 = 500;
+
+        /// Streams every todo matching `filter`, in batches so large lists aren't held
+        /// in memory, each written by `write` along with whether it's the first.
+        async fn export(
+            filter: &str,
+            content_type: &'static str,
+            filename: &'static str,
+            start: String,
+            write: fn(&TodoItem, bool) -> String,
+            end: &'static str,
+        ) -> Response {
+            let (conditions, params) = match Filter::parse(filter) {
+                Ok(filter) => filter.to_sql(),
+                Err(e) => {
+                    return (StatusCode::BAD_REQUEST, format!("invalid filter: {e}")).into_response()
+                }
+            };
+            let sql = format!(
+                "SELECT * FROM todos WHERE id > ? AND ({conditions}) ORDER BY id LIMIT ?"
+            );
+
+            let pool = match db().await {
+                Ok(pool) => pool,
+                Err(e) => {
+                    return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+                }
+            };
+
+            // the id to continue after, `None` once done
+            let batches = stream::unfold(Some(0), move |after| {
+                let pool = pool.clone();
+                let sql = sql.clone();
+                let params = params.clone();
+                async move {
+                    let after = after?;
+                    let todos = params
+                        .into_iter()
+                        .fold(sqlx::query_as::<_, TodoItem>(&sql).bind(after), |select, param| {
+                            select.bind(param)
+                        })
+                        .bind(EXPORT_BATCH)
+                        .fetch_all(&pool)
+                        .await;
+
+                    match todos {
+                        Ok(todos) if todos.is_empty() => None,
+                        Ok(todos) => {
+                            let next = todos.last().map(|todo| todo.id);
+                            let chunk: String = todos
+                                .iter()
+                                .enumerate()
+                                .map(|(i, todo)| write(todo, after == 0 && i == 0))
+                                .collect();
+                            Some((Ok(chunk), next))
+                        }
+                        // ends the response early, so the download fails instead of
+                        // leaving a file that's missing todos
+                        Err(e) => Some((Err(std::io::Error::other(e)), None)),
+                    }
+                }
+            });
+
+            let body = stream::once(async { Ok(start) })
+                .chain(batches)
+                .chain(stream::once(async { Ok(end.to_string()) }));
+
+            (
+                [
+                    (header::CONTENT_TYPE, content_type.to_string()),
+                    (
+                        header::CONTENT_DISPOSITION,
+                        format!("attachment; filename=\"{filename}\""),
+                    ),
+                ],
+                StreamBody::new(body),
+            )
+                .into_response()
+        }
+
+        /// Every todo as a [`Backup`].
+        async fn export_json(filter: &str) -> Response {
+            export(
+                filter,
+                "application/json",
+                "todos.json",
+                format!("{{\"format\":{BACKUP_FORMAT},\"todos\":["),
+                |todo, first| {
+                    let todo = serde_json::to_string(todo).unwrap_or_default();
+                    if first {
+                        todo
+                    } else {
+                        format!(",{todo}")
+                    }
+                },
+                "]}",
+            )
+            .await
+        }
+
+        /// Every todo as CSV with a header row, starting with a BOM so spreadsheets
+        /// read it as UTF-8.
+        async fn export_csv(filter: &str) -> Response {
+            export(
+                filter,
+                "text/csv; charset=utf-8",
+                "todos.csv",
+                format!("{}{}", csv::BOM, csv::row(CSV_COLUMNS)),
+                |todo, _| {
+                    csv::row([
+                        todo.id.to_string().as_str(),
+                        todo.task.as_str(),
+                        if todo.done { "true" } else { "false" },
+                        todo.version.to_string().as_str(),
+                        todo.priority.map_or("", Priority::as_str),
+                        todo.due.as_deref().unwrap_or_default(),
+                        todo.tags.join(" ").as_str(),
+                        todo.list.as_deref().unwrap_or_default(),
+                    ])
+                },
+                "",
+            )
+            .await
+        }
+
+        async fn export_todo_txt(filter: &str) -> Response {
+            export(
+                filter,
+                "text/plain; charset=utf-8",
+                "todo.txt",
+                String::new(),
+                |todo, _| format!("{}\n", todo_txt::format_line(todo)),
+                "",
+            )
+            .await
+        }
+
+        /// Unlike the other files, checklists are written once every todo is read,
+        /// as subtasks go under their todo.
+        async fn export_markdown(filter: &str) -> Response {
+            match checklist(filter).await {
+                Ok(checklist) => (
+                    [
+                        (header::CONTENT_TYPE, "text/markdown; charset=utf-8"),
+                        (header::CONTENT_DISPOSITION, "attachment; filename=\"todos.md\""),
+                    ],
+                    checklist,
+                )
+                    .into_response(),
+                Err(ServerFnError::Args(message)) => {
+                    (StatusCode::BAD_REQUEST, message).into_response()
+                }
+                Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, error_message(e)).into_response(),
+            }
+        }
+
+        /// The todos matching `filter` as a Markdown checklist, see [`markdown::checklist`].
+        async fn checklist(filter: &str) -> Result<String, ServerFnError> {
+            let (conditions, params) = match Filter::parse(filter) {
+                Ok(filter) => filter.to_sql(),
+                Err(e) => return Err(ServerFnError::Args(format!("invalid filter: {e}"))),
+            };
+            let pool = db().await?;
+
+            let sql = format!("SELECT * FROM todos WHERE {conditions} ORDER BY id");
+            match params
+                .into_iter()
+                .fold(sqlx::query_as::<_, TodoItem>(&sql), |select, param| {
+                    select.bind(param)
+                })
+                .fetch_all(&pool)
+                .await
+            {
+                Ok(todos) => Ok(markdown::checklist(&todos)),
+                Err(e) => Err(ServerFnError::ServerError(e.to_string())),
+            }
+        }
+
+        /// Every todo matching `filter` as a file of `file_type`, streamed as it's read,
+        /// for downloads and the server's `export` command.
+        pub async fn export_file(file_type: FileType, filter: &str) -> Response {
+            match file_type {
+                FileType::Json => export_json(filter).await,
+                FileType::Csv => export_csv(filter).await,
+                FileType::TodoTxt => export_todo_txt(filter).await,
+                FileType::Markdown => export_markdown(filter).await,
+            }
+        }
+
+        /// Options of `/api/export.<extension>` in its query string.
+        #[derive(Deserialize)]
+        struct ExportOptions {
+            /// Only the todos matching this filter, like `+work is:open`.
+            #[serde(default)]
+            q: String,
+        }
+
+        /// Options of `/api/import.<extension>` in its query string.
+        #[derive(Deserialize)]
+        struct ImportOptions {
+            #[serde(default)]
+            mode: ImportMode,
+            /// The list todos go into when the file doesn't put them in one.
+            #[serde(default)]
+            list: Option<String>,
+            #[serde(default)]
+            dry_run: bool,
+        }
+
+        async fn import_file(
+            file: String,
+            file_type: FileType,
+            source: Option<Source>,
+            options: ImportOptions,
+        ) -> Result<Json<ImportSummary>, (StatusCode, String)> {
+            let summary = match check_import(&file, file_type, source) {
+                Ok((todos, unmapped)) => {
+                    import(todos, unmapped, options.mode, options.list, options.dry_run).await
+                }
+                Err(e) => Err(e),
+            };
+            summary.map(Json).map_err(|e| match e {
+                ServerFnError::Args(message) => (StatusCode::BAD_REQUEST, message),
+                e => (StatusCode::INTERNAL_SERVER_ERROR, error_message(e)),
+            })
+        }
+
+        /// Downloads of every todo at `/api/export.<extension>`, or those matching
+        /// `?q=`, like `?q=list:work` for a list, and uploads at
+        /// `/api/import.<extension>` taking the file as the body, and `mode`, `list`
+        /// and `dry_run` in the query string like [`import_todos`].
+        /// Files of other apps are uploaded to `/api/import/<source>`, like
+        /// `/api/import/todoist`.
+        pub fn file_routes<S: Clone + Send + Sync + 'static>() -> Router<S> {
+            let router = Source::ALL.into_iter().fold(Router::new(), |router, source| {
+                router.route(
+                    &format!("/api/import/{}", source.as_str()),
+                    post(move |Query(options): Query<ImportOptions>, file: String| {
+                        import_file(file, FileType::default(), Some(source), options)
+                    }),
+                )
+            });
+
+            FileType::ALL.into_iter().fold(router, |router, file_type| {
+                let extension = file_type.extension();
+                router
+                    .route(
+                        &format!("/api/export.{extension}"),
+                        get(move |Query(options): Query<ExportOptions>| async move {
+                            export_file(file_type, &options.q).await
+                        }),
+                    )
+                    .route(
+                        &format!("/api/import.{extension}"),
+                        post(move |Query(options): Query<ImportOptions>, file: String| {
+                            import_file(file, file_type, None, options)
+                        }),
+                    )
+            })
+        }
+
+        /// Maps the rows of a CSV file to todos by the names in its header row, a
+        /// `task` or `title` column, and optional `done` or `completed`, `priority`,
+        /// `due` and `tags` ones. Other columns, like the ids of an export, are ignored.
+        fn check_csv(input: &str) -> Result<Vec<TodoItem>, Vec<String>> {
+            let mut records = csv::parse(input).map_err(|e| vec![e.to_string()])?.into_iter();
+            let Some(header) = records.next() else {
+                return Ok(vec![]);
+            };
+            let column = |names: &[&str]| {
+                header
+                    .fields
+                    .iter()
+                    .position(|name| names.contains(&name.trim().to_lowercase().as_str()))
+            };
+            let Some(task_column) = column(&["task", "title"]) else {
+                return Err(vec![format!(
+                    "line {}: no `task` column in the header",
+                    header.line
+                )]);
+            };
+            let done_column = column(&["done", "completed"]);
+            let priority_column = column(&["priority"]);
+            let due_column = column(&["due", "due date"]);
+            let tags_column = column(&["tags"]);
+            let list_column = column(&["list"]);
+
+            let mut errors = vec![];
+            let mut todos = vec![];
+            for record in records {
+                let field = |column: Option<usize>| {
+                    column
+                        .and_then(|column| record.fields.get(column))
+                        .map_or("", String::as_str)
+                };
+                let task = check_task(field(Some(task_column))).map_err(error_message);
+                let done = done_column.map_or(Ok(false), |column| parse_done(field(Some(column))));
+                let priority = match field(priority_column).trim().to_lowercase().as_str() {
+                    "" => Ok(None),
+                    priority => priority.parse::<Priority>().map(Some),
+                };
+                let due = check_due(Some(field(due_column))).map_err(error_message);
+                match (task, done, priority, due) {
+                    (Ok(task), Ok(done), Ok(priority), Ok(due)) => todos.push(TodoItem {
+                        task,
+                        done,
+                        priority,
+                        due,
+                        tags: parse_tags(field(tags_column)),
+                        list: list_name(field(list_column)),
+                        ..TodoItem::default()
+                    }),
+                    (task, done, priority, due) => errors.extend(
+                        [task.err(), done.err(), priority.err(), due.err()]
+                            .into_iter()
+                            .flatten()
+                            .map(|e| format!("line {}: {e}", record.line)),
+                    ),
+                }
+            }
+
+            if errors.is_empty() {
+                Ok(todos)
+            } else {
+                Err(errors)
+            }
+        }
+
+        /// Checks every todo of a todo.txt file.
+        fn check_todo_txt(input: &str) -> Result<Vec<TodoItem>, Vec<String>> {
+            check_lines(
+                todo_txt::parse(input)
+                    .into_iter()
+                    .map(|line| (line.line, line.todo, None)),
+            )
+        }
+
+        /// Checks every item of a Markdown checklist, with nested items as subtasks.
+        fn check_markdown(input: &str) -> Result<Vec<TodoItem>, Vec<String>> {
+            check_lines(markdown::parse(input).into_iter().map(|item| {
+                let todo = TodoItem {
+                    task: item.task,
+                    done: item.done,
+                    ..TodoItem::default()
+                };
+                (item.line, todo, item.parent)
+            }))
+        }
+
+        /// Checks the todos of files with a todo per line, given with their line and
+        /// the index of the one they're a subtask of. The todos' ids are their place,
+        /// counting from 1, for subtasks to point at theirs.
+        fn check_lines(
+            lines: impl IntoIterator<Item = (usize, TodoItem, Option<usize>)>,
+        ) -> Result<Vec<TodoItem>, Vec<String>> {
+            let mut errors = vec![];
+            let mut todos = vec![];
+            for (place, (line, todo, parent)) in (1..).zip(lines) {
+                match (check_task(&todo.task), check_due(todo.due.as_deref())) {
+                    (Ok(task), Ok(due)) => todos.push(TodoItem {
+                        id: place,
+                        task,
+                        due,
+                        list: todo.list.as_deref().and_then(list_name),
+                        parent: parent.map(|parent| parent as u32 + 1),
+                        ..todo
+                    }),
+                    (task, due) => errors.extend(
+                        [task.err(), due.err()]
+                            .into_iter()
+                            .flatten()
+                            .map(|e| format!("line {line}: {}", error_message(e))),
+                    ),
+                }
+            }
+
+            if errors.is_empty() {
+                Ok(todos)
+            } else {
+                Err(errors)
+            }
+        }
+
+        /// Checks every todo of a file, listing what's wrong with each in the error.
+        fn check_file(
+            file: &str,
+            file_type: FileType,
+        ) -> Result<Vec<TodoItem>, ServerFnError> {
+            let todos = match file_type {
+                FileType::Json => match serde_json::from_str::<Backup>(file) {
+                    Ok(backup) => check_backup(&backup),
+                    Err(e) => return Err(ServerFnError::Args(format!("invalid backup: {e}"))),
+                },
+                FileType::Csv => check_csv(file),
+                FileType::TodoTxt => check_todo_txt(file),
+                FileType::Markdown => check_markdown(file),
+            };
+            todos.map_err(import_errors)
+        }
+
+        /// Checks every todo of a file, one of `file_type` or exported from `source`,
+        /// along with what of it couldn't be mapped.
+        fn check_import(
+            file: &str,
+            file_type: FileType,
+            source: Option<Source>,
+        ) -> Result<(Vec<TodoItem>, Vec<String>), ServerFnError> {
+            let Some(source) = source else {
+                return Ok((check_file(file, file_type)?, vec![]));
+            };
+            let converted = source.convert(file).map_err(ServerFnError::Args)?;
+
+            let mut errors = vec![];
+            let mut todos = vec![];
+            for (place, task) in (1..).zip(converted.tasks) {
+                match check_task(&task.task) {
+                    Ok(checked) => todos.push(TodoItem {
+                        id: place,
+                        task: checked,
+                        done: task.done,
+                        priority: task.priority,
+                        due: task.due,
+                        tags: task.tags,
+                        list: task.list,
+                        parent: task.parent.map(|parent| parent as u32 + 1),
+                        ..TodoItem::default()
+                    }),
+                    Err(e) => errors.push(format!("{}: {}", task.place, error_message(e))),
+                }
+            }
+
+            if errors.is_empty() {
+                Ok((todos, converted.unmapped))
+            } else {
+                Err(import_errors(errors))
+            }
+        }
+
+        /// Checks a backup's `created` or `completed` date, `None` for none.
+        fn check_date(name: &str, date: Option<&str>) -> Result<Option<String>, ServerFnError> {
+            match date {
+                None => Ok(None),
+                Some(date) if dates::is_date(date) => Ok(Some(date.to_string())),
+                Some(date) => Err(ServerFnError::Args(format!(
+                    "{name} date `{date}` isn't a YYYY-MM-DD date"
+                ))),
+            }
+        }
+
+        fn parse_done(value: &str) -> Result<bool, String> {
+            match value.trim().to_lowercase().as_str() {
+                "true" | "yes" | "y" | "1" | "x" | "done" => Ok(true),
+                "false" | "no" | "n" | "0" | "" => Ok(false),
+                _ => Err(format!("`done` is `{}`, expected true or false", value.trim())),
+            }
+        }
+
+        /// Checks every todo of a backup, listing what's wrong with each.
+        fn check_backup(backup: &Backup) -> Result<Vec<TodoItem>, Vec<String>> {
+            if backup.format == 0 || backup.format > BACKUP_FORMAT {
+                return Err(vec![format!(
+                    "unsupported backup format {}, expected {BACKUP_FORMAT}",
+                    backup.format
+                )]);
+            }
+
+            let mut ids = HashSet::new();
+            let mut errors = vec![];
+            let mut todos = vec![];
+            for todo in &backup.todos {
+                // imported in order, a subtask's todo has to be in first
+                if let Some(parent) = todo.parent.filter(|parent| !ids.contains(parent)) {
+                    errors.push(format!("todo {}: todo {parent} isn't before it", todo.id));
+                }
+                if !ids.insert(todo.id) {
+                    errors.push(format!("todo {} appears more than once", todo.id));
+                }
+                let task = check_task(&todo.task);
+                let due = check_due(todo.due.as_deref());
+                let created = check_date("created", todo.created.as_deref());
+                let completed = check_date("completed", todo.completed.as_deref());
+                match (task, due, created, completed) {
+                    (Ok(task), Ok(due), Ok(created), Ok(completed)) => todos.push(TodoItem {
+                        task,
+                        due,
+                        tags: parse_tags(&todo.tags.join(" ")),
+                        list: todo.list.as_deref().and_then(list_name),
+                        created,
+                        completed,
+                        ..todo.clone()
+                    }),
+                    (task, due, created, completed) => errors.extend(
+                        [task.err(), due.err(), created.err(), completed.err()]
+                            .into_iter()
+                            .flatten()
+                            .map(|e| format!("todo {}: {}", todo.id, error_message(e))),
+                    ),
+                }
+            }
+
+            if errors.is_empty() {
+                Ok(todos)
+            } else {
+                Err(errors)
+            }
+        }
+
+        fn error_message(e: ServerFnError) -> String {
+            match e {
+                ServerFnError::Args(message) | ServerFnError::ServerError(message) => message,
+                e => e.to_string(),
+            }
+        }
+
+        /// Lists import errors as one, the first [`MAX_ERRORS`] of them in full.
+        fn import_errors(errors: Vec<String>) -> ServerFnError {
+            let mut message = errors[..errors.len().min(MAX_ERRORS)].join("; ");
+            if errors.len() > MAX_ERRORS {
+                message.push_str(&format!("; and {} more", errors.len() - MAX_ERRORS));
+            }
+            ServerFnError::Args(message)
+        }
+
+        /// [`import_into`] the server's database.
+        async fn import(
+            todos: Vec<TodoItem>,
+            unmapped: Vec<String>,
+            mode: ImportMode,
+            list: Option<String>,
+            dry_run: bool,
+        ) -> Result<ImportSummary, ServerFnError> {
+            import_into(&db().await?, todos, unmapped, mode, list, dry_run).await
+        }
+
+        /// Adds checked todos in order with new ids and versions, in one transaction,
+        /// which is rolled back on a dry run. Todos the file doesn't put in a list go
+        /// into `list`.
+        async fn import_into(
+            pool: &SqlitePool,
+            todos: Vec<TodoItem>,
+            unmapped: Vec<String>,
+            mode: ImportMode,
+            list: Option<String>,
+            dry_run: bool,
+        ) -> Result<ImportSummary, ServerFnError> {
+            let list = list.as_deref().and_then(list_name);
+            let mut tx = pool.begin().await?;
+
+            let mut summary = ImportSummary {
+                dry_run,
+                unmapped,
+                ..ImportSummary::default()
+            };
+
+            if mode == ImportMode::Replace {
+                summary.deleted = sqlx::query("DELETE FROM todos")
+                    .execute(&mut *tx)
+                    .await?
+                    .rows_affected() as usize;
+            }
+
+            // ids of the tasks already there, read once rather than looked up for every
+            // todo
+            let mut existing: HashMap<String, u32> = if mode == ImportMode::Merge {
+                sqlx::query_as::<_, (String, u32)>("SELECT task, id FROM todos")
+                    .fetch_all(&mut *tx)
+                    .await?
+                    .into_iter()
+                    .collect()
+            } else {
+                HashMap::new()
+            };
+            // the ids todos got, by their id in the file, for subtasks to find theirs
+            let mut ids = HashMap::new();
+
+            for todo in todos {
+                // also skips repeats within the import, their subtasks go under the
+                // todo already there
+                if let Some(&id) = existing.get(&todo.task) {
+                    ids.insert(todo.id, id);
+                    summary.skipped += 1;
+                    continue;
+                }
+
+                let parent = todo.parent.and_then(|parent| ids.get(&parent).copied());
+                let id: u32 = sqlx::query_scalar(
+                    "INSERT INTO todos
+                    (task, done, priority, due, tags, list, parent, created, completed)
+                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, CASE WHEN ?2 THEN ?9 END)
+                    RETURNING id",
+                )
+                .bind(&todo.task)
+                .bind(todo.done)
+                .bind(todo.priority)
+                .bind(todo.due)
+                .bind(sqlx::types::Json(todo.tags))
+                .bind(todo.list.or_else(|| list.clone()))
+                .bind(parent)
+                .bind(todo.created)
+                .bind(todo.completed)
+                .fetch_one(&mut *tx)
+                .await?;
+                ids.insert(todo.id, id);
+                if mode == ImportMode::Merge {
+                    existing.insert(todo.task, id);
+                }
+                summary.added += 1;
+            }
+
+            if dry_run {
+                tx.rollback().await?;
+            } else {
+                tx.commit().await?;
+                todos_changed();
+            }
+
+            Ok(summary)
+        }
+    }
+}
+
+/// Imports todos from a file of `file_type`, or exported from another app when
+/// `source` is set, all or nothing, with new ids, into `list` unless the file puts
+/// them in one. A dry run only reports what it would do.
+#[server(ImportTodos, "/api")]
+pub async fn import_todos(
+    file: String,
+    file_type: FileType,
+    source: Option<Source>,
+    list: Option<String>,
+    mode: ImportMode,
+    dry_run: bool,
+) -> Result<ImportSummary, ServerFnError> {
+    let (todos, unmapped) = check_import(&file, file_type, source)?;
+    import(todos, unmapped, mode, list, dry_run).await
+}
+
+/// The todos matching `filter` as a Markdown checklist, with subtasks nested under
+/// their todo.
+#[server(MarkdownChecklist, "/api")]
+pub async fn markdown_checklist(filter: String) -> Result<String, ServerFnError> {
+    checklist(&filter).await
+}
+
+cfg_if! {
+    if #[cfg(feature = "hydrate")] {
+        use wasm_bindgen::prelude::wasm_bindgen;
+        use wasm_bindgen_futures::js_sys::Promise;
+
+        #[wasm_bindgen]
+        extern "C" {
+            // `navigator.clipboard` is missing outside of secure contexts, so this throws
+            #[wasm_bindgen(catch, js_namespace = ["navigator", "clipboard"], js_name = writeText)]
+            fn write_text(text: &str) -> Result<Promise, wasm_bindgen::JsValue>;
+        }
+
+        async fn copy_text(text: &str) -> Result<(), String> {
+            let copied = match write_text(text) {
+                Ok(promise) => wasm_bindgen_futures::JsFuture::from(promise).await,
+                Err(e) => Err(e),
+            };
+            copied.map(|_| ()).map_err(|_| "couldn't copy to the clipboard".to_string())
+        }
+
+        /// Reads the file picked in a file input as text, with its type told by its
+        /// name, `None` when there's none.
+        fn read_file(ev: ev::Event, content: RwSignal<Option<(FileType, String)>>) {
+            let file = event_target::<web_sys::HtmlInputElement>(&ev)
+                .files()
+                .and_then(|files| files.get(0));
+            let Some(file) = file else {
+                content.set(None);
+                return;
+            };
+
+            // unknown types are read as JSON, and fail as that if they aren't
+            let file_type = FileType::from_name(&file.name()).unwrap_or_default();
+            spawn_local(async move {
+                let text = wasm_bindgen_futures::JsFuture::from(file.text()).await;
+                let text = text.ok().and_then(|text| text.as_string());
+                content.set(text.map(|text| (file_type, text)));
+            });
+        }
+    } else {
+        async fn copy_text(_text: &str) -> Result<(), String> {
+            Err("couldn't copy to the clipboard".into())
+        }
+
+        fn read_file(_ev: ev::Event, _content: RwSignal<Option<(FileType, String)>>) {}
+    }
+}
+
+/// Button copying the todos bulk actions apply to as a Markdown checklist, to paste
+/// into issues and docs.
+#[component]
+pub fn CopyMarkdown(scope: Memo<Option<String>>) -> impl IntoView {
+    let copy = create_action(move |filter: &String| {
+        let filter = filter.clone();
+        async move {
+            let checklist = markdown_checklist(filter)
+                .await
+                .map_err(|e| e.to_string())?;
+            copy_text(&checklist).await?;
+            Ok::<_, String>(checklist.lines().count())
+        }
+    });
+
+    view! {
+        <button type="button" class="btn btn-outline-secondary mx-3"
+            title="Copy as a Markdown checklist"
+            disabled=move || scope.with(Option::is_none) || copy.pending().get()
+            on:click=move |_| {
+                if let Some(filter) = scope.get_untracked() {
+                    copy.dispatch(filter);
+                }
+            }>
+            <i class="bi bi-clipboard me-1"></i>
+            {move || match copy.value().get() {
+                Some(Ok(count)) => format!("Copied {}", todo_count(count)),
+                Some(Err(_)) => "Copy failed".to_string(),
+                None => "Copy Markdown".to_string(),
+            }}
+        </button>
+    }
+}
+
+/// Navbar dropdown to export every todo, and to import a file after previewing what
+/// it changes.
+#[component]
+pub fn Backups(
+    import_todos: Action<ImportTodos, Result<ImportSummary, leptos::ServerFnError>>,
+) -> impl IntoView {
+    let content = create_rw_signal::<Option<(FileType, String)>>(None);
+    // the app the file was exported from, `None` for this one
+    let source = create_rw_signal::<Option<Source>>(None);
+    let mode = create_rw_signal(ImportMode::default());
+    // the list todos go into when the file doesn't say
+    let list = create_rw_signal(String::new());
+
+    // importing is only offered once the file, source and mode at hand were previewed
+    let previewed = create_rw_signal(false);
+    create_effect(move |_| {
+        content.track();
+        source.track();
+        list.track();
+        mode.track();
+        previewed.set(false);
+    });
+    create_effect(move |_| {
+        if let Some(Ok(summary)) = import_todos.value().get() {
+            previewed.set(summary.dry_run);
+        }
+    });
+
+    let accept = FileType::ALL
+        .map(|file_type| format!(".{}", file_type.extension()))
+        .join(",");
+
+    let dispatch = move |dry_run: bool| {
+        if let Some((file_type, file)) = content.get_untracked() {
+            import_todos.dispatch(ImportTodos {
+                file,
+                file_type,
+                source: source.get_untracked(),
+                list: list_name(&list.get_untracked()),
+                mode: mode.get_untracked(),
+                dry_run,
+            });
+        }
+    };
+
+    view! {
+        <li class="nav-item dropdown">
+            <a class="nav-link dropdown-toggle" href="#" role="button"
+                data-bs-toggle="dropdown" data-bs-auto-close="outside" aria-expanded="false">
+                <i class="bi bi-archive me-1"></i> Backup
+            </a>
+
+            <ul class="dropdown-menu">
+                {FileType::ALL.map(|file_type| view! {
+                    <li>
+                        <a class="dropdown-item" rel="external" download
+                            href=format!("/api/export.{}", file_type.extension())>
+                            <i class="bi bi-download me-1"></i> "Export " {file_type.label()}
+                        </a>
+                    </li>
+                }).collect_view()}
+                <li>
+                    <a class="dropdown-item" rel="external" href="/api/todos.ics"
+                        title="Subscribe to this link, with ?token= and your token, in a calendar \
+                            app, or to /api/lists/<list>.ics for a list">
+                        <i class="bi bi-calendar-check me-1"></i> Calendar feed
+                    </a>
+                </li>
+
+                <li><hr class="dropdown-divider"/></li>
+                <li class="px-3 py-1">
+                    <select class="form-select form-select-sm mb-2" aria-label="Exported from"
+                        on:change=move |ev| {
+                            let value = event_target_value(&ev);
+                            source.set(Source::ALL.into_iter().find(|found| found.as_str() == value));
+                        }>
+                        <option value="" selected>"From this app, by extension"</option>
+                        {Source::ALL.map(|option| view! {
+                            <option value=option.as_str()>"From " {option.label()}</option>
+                        }).collect_view()}
+                    </select>
+                    <input type="file" accept=accept
+                        class="form-control form-control-sm mb-2"
+                        on:change=move |ev| read_file(ev, content)/>
+                    <input type="text" class="form-control form-control-sm mb-2"
+                        placeholder="Into list" aria-label="Into list"
+                        title="The list todos go into when the file doesn't put them in one"
+                        prop:value=move || list.get()
+                        on:input=move |ev| list.set(event_target_value(&ev))/>
+
+                    <div class="btn-group btn-group-sm w-100 mb-2" role="group">
+                        {ImportMode::ALL.map(|option| view! {
+                            <button type="button" class="btn btn-outline-secondary"
+                                class:active=move || mode.get() == option
+                                on:click=move |_| mode.set(option)>
+                                {option.label()}
+                            </button>
+                        }).collect_view()}
+                    </div>
+
+                    <div class="d-flex">
+                        <button type="button" class="btn btn-sm btn-outline-secondary me-2"
+                            disabled=move || content.with(Option::is_none) || import_todos.pending().get()
+                            on:click=move |_| dispatch(true)>
+                            Preview
+                        </button>
+                        <button type="button" class="btn btn-sm btn-outline-warning"
+                            disabled=move || !previewed.get() || import_todos.pending().get()
+                            on:click=move |_| dispatch(false)>
+                            Import
+                        </button>
+                    </div>
+                </li>
+
+                {move || import_todos.value().get().map(|result| match result {
+                    Ok(summary) => {
+                        let more = summary.unmapped.len().saturating_sub(MAX_UNMAPPED);
+                        let unmapped = (!summary.unmapped.is_empty()).then(|| view! {
+                            <div class="text-warning mt-1">"Couldn't map:"</div>
+                            <ul class="mb-0 ps-3">
+                                {summary.unmapped.iter().take(MAX_UNMAPPED).map(|message| view! {
+                                    <li>{message.clone()}</li>
+                                }).collect_view()}
+                                {(more > 0).then(|| view! { <li>"and " {more} " more"</li> })}
+                            </ul>
+                        });
+                        view! {
+                            <li class="dropdown-item-text small">{summary.describe()} {unmapped}</li>
+                        }
+                    }
+                    Err(e) => view! {
+                        <li class="dropdown-item-text text-danger small">{e.to_string()}</li>
+                    },
+                })}
+            </ul>
+        </li>
+    }
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+    use super::*;
+    use crate::app::Priority;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    fn todo(id: u32, task: &str) -> TodoItem {
+        TodoItem {
+            id,
+            task: task.into(),
+            ..TodoItem::default()
+        }
+    }
+
+    /// An empty database of the test's own, so imports don't touch the server's.
+    async fn database() -> SqlitePool {
+        // a single connection that stays open, each one to memory is its own database
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlx::migrate!().run(&pool).await.unwrap();
+        pool
+    }
+
+    async fn rows(pool: &SqlitePool) -> Vec<(u32, String, Option<u32>, Option<String>)> {
+        sqlx::query_as("SELECT id, task, parent, list FROM todos ORDER BY id")
+            .fetch_all(pool)
+            .await
+            .unwrap()
+    }
+
+    #[test]
+    fn backups_in_other_formats_are_refused() {
+        for format in [0, BACKUP_FORMAT + 1] {
+            let backup = Backup {
+                format,
+                todos: vec![todo(1, "call mom")],
+            };
+            assert_eq!(
+                check_backup(&backup),
+                Err(vec![format!(
+                    "unsupported backup format {format}, expected {BACKUP_FORMAT}"
+                )])
+            );
+        }
+    }
+
+    #[test]
+    fn backups_are_checked_todo_by_todo() {
+        let backup = Backup::new(vec![
+            TodoItem {
+                tags: vec!["#Family".into()],
+                list: Some(" Home ".into()),
+                ..todo(1, " call mom ")
+            },
+            todo(1, "pay rent"),
+            TodoItem {
+                parent: Some(4),
+                ..todo(2, " ")
+            },
+            TodoItem {
+                due: Some("soon".into()),
+                completed: Some("yesterday".into()),
+                ..todo(3, "water plants")
+            },
+        ]);
+        assert_eq!(
+            check_backup(&backup),
+            Err(vec![
+                "todo 1 appears more than once".into(),
+                "todo 2: todo 4 isn't before it".into(),
+                "todo 2: task can't be empty".into(),
+                "todo 3: due date `soon` isn't a YYYY-MM-DD date".into(),
+                "todo 3: completed date `yesterday` isn't a YYYY-MM-DD date".into(),
+            ])
+        );
+
+        let backup = Backup::new(backup.todos[..1].to_vec());
+        assert_eq!(
+            check_backup(&backup),
+            Ok(vec![TodoItem {
+                tags: vec!["family".into()],
+                list: Some("Home".into()),
+                ..todo(1, "call mom")
+            }])
+        );
+    }
+
+    #[test]
+    fn csv_columns_are_found_by_name() {
+        let file = "id,Title,Completed,Priority,Due Date,Tags,List\n\
+            7,call mom,yes,HIGH,2024-03-01,\"#family, calls\",Home\n\
+            8,pay rent,,,,,\n";
+        assert_eq!(
+            check_csv(file),
+            Ok(vec![
+                TodoItem {
+                    done: true,
+                    priority: Some(Priority::High),
+                    due: Some("2024-03-01".into()),
+                    tags: vec!["family".into(), "calls".into()],
+                    list: Some("Home".into()),
+                    ..todo(0, "call mom")
+                },
+                todo(0, "pay rent"),
+            ])
+        );
+        assert_eq!(check_csv(""), Ok(vec![]));
+    }
+
+    #[test]
+    fn csv_errors_name_their_line() {
+        assert_eq!(
+            check_csv("done,due\nyes,2024-03-01\n"),
+            Err(vec!["line 1: no `task` column in the header".into()])
+        );
+        assert_eq!(
+            check_csv("task,done,priority\ncall mom,maybe,\n,,urgent\n"),
+            Err(vec![
+                "line 2: `done` is `maybe`, expected true or false".into(),
+                "line 3: task can't be empty".into(),
+                "line 3: unknown priority `urgent`, expected high, medium or low".into(),
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn merging_skips_tasks_already_there_and_keeps_subtasks_with_their_todo() {
+        let pool = database().await;
+        let summary = import_into(
+            &pool,
+            vec![todo(1, "call mom")],
+            vec![],
+            ImportMode::Merge,
+            None,
+            false,
+        )
+        .await
+        .unwrap();
+        assert_eq!(summary.added, 1);
+
+        // ids in the file are only for subtasks to point at their todo
+        let todos = vec![
+            todo(10, "call mom"),
+            TodoItem {
+                parent: Some(10),
+                ..todo(11, "book trip")
+            },
+            todo(12, "pay rent"),
+            TodoItem {
+                parent: Some(12),
+                list: Some("Bills".into()),
+                ..todo(13, "get details")
+            },
+            todo(14, "pay rent"),
+        ];
+        let summary = import_into(
+            &pool,
+            todos,
+            vec!["a comment".into()],
+            ImportMode::Merge,
+            Some("Inbox".into()),
+            false,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            summary,
+            ImportSummary {
+                added: 3,
+                skipped: 2,
+                deleted: 0,
+                dry_run: false,
+                unmapped: vec!["a comment".into()],
+            }
+        );
+        assert_eq!(
+            rows(&pool).await,
+            [
+                (1, "call mom".into(), None, None),
+                (2, "book trip".into(), Some(1), Some("Inbox".into())),
+                (3, "pay rent".into(), None, Some("Inbox".into())),
+                (4, "get details".into(), Some(3), Some("Bills".into())),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn replacing_deletes_every_todo_first() {
+        let pool = database().await;
+        let todos = vec![todo(1, "call mom"), todo(2, "pay rent")];
+        import_into(&pool, todos.clone(), vec![], ImportMode::Merge, None, false)
+            .await
+            .unwrap();
+
+        let summary = import_into(&pool, todos, vec![], ImportMode::Replace, None, false)
+            .await
+            .unwrap();
+        assert_eq!((summary.added, summary.skipped, summary.deleted), (2, 0, 2));
+        assert_eq!(
+            rows(&pool).await,
+            [
+                (3, "call mom".into(), None, None),
+                (4, "pay rent".into(), None, None),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn dry_runs_count_what_they_would_do_and_change_nothing() {
+        let pool = database().await;
+        import_into(
+            &pool,
+            vec![todo(1, "call mom")],
+            vec![],
+            ImportMode::Merge,
+            None,
+            false,
+        )
+        .await
+        .unwrap();
+        let before = rows(&pool).await;
+
+        let todos = vec![todo(1, "call mom"), todo(2, "pay rent")];
+        let merged = import_into(&pool, todos.clone(), vec![], ImportMode::Merge, None, true)
+            .await
+            .unwrap();
+        assert_eq!((merged.added, merged.skipped, merged.deleted), (1, 1, 0));
+        assert_eq!(
+            merged.describe(),
+            "Would add 1, skip 1 already there and delete 0"
+        );
+
+        let replaced = import_into(&pool, todos, vec![], ImportMode::Replace, None, true)
+            .await
+            .unwrap();
+        assert_eq!(
+            (replaced.added, replaced.skipped, replaced.deleted),
+            (2, 0, 1)
+        );
+        assert_eq!(rows(&pool).await, before);
+    }
+}
@@ -0,0 +1,236 @@
+//! `todo-cli`, a command-line client of a running server's REST API, built with
+//! `cargo build --features cli --bin todo-cli`.
+//!
+//! ```text
+//! todo-cli add "write the report"
+//! todo-cli ls --open
+//! todo-cli done 12
+//! todo-cli rm 12
+//! todo-cli export > backup.json
+//! ```
+//!
+//! The server and token come from `--server` and `--token`, or `TODO_SERVER` and
+//! `TODO_TOKEN`.
+
+use clap::{Parser, Subcommand, ValueEnum};
+use leptos_todo::app::{TodoItem, TodoPage};
+use leptos_todo::backup::Backup;
+use leptos_todo::rest::{ErrorResponse, NewTodo, TodoChanges};
+use reqwest::blocking::{Client, RequestBuilder};
+use serde::de::DeserializeOwned;
+use std::process::ExitCode;
+
+/// Todos `ls` and `export` ask for at a time, the most the API returns.
+const PAGE_SIZE: u32 = 500;
+
+#[derive(Parser)]
+#[command(name = "todo-cli", about = "Manage the todos of a running server")]
+struct Cli {
+    /// Address of the server.
+    #[arg(long, env = "TODO_SERVER", default_value = "http://localhost:3000")]
+    server: String,
+    /// Your token, from `leptos-todo create-user`, when the server has users.
+    #[arg(long, env = "TODO_TOKEN", hide_env_values = true)]
+    token: Option<String>,
+    /// How todos are printed.
+    #[arg(long, value_enum, default_value_t = Format::Table)]
+    format: Format,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    /// Aligned columns with a header.
+    Table,
+    /// The API's JSON.
+    Json,
+    /// Tab-separated id, done and task, for scripts.
+    Plain,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Add a todo, the words joined by spaces.
+    Add {
+        #[arg(required = true)]
+        task: Vec<String>,
+    },
+    /// List todos.
+    Ls {
+        /// Only the todos not done.
+        #[arg(long, conflicts_with = "done")]
+        open: bool,
+        /// Only the todos done.
+        #[arg(long)]
+        done: bool,
+        /// Only the todos matching a filter in the search syntax, like `+work`.
+        #[arg(short, long, default_value = "")]
+        query: String,
+    },
+    /// Mark a todo done.
+    Done { id: u32 },
+    /// Mark a todo not done.
+    Undone { id: u32 },
+    /// Delete a todo.
+    Rm { id: u32 },
+    /// Print every todo as a JSON backup, the same the app exports.
+    Export,
+}
+
+/// The API at one server.
+struct Api {
+    client: Client,
+    server: String,
+    token: Option<String>,
+}
+
+impl Api {
+    fn request(&self, method: reqwest::Method, path: &str) -> RequestBuilder {
+        let url = format!("{}/api/v1/todos{path}", self.server.trim_end_matches('/'));
+        let request = self.client.request(method, url);
+        match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    /// Sends `request`, with the API's error message as the error.
+    fn send(&self, request: RequestBuilder) -> Result<reqwest::blocking::Response, String> {
+        let response = request.send().map_err(|e| e.to_string())?;
+        if response.status().is_success() {
+            return Ok(response);
+        }
+
+        let status = response.status();
+        match response.json::<ErrorResponse>() {
+            Ok(body) => Err(body.error.message),
+            Err(_) => Err(format!("server answered {status}")),
+        }
+    }
+
+    fn json<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T, String> {
+        self.send(request)?.json().map_err(|e| e.to_string())
+    }
+
+    fn get(&self, id: u32) -> Result<TodoItem, String> {
+        self.json(self.request(reqwest::Method::GET, &format!("/{id}")))
+    }
+
+    /// Every todo matching `filter`, a page at a time.
+    fn list(&self, filter: &str) -> Result<Vec<TodoItem>, String> {
+        let mut todos = Vec::new();
+        let mut after: Option<u32> = None;
+        loop {
+            let mut query = vec![("q", filter.to_string()), ("limit", PAGE_SIZE.to_string())];
+            if let Some(after) = after {
+                query.push(("after", after.to_string()));
+            }
+            let page: TodoPage = self.json(self.request(reqwest::Method::GET, "").query(&query))?;
+            let (page, next) = page.into_parts();
+            todos.extend(page);
+            match next {
+                Some(next) => after = Some(next),
+                None => return Ok(todos),
+            }
+        }
+    }
+
+    fn set_done(&self, id: u32, done: bool) -> Result<TodoItem, String> {
+        let todo = self.get(id)?;
+        let changes = TodoChanges {
+            done: Some(done),
+            version: todo.version(),
+            ..TodoChanges::default()
+        };
+        self.json(
+            self.request(reqwest::Method::PATCH, &format!("/{id}"))
+                .json(&changes),
+        )
+    }
+
+    fn delete(&self, id: u32) -> Result<(), String> {
+        let todo = self.get(id)?;
+        let path = format!("/{id}?version={}", todo.version());
+        self.send(self.request(reqwest::Method::DELETE, &path))
+            .map(drop)
+    }
+}
+
+fn print(todos: &[TodoItem], format: Format) {
+    match format {
+        Format::Json => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(todos).expect("todos are JSON")
+            )
+        }
+        Format::Plain => {
+            for todo in todos {
+                println!("{}\t{}\t{}", todo.id(), todo.done(), todo.task());
+            }
+        }
+        Format::Table => {
+            let width = todos
+                .iter()
+                .map(|todo| todo.id().to_string().len())
+                .max()
+                .unwrap_or(0)
+                .max("ID".len());
+            println!("{:>width$}  DONE  TASK", "ID");
+            for todo in todos {
+                let done = if todo.done() { "x" } else { "" };
+                println!("{:>width$}  {done:<4}  {}", todo.id(), todo.task());
+            }
+        }
+    }
+}
+
+fn run(cli: Cli) -> Result<(), String> {
+    let api = Api {
+        client: Client::new(),
+        server: cli.server,
+        token: cli.token,
+    };
+
+    match cli.command {
+        Command::Add { task } => {
+            let todo = NewTodo {
+                task: task.join(" "),
+                ..NewTodo::default()
+            };
+            let todo = api.json(api.request(reqwest::Method::POST, "").json(&todo))?;
+            print(&[todo], cli.format);
+        }
+        Command::Ls { open, done, query } => {
+            let status = match (open, done) {
+                (true, _) => " is:open",
+                (_, true) => " is:done",
+                _ => "",
+            };
+            let todos = api.list(format!("{query}{status}").trim())?;
+            print(&todos, cli.format);
+        }
+        Command::Done { id } => print(&[api.set_done(id, true)?], cli.format),
+        Command::Undone { id } => print(&[api.set_done(id, false)?], cli.format),
+        Command::Rm { id } => api.delete(id)?,
+        Command::Export => {
+            let backup = Backup::new(api.list("")?);
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&backup).expect("backups are JSON")
+            );
+        }
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("error: {message}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,620 @@
+//! `todo-tui`, a terminal client, built with `cargo build --features tui --bin todo-tui`.
+//!
+//! It works against a running server's REST API, with `--server` and `--token` or
+//! `TODO_SERVER` and `TODO_TOKEN`, or on a SQLite file directly with `--db`, with no
+//! server. `--db` needs the `standalone` feature, which builds in the server's
+//! database code. The saved searches are the lists, and todos changed elsewhere show up as
+//! they change.
+//!
+//! Keys: `j`/`k` move, `g`/`G` go to the first or last, `h`/`l` or tab switch between
+//! lists and todos, `a` adds a todo, space or `x` toggles one, `dd` deletes one, `r`
+//! reloads and `q` quits.
+
+use clap::Parser;
+use crossterm::{
+    event::{self, Event as TermEvent, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+#[cfg(feature = "standalone")]
+use leptos::ServerFnError;
+#[cfg(feature = "standalone")]
+use leptos_todo::app::{add_todo, delete_todo, list_todos, todos_tag, toggle_todo, TODOS_DB};
+use leptos_todo::app::{TodoItem, TodoPage, MAX_PAGE_SIZE};
+use leptos_todo::rest::{ErrorResponse, Lists, NewTodo, TodoChanges, TodoList};
+#[cfg(feature = "standalone")]
+use leptos_todo::saved_search::get_saved_searches;
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    Frame, Terminal,
+};
+use reqwest::blocking::{Client, RequestBuilder};
+use serde::de::DeserializeOwned;
+use std::io::{self, BufRead, BufReader, Stdout};
+use std::process::ExitCode;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+
+/// How long to wait for a key before looking for changes.
+const TICK: Duration = Duration::from_millis(250);
+
+/// How often the standalone mode looks for changes made by others to the file.
+#[cfg(feature = "standalone")]
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long to wait before reconnecting to a server's event stream.
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+#[derive(Parser)]
+#[command(name = "todo-tui", about = "Manage todos in the terminal")]
+struct Cli {
+    /// Address of the server.
+    #[arg(long, env = "TODO_SERVER", default_value = "http://localhost:3000")]
+    server: String,
+    /// Your token, from `leptos-todo create-user`, when the server has users.
+    #[arg(long, env = "TODO_TOKEN", hide_env_values = true)]
+    token: Option<String>,
+    /// SQLite file to work on without a server, created when missing.
+    #[arg(long, conflicts_with_all = ["server", "token"])]
+    db: Option<String>,
+}
+
+/// Where todos come from, errors being messages for the status line.
+trait Backend {
+    fn lists(&self) -> Result<Vec<TodoList>, String>;
+    fn todos(&self, filter: &str) -> Result<Vec<TodoItem>, String>;
+    fn add(&self, task: &str) -> Result<(), String>;
+    fn toggle(&self, todo: &TodoItem) -> Result<(), String>;
+    fn delete(&self, todo: &TodoItem) -> Result<(), String>;
+    /// Sends on `changed` whenever todos may have changed, until it's closed.
+    fn watch(&self, changed: Sender<()>);
+}
+
+/// A server's REST API.
+struct Remote {
+    client: Client,
+    server: String,
+    token: Option<String>,
+}
+
+impl Remote {
+    fn request(&self, client: &Client, method: reqwest::Method, path: &str) -> RequestBuilder {
+        let url = format!("{}/api/v1{path}", self.server.trim_end_matches('/'));
+        let request = client.request(method, url);
+        match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    fn send(&self, request: RequestBuilder) -> Result<reqwest::blocking::Response, String> {
+        let response = request.send().map_err(|e| e.to_string())?;
+        if response.status().is_success() {
+            return Ok(response);
+        }
+
+        let status = response.status();
+        match response.json::<ErrorResponse>() {
+            Ok(body) => Err(body.error.message),
+            Err(_) => Err(format!("server answered {status}")),
+        }
+    }
+
+    fn json<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T, String> {
+        self.send(request)?.json().map_err(|e| e.to_string())
+    }
+}
+
+impl Backend for Remote {
+    fn lists(&self) -> Result<Vec<TodoList>, String> {
+        let lists: Lists = self.json(self.request(&self.client, reqwest::Method::GET, "/lists"))?;
+        Ok(lists.lists)
+    }
+
+    fn todos(&self, filter: &str) -> Result<Vec<TodoItem>, String> {
+        let mut todos = Vec::new();
+        let mut after: Option<u32> = None;
+        loop {
+            let mut query = vec![
+                ("q", filter.to_string()),
+                ("limit", MAX_PAGE_SIZE.to_string()),
+            ];
+            if let Some(after) = after {
+                query.push(("after", after.to_string()));
+            }
+            let request = self.request(&self.client, reqwest::Method::GET, "/todos");
+            let page: TodoPage = self.json(request.query(&query))?;
+            let (page, next) = page.into_parts();
+            todos.extend(page);
+            match next {
+                Some(next) => after = Some(next),
+                None => return Ok(todos),
+            }
+        }
+    }
+
+    fn add(&self, task: &str) -> Result<(), String> {
+        let todo = NewTodo {
+            task: task.into(),
+            ..NewTodo::default()
+        };
+        let request = self.request(&self.client, reqwest::Method::POST, "/todos");
+        self.send(request.json(&todo)).map(drop)
+    }
+
+    fn toggle(&self, todo: &TodoItem) -> Result<(), String> {
+        let changes = TodoChanges {
+            done: Some(!todo.done()),
+            version: todo.version(),
+            ..TodoChanges::default()
+        };
+        let path = format!("/todos/{}", todo.id());
+        let request = self.request(&self.client, reqwest::Method::PATCH, &path);
+        self.send(request.json(&changes)).map(drop)
+    }
+
+    fn delete(&self, todo: &TodoItem) -> Result<(), String> {
+        let path = format!("/todos/{}?version={}", todo.id(), todo.version());
+        self.send(self.request(&self.client, reqwest::Method::DELETE, &path))
+            .map(drop)
+    }
+
+    fn watch(&self, changed: Sender<()>) {
+        // the stream stays open, so without the default timeout
+        let client = Client::builder()
+            .timeout(None)
+            .build()
+            .expect("the default settings are valid");
+        let events = self.request(&client, reqwest::Method::GET, "/events");
+        thread::spawn(move || loop {
+            let request = events.try_clone().expect("the request has no body");
+            if let Ok(response) = request.send() {
+                for line in BufReader::new(response).lines() {
+                    let Ok(line) = line else { break };
+                    if line.starts_with("data:") && changed.send(()).is_err() {
+                        return;
+                    }
+                }
+            }
+            thread::sleep(RECONNECT_DELAY);
+        });
+    }
+}
+
+/// A SQLite file, changed with the same functions as the server's.
+#[cfg(feature = "standalone")]
+struct Local {
+    runtime: tokio::runtime::Runtime,
+}
+
+#[cfg(feature = "standalone")]
+fn message(e: ServerFnError) -> String {
+    match e {
+        ServerFnError::ServerError(message) | ServerFnError::Args(message) => message,
+        e => e.to_string(),
+    }
+}
+
+#[cfg(feature = "standalone")]
+impl Backend for Local {
+    fn lists(&self) -> Result<Vec<TodoList>, String> {
+        let saved = self
+            .runtime
+            .block_on(get_saved_searches())
+            .map_err(message)?;
+        Ok(saved.into_iter().map(TodoList::from).collect())
+    }
+
+    fn todos(&self, filter: &str) -> Result<Vec<TodoItem>, String> {
+        let mut todos = Vec::new();
+        let mut after: Option<u32> = None;
+        loop {
+            let page = self
+                .runtime
+                .block_on(list_todos(filter, after, MAX_PAGE_SIZE))
+                .map_err(message)?;
+            let (page, next) = page.into_parts();
+            todos.extend(page);
+            match next {
+                Some(next) => after = Some(next),
+                None => return Ok(todos),
+            }
+        }
+    }
+
+    fn add(&self, task: &str) -> Result<(), String> {
+        self.runtime
+            .block_on(add_todo(task.into()))
+            .map(drop)
+            .map_err(message)
+    }
+
+    fn toggle(&self, todo: &TodoItem) -> Result<(), String> {
+        self.runtime
+            .block_on(toggle_todo(todo.id(), todo.version()))
+            .map(drop)
+            .map_err(message)
+    }
+
+    fn delete(&self, todo: &TodoItem) -> Result<(), String> {
+        self.runtime
+            .block_on(delete_todo(todo.id(), todo.version()))
+            .map(drop)
+            .map_err(message)
+    }
+
+    fn watch(&self, changed: Sender<()>) {
+        let runtime = self.runtime.handle().clone();
+        thread::spawn(move || {
+            let mut last = None;
+            loop {
+                if let Ok(tag) = runtime.block_on(todos_tag()) {
+                    if last.as_ref() != Some(&tag) {
+                        if changed.send(()).is_err() {
+                            return;
+                        }
+                        last = Some(tag);
+                    }
+                }
+                thread::sleep(POLL_INTERVAL);
+            }
+        });
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Pane {
+    Lists,
+    Todos,
+}
+
+enum Mode {
+    Normal,
+    /// Typing the task of a new todo.
+    Adding(String),
+}
+
+struct App {
+    /// The saved searches, after one with every todo.
+    lists: Vec<TodoList>,
+    list: ListState,
+    todos: Vec<TodoItem>,
+    todo: ListState,
+    pane: Pane,
+    mode: Mode,
+    /// Whether the last key was a `d`, which another one deletes.
+    deleting: bool,
+    status: String,
+    quit: bool,
+}
+
+impl App {
+    fn new() -> App {
+        App {
+            lists: vec![],
+            list: ListState::default().with_selected(Some(0)),
+            todos: vec![],
+            todo: ListState::default(),
+            pane: Pane::Todos,
+            mode: Mode::Normal,
+            deleting: false,
+            status: String::new(),
+            quit: false,
+        }
+    }
+
+    fn all() -> TodoList {
+        TodoList {
+            id: 0,
+            name: "All".into(),
+            filter: String::new(),
+        }
+    }
+
+    fn selected_list(&self) -> Option<&TodoList> {
+        self.list.selected().and_then(|index| self.lists.get(index))
+    }
+
+    fn selected_todo(&self) -> Option<&TodoItem> {
+        self.todo.selected().and_then(|index| self.todos.get(index))
+    }
+
+    /// Loads the lists and the todos of the one selected, keeping the selections.
+    fn reload(&mut self, backend: &dyn Backend) {
+        let list_id = self.selected_list().map(|list| list.id);
+        match backend.lists() {
+            Ok(lists) => self.lists = std::iter::once(App::all()).chain(lists).collect(),
+            Err(e) => {
+                self.status = e;
+                return;
+            }
+        }
+        let index = self.lists.iter().position(|list| Some(list.id) == list_id);
+        self.list.select(Some(index.unwrap_or(0)));
+
+        self.reload_todos(backend);
+    }
+
+    fn reload_todos(&mut self, backend: &dyn Backend) {
+        let filter = self.selected_list().map(|list| list.filter.clone());
+        let todo_id = self.selected_todo().map(TodoItem::id);
+        match backend.todos(filter.as_deref().unwrap_or_default()) {
+            Ok(todos) => self.todos = todos,
+            Err(e) => {
+                self.status = e;
+                return;
+            }
+        }
+        // the same todo, or the one now where it was when it's gone
+        let index = self
+            .todos
+            .iter()
+            .position(|todo| Some(todo.id()) == todo_id);
+        let index = index.or(self.todo.selected()).unwrap_or(0);
+        self.todo.select(match self.todos.len() {
+            0 => None,
+            len => Some(index.min(len - 1)),
+        });
+    }
+
+    /// Moves the selection of the focused pane, showing the todos of a list selected.
+    fn select(&mut self, backend: &dyn Backend, to: Step) {
+        let (state, len) = match self.pane {
+            Pane::Lists => (&mut self.list, self.lists.len()),
+            Pane::Todos => (&mut self.todo, self.todos.len()),
+        };
+        if len == 0 {
+            return;
+        }
+        let current = state.selected().unwrap_or(0);
+        state.select(Some(match to {
+            Step::By(delta) => current.saturating_add_signed(delta).min(len - 1),
+            Step::First => 0,
+            Step::Last => len - 1,
+        }));
+        if self.pane == Pane::Lists {
+            self.todo.select(None);
+            self.reload_todos(backend);
+        }
+    }
+
+    /// Runs a change, showing its error or reloading after it.
+    fn change(&mut self, backend: &dyn Backend, result: Result<(), String>) {
+        match result {
+            Ok(()) => {
+                self.status.clear();
+                self.reload(backend);
+            }
+            Err(e) => self.status = e,
+        }
+    }
+
+    fn key(&mut self, backend: &dyn Backend, key: KeyEvent) {
+        if let Mode::Adding(task) = &mut self.mode {
+            match key.code {
+                KeyCode::Enter => {
+                    let task = std::mem::take(task);
+                    self.mode = Mode::Normal;
+                    let added = backend.add(&task);
+                    self.change(backend, added);
+                }
+                KeyCode::Esc => self.mode = Mode::Normal,
+                KeyCode::Backspace => {
+                    task.pop();
+                }
+                KeyCode::Char(c) => task.push(c),
+                _ => {}
+            }
+            return;
+        }
+
+        let deleting = std::mem::take(&mut self.deleting);
+        match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => self.quit = true,
+            KeyCode::Char('q') => self.quit = true,
+            KeyCode::Char('j') | KeyCode::Down => self.select(backend, Step::By(1)),
+            KeyCode::Char('k') | KeyCode::Up => self.select(backend, Step::By(-1)),
+            KeyCode::Char('g') | KeyCode::Home => self.select(backend, Step::First),
+            KeyCode::Char('G') | KeyCode::End => self.select(backend, Step::Last),
+            KeyCode::Char('h') | KeyCode::Left => self.pane = Pane::Lists,
+            KeyCode::Char('l') | KeyCode::Right | KeyCode::Enter => self.pane = Pane::Todos,
+            KeyCode::Tab | KeyCode::BackTab => {
+                self.pane = match self.pane {
+                    Pane::Lists => Pane::Todos,
+                    Pane::Todos => Pane::Lists,
+                }
+            }
+            KeyCode::Char('a') | KeyCode::Char('o') => self.mode = Mode::Adding(String::new()),
+            KeyCode::Char(' ') | KeyCode::Char('x') => {
+                if let Some(todo) = self.selected_todo() {
+                    let toggled = backend.toggle(todo);
+                    self.change(backend, toggled);
+                }
+            }
+            KeyCode::Char('d') => match self.selected_todo() {
+                Some(todo) if deleting => {
+                    let deleted = backend.delete(todo);
+                    self.change(backend, deleted);
+                }
+                Some(_) => self.deleting = true,
+                None => {}
+            },
+            KeyCode::Char('r') => {
+                self.status.clear();
+                self.reload(backend);
+            }
+            _ => {}
+        }
+    }
+}
+
+enum Step {
+    By(isize),
+    First,
+    Last,
+}
+
+fn draw(frame: &mut Frame, app: &mut App) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(1)])
+        .split(frame.size());
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Length(24), Constraint::Min(0)])
+        .split(rows[0]);
+
+    let block = |title: String, pane: Pane| {
+        let border = if app.pane == pane {
+            Style::default().fg(Color::Cyan)
+        } else {
+            Style::default()
+        };
+        Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_style(border)
+    };
+    let highlight = Style::default().add_modifier(Modifier::REVERSED);
+
+    let lists = List::new(
+        app.lists
+            .iter()
+            .map(|list| ListItem::new(list.name.as_str()))
+            .collect::<Vec<_>>(),
+    )
+    .block(block(" Lists ".into(), Pane::Lists))
+    .highlight_style(highlight);
+    frame.render_stateful_widget(lists, columns[0], &mut app.list);
+
+    let name = app.selected_list().map(|list| list.name.clone());
+    let title = format!(" {} ({}) ", name.unwrap_or_default(), app.todos.len());
+    let todos = List::new(
+        app.todos
+            .iter()
+            .map(|todo| {
+                let item = ListItem::new(format!(
+                    "[{}] {}",
+                    if todo.done() { "x" } else { " " },
+                    todo.task()
+                ));
+                if todo.done() {
+                    item.style(Style::default().fg(Color::DarkGray))
+                } else {
+                    item
+                }
+            })
+            .collect::<Vec<_>>(),
+    )
+    .block(block(title, Pane::Todos))
+    .highlight_style(highlight);
+    frame.render_stateful_widget(todos, columns[1], &mut app.todo);
+
+    let footer = match &app.mode {
+        Mode::Adding(task) => {
+            let prompt = format!("add: {task}");
+            frame.set_cursor(rows[1].x + prompt.chars().count() as u16, rows[1].y);
+            prompt
+        }
+        Mode::Normal if !app.status.is_empty() => app.status.clone(),
+        Mode::Normal => {
+            "j/k move  h/l switch  a add  space toggle  dd delete  r reload  q quit".into()
+        }
+    };
+    frame.render_widget(Paragraph::new(footer), rows[1]);
+}
+
+fn event_loop(
+    terminal: &mut Terminal<CrosstermBackend<Stdout>>,
+    app: &mut App,
+    backend: &dyn Backend,
+    changed: &Receiver<()>,
+) -> io::Result<()> {
+    while !app.quit {
+        terminal.draw(|frame| draw(frame, app))?;
+
+        if event::poll(TICK)? {
+            if let TermEvent::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press {
+                    app.key(backend, key);
+                }
+            }
+        }
+        // changes made here come back too, reloading again does no harm
+        if changed.try_iter().count() > 0 {
+            app.reload(backend);
+        }
+    }
+    Ok(())
+}
+
+fn restore_terminal() {
+    _ = disable_raw_mode();
+    _ = execute!(io::stdout(), LeaveAlternateScreen, crossterm::cursor::Show);
+}
+
+fn run(backend: &dyn Backend) -> io::Result<()> {
+    let (changes, changed) = mpsc::channel();
+    backend.watch(changes);
+
+    let mut app = App::new();
+    app.reload(backend);
+
+    // a panic would otherwise leave the terminal raw and its message unreadable
+    let panic_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        restore_terminal();
+        panic_hook(info);
+    }));
+
+    enable_raw_mode()?;
+    execute!(io::stdout(), EnterAlternateScreen)?;
+    let result = Terminal::new(CrosstermBackend::new(io::stdout()))
+        .and_then(|mut terminal| event_loop(&mut terminal, &mut app, backend, &changed));
+    restore_terminal();
+    result
+}
+
+/// The standalone mode, on `file`.
+#[cfg(feature = "standalone")]
+fn local(file: String) -> Result<Box<dyn Backend>, String> {
+    std::env::set_var(TODOS_DB, file);
+    let runtime = tokio::runtime::Runtime::new().map_err(|e| e.to_string())?;
+    Ok(Box::new(Local { runtime }))
+}
+
+#[cfg(not(feature = "standalone"))]
+fn local(_file: String) -> Result<Box<dyn Backend>, String> {
+    Err("--db needs todo-tui built with `--features standalone`".into())
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    let backend: Box<dyn Backend> = match cli.db {
+        Some(file) => match local(file) {
+            Ok(local) => local,
+            Err(e) => {
+                eprintln!("error: {e}");
+                return ExitCode::FAILURE;
+            }
+        },
+        None => Box::new(Remote {
+            client: Client::new(),
+            server: cli.server,
+            token: cli.token,
+        }),
+    };
+
+    match run(backend.as_ref()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
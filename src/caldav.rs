@@ -0,0 +1,771 @@
+//! Enough of [CalDAV](https://www.rfc-editor.org/rfc/rfc4791) for phones to sync
+//! todos both ways, as one calendar of `VTODO`s at `/caldav/todos/`, with a
+//! resource per todo and its version as the ETag. Clients sign in with a user's
+//! token as the password.
+
+// errors are the responses handlers return as they are
+#![allow(clippy::result_large_err)]
+
+use cfg_if::cfg_if;
+
+cfg_if! { if #[cfg(feature = "ssr")] {
+    use crate::app::{
+        check_due, check_task, db, set_completed, todos_changed, todos_tag, TodoItem,
+    };
+    use crate::auth;
+    use crate::ical;
+    use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+    use roxmltree::{Document, Node};
+    use axum::{
+        extract::Path,
+        http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+        response::{IntoResponse, Redirect, Response},
+        routing::any,
+        Router,
+    };
+    use sqlx::SqlitePool;
+
+    /// The principal, and the home of its one calendar.
+    const ROOT: &str = "/caldav/";
+
+    /// The calendar of every todo.
+    const COLLECTION: &str = "/caldav/todos/";
+
+    const DAV: &str = "DAV:";
+    const CALDAV: &str = "urn:ietf:params:xml:ns:caldav";
+
+    const SELECT: &str = "SELECT todos.*, caldav_resources.name, caldav_resources.uid
+        FROM todos LEFT JOIN caldav_resources ON caldav_resources.todo_id = todos.id";
+
+    type Result<T> = std::result::Result<T, Response>;
+
+    /// A todo as a calendar resource.
+    #[derive(sqlx::FromRow)]
+    struct Resource {
+        #[sqlx(flatten)]
+        todo: TodoItem,
+        /// Set for todos created by a CalDAV client.
+        name: Option<String>,
+        uid: Option<String>,
+    }
+
+    impl Resource {
+        fn href(&self) -> String {
+            match &self.name {
+                Some(name) => format!("{COLLECTION}{name}"),
+                None => format!("{COLLECTION}{}.ics", self.todo.id),
+            }
+        }
+
+        fn etag(&self) -> String {
+            format!("\"{}-{}\"", self.todo.id, self.todo.version)
+        }
+
+        fn calendar(&self) -> String {
+            let uid = self.uid.clone().unwrap_or_else(|| ical::uid(self.todo.id));
+            ical::calendar(None, &[ical::vtodo(&self.todo, &uid, &ical::now())])
+        }
+
+        fn properties(&self) -> Vec<(String, String)> {
+            ical::properties(&self.calendar()).unwrap_or_default()
+        }
+
+        fn props(&self, with_data: bool) -> String {
+            let mut props = format!(
+                "<d:resourcetype/>\
+                <d:getcontenttype>text/calendar; charset=utf-8; component=VTODO</d:getcontenttype>\
+                <d:getetag>{}</d:getetag>",
+                xml_escape(&self.etag())
+            );
+            if with_data {
+                props.push_str(&format!(
+                    "<c:calendar-data>{}</c:calendar-data>",
+                    xml_escape(&self.calendar())
+                ));
+            }
+            props
+        }
+    }
+
+    /// `/.well-known/caldav` for clients to find the calendar from the server's
+    /// address, and the principal, calendar and its resources under `/caldav/`.
+    pub fn caldav_routes<S: Clone + Send + Sync + 'static>() -> Router<S> {
+        Router::new()
+            .route("/.well-known/caldav", any(|| async { Redirect::permanent(ROOT) }))
+            .route("/caldav", any(principal))
+            .route("/caldav/", any(principal))
+            .route("/caldav/todos", any(collection))
+            .route("/caldav/todos/", any(collection))
+            .route("/caldav/todos/:name", any(resource))
+    }
+
+    async fn principal(method: Method, headers: HeaderMap) -> Result<Response> {
+        authorize(&headers).await?;
+
+        match method.as_str() {
+            "OPTIONS" => Ok(options()),
+            "PROPFIND" => {
+                let depth = depth(&headers)?;
+                let mut responses = vec![response(ROOT, &principal_props())];
+                if depth > 0 {
+                    responses.push(response(COLLECTION, &collection_props().await?));
+                }
+                if depth > 1 {
+                    for resource in all(&pool().await?).await? {
+                        responses.push(response(&resource.href(), &resource.props(false)));
+                    }
+                }
+                Ok(multistatus(responses))
+            }
+            _ => Err(not_allowed()),
+        }
+    }
+
+    async fn collection(method: Method, headers: HeaderMap, body: String) -> Result<Response> {
+        authorize(&headers).await?;
+
+        match method.as_str() {
+            "OPTIONS" => Ok(options()),
+            "PROPFIND" => {
+                let depth = depth(&headers)?;
+                let pool = pool().await?;
+                let mut responses = vec![response(COLLECTION, &collection_props().await?)];
+                if depth > 0 {
+                    for resource in all(&pool).await? {
+                        responses.push(response(&resource.href(), &resource.props(false)));
+                    }
+                }
+                Ok(multistatus(responses))
+            }
+            "REPORT" => {
+                let report = Report::parse(&body)?;
+                let pool = pool().await?;
+                let mut responses = vec![];
+                match report {
+                    Report::Multiget(hrefs) => {
+                        for href in hrefs {
+                            let name = href.rsplit('/').next().unwrap_or_default();
+                            responses.push(match find(&pool, name).await? {
+                                Some(resource) => response(&resource.href(), &resource.props(true)),
+                                None => not_found(&href),
+                            });
+                        }
+                    }
+                    Report::Query(filter) => {
+                        for resource in all(&pool).await? {
+                            if filter.matches(&resource) {
+                                responses.push(response(&resource.href(), &resource.props(true)));
+                            }
+                        }
+                    }
+                }
+                Ok(multistatus(responses))
+            }
+            _ => Err(not_allowed()),
+        }
+    }
+
+    async fn resource(
+        Path(name): Path<String>,
+        method: Method,
+        headers: HeaderMap,
+        body: String,
+    ) -> Result<Response> {
+        authorize(&headers).await?;
+        let pool = pool().await?;
+        let existing = find(&pool, &name).await?;
+
+        match method.as_str() {
+            "OPTIONS" => Ok(options()),
+            "GET" | "HEAD" => {
+                let resource = existing.ok_or_else(|| error(StatusCode::NOT_FOUND, "no such todo"))?;
+                Ok((
+                    [
+                        (header::CONTENT_TYPE, "text/calendar; charset=utf-8".to_string()),
+                        (header::ETAG, resource.etag()),
+                    ],
+                    resource.calendar(),
+                )
+                    .into_response())
+            }
+            "PROPFIND" => {
+                let resource = existing.ok_or_else(|| error(StatusCode::NOT_FOUND, "no such todo"))?;
+                Ok(multistatus(vec![response(&resource.href(), &resource.props(false))]))
+            }
+            "PUT" => {
+                if existing.is_none() && is_id_name(&name) {
+                    return Err(error(
+                        StatusCode::FORBIDDEN,
+                        "names like `<number>.ics` are kept for the app's todos",
+                    ));
+                }
+                check_preconditions(&headers, existing.as_ref())?;
+                let parsed = ical::parse_vtodo(&body)
+                    .map_err(|e| error(StatusCode::BAD_REQUEST, &e))?;
+                let task = check_task(&parsed.summary)
+                    .map_err(|_| error(StatusCode::BAD_REQUEST, "SUMMARY can't be empty"))?;
+                let due = check_due(parsed.due.as_deref())
+                    .map_err(|_| error(StatusCode::BAD_REQUEST, "DUE isn't a date"))?;
+
+                match existing {
+                    Some(resource) => {
+                        let sql = format!(
+                            "UPDATE todos SET task = ?, done = ?, {}, priority = ?, due = ?,
+                            tags = ?, version = version + 1 WHERE id = ? AND version = ?
+                            RETURNING *",
+                            set_completed("?")
+                        );
+                        let todo = sqlx::query_as::<_, TodoItem>(&sql)
+                            .bind(task)
+                            .bind(parsed.done)
+                            .bind(parsed.done)
+                            .bind(parsed.priority)
+                            .bind(due)
+                            .bind(sqlx::types::Json(&parsed.tags))
+                            .bind(resource.todo.id)
+                            .bind(resource.todo.version)
+                            .fetch_optional(&pool)
+                            .await
+                            .map_err(internal)?
+                            // changed since it was read
+                            .ok_or_else(|| {
+                                error(StatusCode::PRECONDITION_FAILED, "todo changed")
+                            })?;
+                        todos_changed();
+
+                        let resource = Resource { todo, ..resource };
+                        Ok((StatusCode::NO_CONTENT, [(header::ETAG, resource.etag())]).into_response())
+                    }
+                    None => {
+                        let mut tx = pool.begin().await.map_err(internal)?;
+                        let todo = sqlx::query_as::<_, TodoItem>(
+                            "INSERT INTO todos
+                            (task, done, priority, due, tags, created, completed)
+                            VALUES (?1, ?2, ?3, ?4, ?5, date('now'),
+                            CASE WHEN ?2 THEN date('now') END) RETURNING *",
+                        )
+                        .bind(task)
+                        .bind(parsed.done)
+                        .bind(parsed.priority)
+                        .bind(due)
+                        .bind(sqlx::types::Json(&parsed.tags))
+                        .fetch_one(&mut *tx)
+                        .await
+                        .map_err(internal)?;
+                        let uid = parsed.uid.unwrap_or_else(|| ical::uid(todo.id));
+                        sqlx::query("INSERT INTO caldav_resources (todo_id, name, uid) VALUES (?, ?, ?)")
+                            .bind(todo.id)
+                            .bind(&name)
+                            .bind(&uid)
+                            .execute(&mut *tx)
+                            .await
+                            .map_err(internal)?;
+                        tx.commit().await.map_err(internal)?;
+                        todos_changed();
+
+                        let resource = Resource {
+                            todo,
+                            name: Some(name),
+                            uid: Some(uid),
+                        };
+                        Ok((StatusCode::CREATED, [(header::ETAG, resource.etag())]).into_response())
+                    }
+                }
+            }
+            "DELETE" => {
+                let resource = existing.ok_or_else(|| error(StatusCode::NOT_FOUND, "no such todo"))?;
+                check_preconditions(&headers, Some(&resource))?;
+                let deleted = sqlx::query("DELETE FROM todos WHERE id = ? AND version = ?")
+                    .bind(resource.todo.id)
+                    .bind(resource.todo.version)
+                    .execute(&pool)
+                    .await
+                    .map_err(internal)?;
+                if deleted.rows_affected() == 0 {
+                    return Err(error(StatusCode::PRECONDITION_FAILED, "todo changed"));
+                }
+                todos_changed();
+                Ok(StatusCode::NO_CONTENT.into_response())
+            }
+            _ => Err(not_allowed()),
+        }
+    }
+
+    /// Checks `If-Match` against the resource's ETag, and `If-None-Match: *` for
+    /// creating a resource only if it doesn't exist.
+    fn check_preconditions(headers: &HeaderMap, existing: Option<&Resource>) -> Result<()> {
+        let header = |name| headers.get(name).and_then(|value: &HeaderValue| value.to_str().ok());
+
+        let failed = match (header(header::IF_MATCH), header(header::IF_NONE_MATCH), existing) {
+            (Some(_), _, None) => true,
+            (Some(etag), _, Some(resource)) => etag.trim() != "*" && etag.trim() != resource.etag(),
+            (_, Some(etag), Some(_)) => etag.trim() == "*",
+            _ => false,
+        };
+        if failed {
+            Err(error(StatusCode::PRECONDITION_FAILED, "ETag doesn't match"))
+        } else {
+            Ok(())
+        }
+    }
+
+    async fn pool() -> Result<SqlitePool> {
+        db().await.map_err(internal)
+    }
+
+    async fn all(pool: &SqlitePool) -> Result<Vec<Resource>> {
+        sqlx::query_as::<_, Resource>(&format!("{SELECT} ORDER BY todos.id"))
+            .fetch_all(pool)
+            .await
+            .map_err(internal)
+    }
+
+    /// The resource named `name`, a client's name for todos it created, `<id>.ics`
+    /// for others. Clients can't create names like those, see [`is_id_name`], so the
+    /// two never clash.
+    async fn find(pool: &SqlitePool, name: &str) -> Result<Option<Resource>> {
+        sqlx::query_as::<_, Resource>(&format!(
+            "{SELECT} WHERE caldav_resources.name = ?1
+            OR (caldav_resources.name IS NULL AND todos.id || '.ics' = ?1)"
+        ))
+        .bind(name)
+        .fetch_optional(pool)
+        .await
+        .map_err(internal)
+    }
+
+    /// Whether `name` is like the `<id>.ics` of a todo that wasn't created by a client.
+    fn is_id_name(name: &str) -> bool {
+        name.strip_suffix(".ics")
+            .is_some_and(|id| !id.is_empty() && id.bytes().all(|b| b.is_ascii_digit()))
+    }
+
+    fn principal_props() -> String {
+        format!(
+            "<d:resourcetype><d:collection/><d:principal/></d:resourcetype>\
+            <d:displayname>Todos</d:displayname>\
+            <d:current-user-principal><d:href>{ROOT}</d:href></d:current-user-principal>\
+            <d:principal-URL><d:href>{ROOT}</d:href></d:principal-URL>\
+            <c:calendar-home-set><d:href>{ROOT}</d:href></c:calendar-home-set>"
+        )
+    }
+
+    async fn collection_props() -> Result<String> {
+        Ok(format!(
+            "<d:resourcetype><d:collection/><c:calendar/></d:resourcetype>\
+            <d:displayname>Todos</d:displayname>\
+            <d:current-user-principal><d:href>{ROOT}</d:href></d:current-user-principal>\
+            <c:supported-calendar-component-set><c:comp name=\"VTODO\"/></c:supported-calendar-component-set>\
+            <d:current-user-privilege-set>\
+                <d:privilege><d:read/></d:privilege><d:privilege><d:write/></d:privilege>\
+            </d:current-user-privilege-set>\
+            <d:supported-report-set>\
+                <d:supported-report><d:report><c:calendar-query/></d:report></d:supported-report>\
+                <d:supported-report><d:report><c:calendar-multiget/></d:report></d:supported-report>\
+            </d:supported-report-set>\
+            <cs:getctag>{}</cs:getctag>",
+            todos_tag().await.map_err(internal)?
+        ))
+    }
+
+    fn response(href: &str, props: &str) -> String {
+        format!(
+            "<d:response><d:href>{}</d:href><d:propstat><d:prop>{props}</d:prop>\
+            <d:status>HTTP/1.1 200 OK</d:status></d:propstat></d:response>",
+            xml_escape(href)
+        )
+    }
+
+    fn not_found(href: &str) -> String {
+        format!(
+            "<d:response><d:href>{}</d:href><d:status>HTTP/1.1 404 Not Found</d:status></d:response>",
+            xml_escape(href)
+        )
+    }
+
+    fn multistatus(responses: Vec<String>) -> Response {
+        let body = format!(
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>\
+            <d:multistatus xmlns:d=\"DAV:\" xmlns:c=\"urn:ietf:params:xml:ns:caldav\" \
+            xmlns:cs=\"http://calendarserver.org/ns/\">{}</d:multistatus>",
+            responses.concat()
+        );
+        (
+            StatusCode::MULTI_STATUS,
+            [(header::CONTENT_TYPE, "application/xml; charset=utf-8")],
+            body,
+        )
+            .into_response()
+    }
+
+    fn options() -> Response {
+        (
+            [
+                ("dav", "1, calendar-access"),
+                ("allow", "OPTIONS, GET, HEAD, PUT, DELETE, PROPFIND, REPORT"),
+            ],
+            StatusCode::OK,
+        )
+            .into_response()
+    }
+
+    /// Depth of a PROPFIND, 0, 1, or `u8::MAX` for infinity, which it is without the
+    /// header.
+    fn depth(headers: &HeaderMap) -> Result<u8> {
+        let depth = headers
+            .get("depth")
+            .map(|depth| depth.to_str().unwrap_or_default().trim().to_ascii_lowercase());
+        match depth.as_deref() {
+            Some("0") => Ok(0),
+            Some("1") => Ok(1),
+            None | Some("infinity") => Ok(u8::MAX),
+            Some(_) => Err(error(StatusCode::BAD_REQUEST, "Depth is 0, 1 or infinity")),
+        }
+    }
+
+    /// The REPORTs the calendar answers.
+    #[derive(Debug, PartialEq)]
+    enum Report {
+        /// `calendar-multiget`, the resources at these hrefs.
+        Multiget(Vec<String>),
+        /// `calendar-query`, the resources matching a filter.
+        Query(Filter),
+    }
+
+    impl Report {
+        fn parse(body: &str) -> Result<Report> {
+            let document = Document::parse(body)
+                .map_err(|e| error(StatusCode::BAD_REQUEST, &format!("invalid XML: {e}")))?;
+            let report = document.root_element();
+            if is(report, CALDAV, "calendar-multiget") {
+                let hrefs = children(report, DAV, "href")
+                    .map(|href| href.text().unwrap_or_default().trim().to_string())
+                    .collect();
+                Ok(Report::Multiget(hrefs))
+            } else if is(report, CALDAV, "calendar-query") {
+                let filter = children(report, CALDAV, "filter").next();
+                Ok(Report::Query(filter.map(Filter::parse).unwrap_or_default()))
+            } else {
+                Err(error(StatusCode::FORBIDDEN, "unsupported report"))
+            }
+        }
+    }
+
+    /// What a `calendar-query` filter asks of todos: to be `VTODO`s, in a time range,
+    /// and tests of their properties.
+    #[derive(Debug, Default, PartialEq)]
+    struct Filter {
+        /// Set when it asks for something other than `VTODO`s, which there are none of.
+        nothing: bool,
+        /// The `start` and `end` of a `time-range`, UTC date-times like `20240301T120000Z`.
+        time_range: Option<(Option<String>, Option<String>)>,
+        properties: Vec<PropertyFilter>,
+    }
+
+    /// A `prop-filter`. Time ranges and parameters in it are only checked for the
+    /// property being defined.
+    #[derive(Debug, PartialEq)]
+    struct PropertyFilter {
+        name: String,
+        test: PropertyTest,
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum PropertyTest {
+        Defined,
+        NotDefined,
+        /// A `text-match`, a value containing `text`, ignoring ASCII case, or when
+        /// negated one that doesn't.
+        Contains { text: String, negate: bool },
+    }
+
+    impl Filter {
+        fn parse(filter: Node) -> Filter {
+            let mut parsed = Filter::default();
+            for calendar in children(filter, CALDAV, "comp-filter") {
+                if !named(calendar, "VCALENDAR") || has(calendar, "is-not-defined") {
+                    parsed.nothing = true;
+                }
+                for component in children(calendar, CALDAV, "comp-filter") {
+                    let defined = !has(component, "is-not-defined");
+                    if named(component, "VTODO") != defined {
+                        parsed.nothing = true;
+                    } else if defined {
+                        parsed.parse_vtodo(component);
+                    }
+                }
+            }
+            parsed
+        }
+
+        fn parse_vtodo(&mut self, vtodo: Node) {
+            for node in vtodo.children() {
+                if is(node, CALDAV, "time-range") {
+                    let bound = |name| node.attribute(name).map(str::to_ascii_uppercase);
+                    self.time_range = Some((bound("start"), bound("end")));
+                } else if is(node, CALDAV, "prop-filter") {
+                    let test = match children(node, CALDAV, "text-match").next() {
+                        Some(text_match) => PropertyTest::Contains {
+                            text: text_match.text().unwrap_or_default().to_string(),
+                            negate: text_match.attribute("negate-condition") == Some("yes"),
+                        },
+                        None if has(node, "is-not-defined") => PropertyTest::NotDefined,
+                        None => PropertyTest::Defined,
+                    };
+                    self.properties.push(PropertyFilter {
+                        name: node.attribute("name").unwrap_or_default().to_ascii_uppercase(),
+                        test,
+                    });
+                } else if is(node, CALDAV, "comp-filter") && !has(node, "is-not-defined") {
+                    // todos have nothing in them, like alarms
+                    self.nothing = true;
+                }
+            }
+        }
+
+        fn matches(&self, resource: &Resource) -> bool {
+            if self.nothing {
+                return false;
+            }
+            // due dates are whole days, todos without one are in every range
+            let in_range = match (&self.time_range, &resource.todo.due) {
+                (Some((start, end)), Some(due)) => {
+                    let day = due.replace('-', "");
+                    start.as_ref().is_none_or(|start| *start <= format!("{day}T235959Z"))
+                        && end.as_ref().is_none_or(|end| *end > format!("{day}T000000Z"))
+                }
+                _ => true,
+            };
+            if !in_range {
+                return false;
+            }
+
+            let properties = resource.properties();
+            self.properties.iter().all(|filter| {
+                let values: Vec<_> = properties
+                    .iter()
+                    .filter(|(name, _)| *name == filter.name)
+                    .map(|(_, value)| ical::unescape(value).to_ascii_lowercase())
+                    .collect();
+                match &filter.test {
+                    PropertyTest::Defined => !values.is_empty(),
+                    PropertyTest::NotDefined => values.is_empty(),
+                    PropertyTest::Contains { text, negate } => {
+                        let text = text.to_ascii_lowercase();
+                        !values.is_empty()
+                            && values.iter().any(|value| value.contains(&text)) != *negate
+                    }
+                }
+            })
+        }
+    }
+
+    fn is(node: Node, namespace: &str, name: &str) -> bool {
+        node.is_element()
+            && node.tag_name().namespace() == Some(namespace)
+            && node.tag_name().name() == name
+    }
+
+    fn children<'a, 'input: 'a>(
+        node: Node<'a, 'input>,
+        namespace: &'a str,
+        name: &'a str,
+    ) -> impl Iterator<Item = Node<'a, 'input>> {
+        node.children().filter(move |child| is(*child, namespace, name))
+    }
+
+    /// Whether a CalDAV filter has a `name` element, like `is-not-defined`.
+    fn has(node: Node, name: &str) -> bool {
+        children(node, CALDAV, name).next().is_some()
+    }
+
+    /// Whether a `comp-filter` is for `component`.
+    fn named(node: Node, component: &str) -> bool {
+        node.attribute("name")
+            .is_some_and(|name| name.eq_ignore_ascii_case(component))
+    }
+
+    fn xml_escape(text: &str) -> String {
+        text.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;")
+    }
+
+    /// Checks a user's token, as the Basic auth password with any user name, as
+    /// calendar apps ask for one, or as a bearer token. Like calendar feeds, CalDAV
+    /// is never open: without users there's none.
+    async fn authorize(headers: &HeaderMap) -> Result<()> {
+        if auth::is_open().await {
+            let message = "CalDAV needs a user, add one with `leptos-todo create-user <name>`";
+            return Err(error(StatusCode::FORBIDDEN, message));
+        }
+
+        let password = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Basic "))
+            .and_then(|encoded| BASE64.decode(encoded.trim()).ok())
+            .and_then(|decoded| String::from_utf8(decoded).ok())
+            .and_then(|credentials| Some(credentials.split_once(':')?.1.to_string()));
+
+        let user = match password {
+            Some(password) => auth::user_with_token(&password).await,
+            None => None,
+        };
+        match user {
+            Some(_) => Ok(()),
+            None if auth::request_user(headers).await.is_some() => Ok(()),
+            None => Err((
+                StatusCode::UNAUTHORIZED,
+                [(header::WWW_AUTHENTICATE, "Basic realm=\"todos\"")],
+            )
+                .into_response()),
+        }
+    }
+
+    fn error(status: StatusCode, message: &str) -> Response {
+        (status, message.to_string()).into_response()
+    }
+
+    fn not_allowed() -> Response {
+        error(StatusCode::METHOD_NOT_ALLOWED, "method not allowed")
+    }
+
+    fn internal(e: impl std::fmt::Display) -> Response {
+        error(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string())
+    }
+}}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+    use super::*;
+
+    fn resource(todo: TodoItem) -> Resource {
+        Resource {
+            todo,
+            name: None,
+            uid: None,
+        }
+    }
+
+    fn query(filter: &str) -> Filter {
+        let body = format!(
+            "<?xml version=\"1.0\"?>\
+            <C:calendar-query xmlns:D=\"DAV:\" xmlns:C=\"urn:ietf:params:xml:ns:caldav\">\
+            <D:prop><D:getetag/></D:prop><C:filter>{filter}</C:filter></C:calendar-query>"
+        );
+        match Report::parse(&body).ok() {
+            Some(Report::Query(filter)) => filter,
+            other => panic!("not a query: {other:?}"),
+        }
+    }
+
+    fn todo(due: Option<&str>, done: bool) -> Resource {
+        resource(TodoItem {
+            task: "call mom".into(),
+            done,
+            due: due.map(Into::into),
+            ..TodoItem::default()
+        })
+    }
+
+    #[test]
+    fn reads_the_hrefs_of_a_multiget() {
+        let body = "<multiget:calendar-multiget xmlns:multiget=\"urn:ietf:params:xml:ns:caldav\" \
+            xmlns:dav=\"DAV:\"><dav:prop><dav:getetag/></dav:prop>\
+            <dav:href> /caldav/todos/1.ics </dav:href>\
+            <dav:href>/caldav/todos/a&amp;b.ics</dav:href></multiget:calendar-multiget>";
+        assert_eq!(
+            Report::parse(body).ok(),
+            Some(Report::Multiget(vec![
+                "/caldav/todos/1.ics".into(),
+                "/caldav/todos/a&b.ics".into(),
+            ]))
+        );
+        assert!(Report::parse("<calendar-multiget>").is_err());
+        assert!(Report::parse("<D:sync-collection xmlns:D=\"DAV:\"/>").is_err());
+    }
+
+    #[test]
+    fn queries_for_other_components_match_nothing() {
+        let events = query(
+            "<C:comp-filter name=\"VCALENDAR\"><C:comp-filter name=\"VEVENT\"/></C:comp-filter>",
+        );
+        assert!(!events.matches(&todo(None, false)));
+
+        let todos = query(
+            "<C:comp-filter name=\"VCALENDAR\"><C:comp-filter name=\"VTODO\"/></C:comp-filter>",
+        );
+        assert_eq!(todos, Filter::default());
+        assert!(todos.matches(&todo(None, false)));
+
+        let no_events = query(
+            "<C:comp-filter name=\"VCALENDAR\"><C:comp-filter name=\"VEVENT\">\
+            <C:is-not-defined/></C:comp-filter></C:comp-filter>",
+        );
+        assert!(no_events.matches(&todo(None, false)));
+    }
+
+    #[test]
+    fn matches_due_dates_in_a_time_range() {
+        let march = query(
+            "<C:comp-filter name=\"VCALENDAR\"><C:comp-filter name=\"VTODO\">\
+            <C:time-range start=\"20240301T000000Z\" end=\"20240401T000000Z\"/>\
+            </C:comp-filter></C:comp-filter>",
+        );
+        assert!(march.matches(&todo(Some("2024-03-01"), false)));
+        assert!(march.matches(&todo(Some("2024-03-31"), false)));
+        assert!(!march.matches(&todo(Some("2024-04-01"), false)));
+        assert!(!march.matches(&todo(Some("2024-02-29"), false)));
+        // without a due date, a todo is in every range
+        assert!(march.matches(&todo(None, false)));
+    }
+
+    #[test]
+    fn matches_properties() {
+        let not_done = query(
+            "<C:comp-filter name=\"VCALENDAR\"><C:comp-filter name=\"VTODO\">\
+            <C:prop-filter name=\"status\">\
+            <C:text-match negate-condition=\"yes\">completed</C:text-match>\
+            </C:prop-filter></C:comp-filter></C:comp-filter>",
+        );
+        assert!(not_done.matches(&todo(None, false)));
+        assert!(!not_done.matches(&todo(None, true)));
+
+        let undated = query(
+            "<C:comp-filter name=\"VCALENDAR\"><C:comp-filter name=\"VTODO\">\
+            <C:prop-filter name=\"DUE\"><C:is-not-defined/></C:prop-filter>\
+            </C:comp-filter></C:comp-filter>",
+        );
+        assert!(undated.matches(&todo(None, false)));
+        assert!(!undated.matches(&todo(Some("2024-03-01"), false)));
+
+        let summary = query(
+            "<C:comp-filter name=\"VCALENDAR\"><C:comp-filter name=\"VTODO\">\
+            <C:prop-filter name=\"SUMMARY\"><C:text-match>MOM</C:text-match></C:prop-filter>\
+            </C:comp-filter></C:comp-filter>",
+        );
+        assert!(summary.matches(&todo(None, false)));
+    }
+
+    #[test]
+    fn reads_the_depth() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(depth(&headers).ok(), Some(u8::MAX));
+        for (value, expected) in [("0", 0), ("1", 1), ("Infinity", u8::MAX)] {
+            headers.insert("depth", value.parse().unwrap());
+            assert_eq!(depth(&headers).ok(), Some(expected));
+        }
+        headers.insert("depth", "2".parse().unwrap());
+        assert!(depth(&headers).is_err());
+    }
+
+    #[test]
+    fn keeps_id_names_for_the_app() {
+        assert!(is_id_name("12.ics"));
+        assert!(!is_id_name(".ics"));
+        assert!(!is_id_name("12"));
+        assert!(!is_id_name("0b0e6a2c-client-made.ics"));
+    }
+}
@@ -0,0 +1,193 @@
+//! Just enough of RFC 4180 CSV to round-trip todos through spreadsheets.
+
+use std::{borrow::Cow, fmt};
+
+/// Byte order mark spreadsheets write at the start of UTF-8 files, and need to
+/// read them as UTF-8.
+pub const BOM: char = '\u{feff}';
+
+/// A row of a CSV file, with the line it starts on for error messages.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Record {
+    pub line: usize,
+    pub fields: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CsvError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for CsvError {}
+
+/// Quotes a field if it has a delimiter, quote or line break in it.
+pub fn field(value: &str) -> Cow<'_, str> {
+    if value.contains([',', ';', '"', '\r', '\n']) || value.trim() != value {
+        Cow::Owned(format!("\"{}\"", value.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+/// A row of fields ending in CRLF, as spreadsheets write them.
+pub fn row<'a>(fields: impl IntoIterator<Item = &'a str>) -> String {
+    let fields: Vec<_> = fields.into_iter().map(field).collect();
+    format!("{}\r\n", fields.join(","))
+}
+
+/// Reads every row, skipping blank lines. Fields are separated by commas, or by
+/// semicolons when the first line has those but no commas, as spreadsheets set to
+/// some locales write them. Lines end in LF, CRLF or a lone CR, as old Mac
+/// spreadsheets write them, and line breaks in fields are read as LF.
+pub fn parse(input: &str) -> Result<Vec<Record>, CsvError> {
+    let input = input.strip_prefix(BOM).unwrap_or(input);
+    let first_line = input.split(['\r', '\n']).next().unwrap_or_default();
+    let delimiter = if !first_line.contains(',') && first_line.contains(';') {
+        ';'
+    } else {
+        ','
+    };
+
+    let mut records = vec![];
+    let mut fields = vec![];
+    let mut field = String::new();
+    let mut line = 1;
+    let mut record_line = 1;
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if field.is_empty() => {
+                let quote_line = line;
+                loop {
+                    match chars.next() {
+                        Some('"') if chars.peek() == Some(&'"') => {
+                            chars.next();
+                            field.push('"');
+                        }
+                        Some('"') => break,
+                        Some('\r') if chars.peek() == Some(&'\n') => {}
+                        Some('\r' | '\n') => {
+                            line += 1;
+                            field.push('\n');
+                        }
+                        Some(c) => field.push(c),
+                        None => {
+                            return Err(CsvError {
+                                line: quote_line,
+                                message: "missing closing `\"`".into(),
+                            })
+                        }
+                    }
+                }
+                match chars.peek() {
+                    None | Some('\r' | '\n') => {}
+                    Some(&c) if c == delimiter => {}
+                    Some(_) => {
+                        return Err(CsvError {
+                            line,
+                            message: format!(
+                                "unexpected text after closing `\"`, expected `{delimiter}`"
+                            ),
+                        })
+                    }
+                }
+            }
+            c if c == delimiter => fields.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\r' | '\n' => {
+                fields.push(std::mem::take(&mut field));
+                push_record(&mut records, record_line, std::mem::take(&mut fields));
+                line += 1;
+                record_line = line;
+            }
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    push_record(&mut records, record_line, fields);
+
+    Ok(records)
+}
+
+fn push_record(records: &mut Vec<Record>, line: usize, fields: Vec<String>) {
+    if !(fields.len() == 1 && fields[0].trim().is_empty()) {
+        records.push(Record { line, fields });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(input: &str) -> Vec<Vec<String>> {
+        parse(input)
+            .unwrap()
+            .into_iter()
+            .map(|record| record.fields)
+            .collect()
+    }
+
+    #[test]
+    fn reads_quoted_fields() {
+        assert_eq!(
+            fields("task,done\r\n\"milk, oat\",true\r\n\"say \"\"hi\"\"\",\r\n"),
+            [
+                vec!["task", "done"],
+                vec!["milk, oat", "true"],
+                vec!["say \"hi\"", ""],
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_line_breaks_in_fields_as_lf() {
+        let records = parse("task\r\n\"two\r\nlines\"\r\n\"old\rmac\"\rnext\r").unwrap();
+        let lines: Vec<_> = records.iter().map(|record| record.line).collect();
+        assert_eq!(lines, [1, 2, 4, 6]);
+        assert_eq!(records[1].fields, ["two\nlines"]);
+        assert_eq!(records[2].fields, ["old\nmac"]);
+        assert_eq!(records[3].fields, ["next"]);
+    }
+
+    #[test]
+    fn skips_the_bom_and_blank_lines() {
+        assert_eq!(
+            fields(&format!("{BOM}task\n\nmilk\n")),
+            [vec!["task"], vec!["milk"]]
+        );
+    }
+
+    #[test]
+    fn detects_semicolons() {
+        assert_eq!(
+            fields("task;done\nmilk, oat;true\n"),
+            [vec!["task", "done"], vec!["milk, oat", "true"]]
+        );
+        assert_eq!(
+            fields("task,note\na;b,c\n"),
+            [vec!["task", "note"], vec!["a;b", "c"]]
+        );
+    }
+
+    #[test]
+    fn points_at_broken_quotes() {
+        let error = |input: &str| parse(input).unwrap_err();
+        assert_eq!(error("task\n\"milk\n").line, 2);
+        assert_eq!(error("task\n\"milk\"x,y\n").line, 2);
+    }
+
+    #[test]
+    fn round_trips_rows() {
+        let values = ["plain", "a, b", " padded ", "say \"hi\"", "two\nlines"];
+        let written = format!("{BOM}{}", row(values));
+        assert_eq!(fields(&written), [values]);
+    }
+}
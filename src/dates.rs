@@ -0,0 +1,112 @@
+//! Calendar dates as `YYYY-MM-DD`, the way todos keep their due dates, without a
+//! date library. Days are counted in UTC, on the server and in the browser alike.
+
+use cfg_if::cfg_if;
+
+const SECONDS_PER_DAY: u64 = 86_400;
+
+cfg_if! {
+    if #[cfg(feature = "hydrate")] {
+        /// Seconds since 1970-01-01 UTC.
+        pub fn unix_time() -> u64 {
+            (wasm_bindgen_futures::js_sys::Date::now() / 1000.0) as u64
+        }
+    } else {
+        use std::time::{SystemTime, UNIX_EPOCH};
+
+        /// Seconds since 1970-01-01 UTC.
+        pub fn unix_time() -> u64 {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |since| since.as_secs())
+        }
+    }
+}
+
+/// Days since 1970-01-01 UTC.
+pub fn today_number() -> i64 {
+    (unix_time() / SECONDS_PER_DAY) as i64
+}
+
+/// Today's date.
+pub fn today() -> String {
+    format(today_number())
+}
+
+/// The date `days` from today, before it when negative.
+pub fn days_from_today(days: i64) -> String {
+    format(today_number() + days)
+}
+
+/// The date `days` after 1970-01-01.
+pub fn format(days: i64) -> String {
+    let (year, month, day) = civil_date(days);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// Whether `text` is a `YYYY-MM-DD` date that exists.
+pub fn is_date(text: &str) -> bool {
+    let number = |range: std::ops::Range<usize>| {
+        let digits = text.get(range)?;
+        digits
+            .bytes()
+            .all(|digit| digit.is_ascii_digit())
+            .then(|| digits.parse::<u32>().ok())?
+    };
+    let (Some(year), Some(month), Some(day)) = (number(0..4), number(5..7), number(8..10)) else {
+        return false;
+    };
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days = match month {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        1..=12 => 31,
+        _ => return false,
+    };
+    text.len() == 10 && &text[4..5] == "-" && &text[7..8] == "-" && (1..=days).contains(&day)
+}
+
+/// The year, month and day `days` after 1970-01-01.
+pub fn civil_date(days: i64) -> (i64, i64, i64) {
+    // civil_from_days from http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_days_since_the_epoch() {
+        assert_eq!(format(0), "1970-01-01");
+        assert_eq!(format(-1), "1969-12-31");
+        assert_eq!(format(19_782), "2024-02-29");
+        assert_eq!(format(19_783), "2024-03-01");
+    }
+
+    #[test]
+    fn tells_dates_that_exist() {
+        assert!(is_date("2024-02-29"));
+        assert!(!is_date("2023-02-29"));
+        assert!(!is_date("2024-13-01"));
+        assert!(!is_date("2024-1-01"));
+        assert!(!is_date("2024-01-01T00:00"));
+        assert!(!is_date("tomorrow"));
+    }
+}
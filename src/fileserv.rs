@@ -5,14 +5,21 @@ cfg_if! { if #[cfg(feature = "ssr")] {
         body::{boxed, Body, BoxBody},
         extract::State,
         response::IntoResponse,
-        http::{Request, Response, StatusCode, Uri},
+        http::{header, Request, Response, StatusCode, Uri},
     };
     use axum::response::Response as AxumResponse;
     use tower::ServiceExt;
     use tower_http::services::ServeDir;
     use leptos::*;
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+    use std::path::Path;
+    use tokio::sync::OnceCell;
     use crate::app::App;
 
+    /// The service worker as served, the build output doesn't change while running.
+    static SERVICE_WORKER: OnceCell<String> = OnceCell::const_new();
+
     pub async fn file_and_error_handler(uri: Uri, State(options): State<LeptosOptions>, req: Request<Body>) -> AxumResponse {
         let root = options.site_root.clone();
         let res = get_static_file(uri.clone(), &root).await.unwrap();
@@ -37,4 +44,55 @@ cfg_if! { if #[cfg(feature = "ssr")] {
             )),
         }
     }
+
+    /// Serves the service worker with its cache version and precache list filled in
+    /// from the build output under site-pkg-dir, so each new build replaces the caches
+    /// of the previous one. Rendered on the first request, and again only after failing.
+    pub async fn service_worker(State(options): State<LeptosOptions>) -> AxumResponse {
+        match SERVICE_WORKER.get_or_try_init(|| render_service_worker(&options)).await {
+            Ok(script) => (
+                [
+                    (header::CONTENT_TYPE, "application/javascript"),
+                    (header::CACHE_CONTROL, "no-cache"),
+                ],
+                script.clone(),
+            )
+                .into_response(),
+            Err(err) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Something went wrong: {err}"),
+            )
+                .into_response(),
+        }
+    }
+
+    async fn render_service_worker(options: &LeptosOptions) -> std::io::Result<String> {
+        let root = Path::new(&options.site_root);
+        let pkg_dir = root.join(&options.site_pkg_dir);
+        let script = tokio::fs::read_to_string(root.join("sw.js")).await?;
+
+        let mut files = vec![];
+        let mut entries = tokio::fs::read_dir(&pkg_dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            if entry.file_type().await?.is_file() {
+                files.push(entry.file_name().to_string_lossy().into_owned());
+            }
+        }
+        files.sort();
+
+        let mut hasher = DefaultHasher::new();
+        for file in &files {
+            file.hash(&mut hasher);
+            tokio::fs::read(pkg_dir.join(file)).await?.hash(&mut hasher);
+        }
+
+        let urls: Vec<_> = files
+            .iter()
+            .map(|file| format!("/{}/{file}", options.site_pkg_dir))
+            .collect();
+
+        Ok(script
+            .replace("__CACHE_VERSION__", &format!("{:016x}", hasher.finish()))
+            .replace("__PKG_URLS__", &serde_json::to_string(&urls)?))
+    }
 }}
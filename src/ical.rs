@@ -0,0 +1,506 @@
+//! Todos as an [RFC 5545](https://www.rfc-editor.org/rfc/rfc5545) calendar of `VTODO`s,
+//! for calendar apps to subscribe to.
+//!
+//! A todo's priority, due date and tags are its `PRIORITY`, `DUE` and `CATEGORIES`.
+
+use crate::{
+    app::{parse_tags, Priority, TodoItem},
+    dates,
+};
+use cfg_if::cfg_if;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Longest content line, in octets without the line break.
+const LINE_LIMIT: usize = 75;
+
+/// Escapes a TEXT value.
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Folds a content line into lines of at most [`LINE_LIMIT`] octets, continued
+/// with a space, without splitting characters, and ends it with CRLF.
+pub fn fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + 2);
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > LINE_LIMIT {
+            folded.push_str("\r\n ");
+            // the space counts towards the continued line
+            length = 1;
+        }
+        folded.push(c);
+        length += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+/// The UID of todos not created by a CalDAV client, ids aren't reused so calendar
+/// apps can tell todos apart by them.
+pub fn uid(id: u32) -> String {
+    format!("todo-{id}@leptos-todo")
+}
+
+/// A todo as a `VTODO`, stamped at `stamp`, a UTC date-time like `20240301T120000Z`.
+pub fn vtodo(todo: &TodoItem, uid: &str, stamp: &str) -> String {
+    let mut lines = vec![
+        "BEGIN:VTODO".to_string(),
+        format!("UID:{uid}"),
+        format!("DTSTAMP:{stamp}"),
+        format!("SEQUENCE:{}", todo.version),
+        format!("SUMMARY:{}", escape(&todo.task)),
+        format!(
+            "STATUS:{}",
+            if todo.done {
+                "COMPLETED"
+            } else {
+                "NEEDS-ACTION"
+            }
+        ),
+    ];
+    if let Some(priority) = todo.priority {
+        lines.push(format!("PRIORITY:{}", priority_number(priority)));
+    }
+    if let Some(due) = &todo.due {
+        lines.push(format!("DUE;VALUE=DATE:{}", due.replace('-', "")));
+    }
+    if !todo.tags.is_empty() {
+        let categories: Vec<_> = todo.tags.iter().map(|tag| escape(tag)).collect();
+        lines.push(format!("CATEGORIES:{}", categories.join(",")));
+    }
+    lines.push("END:VTODO".into());
+
+    lines.iter().map(|line| fold(line)).collect()
+}
+
+/// A priority as a `PRIORITY` number, 1 is the highest and 9 the lowest, with
+/// medium in the middle as RFC 5545 suggests.
+fn priority_number(priority: Priority) -> u8 {
+    match priority {
+        Priority::High => 1,
+        Priority::Medium => 5,
+        Priority::Low => 9,
+    }
+}
+
+/// Undoes [`priority_number`] for any number a client sends, 0 being none.
+fn priority_of(number: u8) -> Option<Priority> {
+    match number {
+        1..=4 => Some(Priority::High),
+        5 => Some(Priority::Medium),
+        6..=9 => Some(Priority::Low),
+        _ => None,
+    }
+}
+
+/// A calendar of `VTODO`s, named `name` for calendar apps subscribing to it.
+pub fn calendar(name: Option<&str>, vtodos: &[String]) -> String {
+    let mut calendar = [
+        "BEGIN:VCALENDAR",
+        "VERSION:2.0",
+        "PRODID:-//leptos-todo//todos//EN",
+    ]
+    .map(fold)
+    .concat();
+    if let Some(name) = name {
+        calendar.push_str(&fold(&format!("X-WR-CALNAME:{}", escape(name))));
+    }
+    vtodos.iter().for_each(|vtodo| calendar.push_str(vtodo));
+    calendar.push_str(&fold("END:VCALENDAR"));
+    calendar
+}
+
+/// The current UTC date-time like `20240301T120000Z`.
+pub fn now() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    let (year, month, day) = dates::civil_date((seconds / 86_400) as i64);
+    let time = seconds % 86_400;
+    format!(
+        "{year:04}{month:02}{day:02}T{:02}{:02}{:02}Z",
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    )
+}
+
+/// The parts of a `VTODO` a todo keeps, as a CalDAV client sent it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ParsedTodo {
+    pub uid: Option<String>,
+    pub summary: String,
+    pub done: bool,
+    pub priority: Option<Priority>,
+    /// As `YYYY-MM-DD`.
+    pub due: Option<String>,
+    /// The `CATEGORIES`, as tags, see [`parse_tags`].
+    pub tags: Vec<String>,
+}
+
+/// Reads the one `VTODO` of a calendar resource.
+pub fn parse_vtodo(text: &str) -> Result<ParsedTodo, String> {
+    let mut todo = ParsedTodo::default();
+    let mut status = None;
+    let mut completed = false;
+    let mut categories = vec![];
+
+    for (name, value) in properties(text)? {
+        match name.as_str() {
+            "UID" => todo.uid = Some(value),
+            "SUMMARY" => todo.summary = unescape(&value),
+            "STATUS" => status = Some(value.to_ascii_uppercase()),
+            "COMPLETED" => completed = true,
+            "PRIORITY" => todo.priority = value.trim().parse().ok().and_then(priority_of),
+            "DUE" => todo.due = date(&value),
+            "CATEGORIES" => categories.extend(split_list(&value)),
+            _ => {}
+        }
+    }
+
+    // some clients only set the completion time
+    todo.done = match status.as_deref() {
+        Some(status) => status == "COMPLETED",
+        None => completed,
+    };
+    todo.tags = parse_tags(&categories.join(","));
+    Ok(todo)
+}
+
+/// The properties of the one `VTODO` of a calendar resource, by their name in
+/// uppercase and without parameters, with their values as written. What's nested
+/// in it, like alarms, is left out.
+pub fn properties(text: &str) -> Result<Vec<(String, String)>, String> {
+    let unfolded = text
+        .replace("\r\n ", "")
+        .replace("\r\n\t", "")
+        .replace("\n ", "")
+        .replace("\n\t", "");
+
+    let mut properties = None::<Vec<(String, String)>>;
+    // components open inside the VTODO, `None` outside of it
+    let mut depth = None::<usize>;
+
+    for line in unfolded.lines() {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let name = name
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .to_ascii_uppercase();
+        let value = value.trim_end();
+
+        match (name.as_str(), depth) {
+            ("BEGIN", None) if value.eq_ignore_ascii_case("VTODO") => {
+                if properties.is_some() {
+                    return Err("only one VTODO per resource".into());
+                }
+                properties = Some(vec![]);
+                depth = Some(0);
+            }
+            ("BEGIN", Some(nested)) => depth = Some(nested + 1),
+            ("END", Some(0)) => depth = None,
+            ("END", Some(nested)) => depth = Some(nested - 1),
+            (_, Some(0)) => {
+                let properties = properties.as_mut().expect("inside a VTODO");
+                properties.push((name, value.to_string()));
+            }
+            _ => {}
+        }
+    }
+
+    properties.ok_or_else(|| "no VTODO in the resource".into())
+}
+
+/// Undoes [`escape`].
+pub fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => unescaped.push('\n'),
+            Some(c) => unescaped.push(c),
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+/// The values of a list like `CATEGORIES`, split at the commas that aren't escaped.
+fn split_list(text: &str) -> Vec<String> {
+    let mut values = vec![];
+    let mut start = 0;
+    let mut escaped = false;
+    for (i, c) in text.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            ',' => {
+                values.push(unescape(&text[start..i]));
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    values.push(unescape(&text[start..]));
+    values
+}
+
+/// The `YYYY-MM-DD` date of a DATE or DATE-TIME value like `20240301T120000Z`.
+fn date(value: &str) -> Option<String> {
+    let digits = value
+        .get(..8)
+        .filter(|digits| digits.bytes().all(|b| b.is_ascii_digit()))?;
+    Some(format!(
+        "{}-{}-{}",
+        &digits[..4],
+        &digits[4..6],
+        &digits[6..]
+    ))
+}
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use crate::app::db;
+        use crate::auth::{self, User};
+        use axum::{
+            extract::{Path, Query},
+            http::{header, HeaderMap, StatusCode},
+            response::{IntoResponse, Response},
+        };
+        use serde::Deserialize;
+
+        #[derive(Deserialize)]
+        pub struct FeedOptions {
+            /// A user's token, as calendar apps can't sign in.
+            #[serde(default)]
+            token: String,
+        }
+
+        /// `/api/todos.ics`, a calendar of every todo.
+        pub async fn feed(Query(options): Query<FeedOptions>, headers: HeaderMap) -> Response {
+            respond(None, &options, &headers).await
+        }
+
+        /// `/api/lists/<list>.ics`, a calendar of the todos in a list, by its name.
+        pub async fn list_feed(
+            Path(file): Path<String>,
+            Query(options): Query<FeedOptions>,
+            headers: HeaderMap,
+        ) -> Response {
+            match file.strip_suffix(".ics") {
+                Some(list) => respond(Some(list), &options, &headers).await,
+                None => (StatusCode::NOT_FOUND, "feeds end in `.ics`").into_response(),
+            }
+        }
+
+        /// The user subscribing, by the token in `?token=`, or the one a signed-in
+        /// browser sends. Unlike the rest of the app, feeds are never open, as their
+        /// links end up in calendar apps and their sync services: without users there
+        /// are none.
+        async fn subscriber(
+            options: &FeedOptions,
+            headers: &HeaderMap,
+        ) -> Result<User, Response> {
+            if auth::is_open().await {
+                return Err((
+                    StatusCode::FORBIDDEN,
+                    "calendar feeds need a user, add one with `leptos-todo create-user <name>`",
+                )
+                    .into_response());
+            }
+            let user = match auth::user_with_token(options.token.trim()).await {
+                Some(user) => Some(user),
+                None => auth::request_user(headers).await,
+            };
+            user.ok_or_else(|| {
+                (StatusCode::UNAUTHORIZED, "missing or wrong `token`").into_response()
+            })
+        }
+
+        async fn respond(
+            list: Option<&str>,
+            options: &FeedOptions,
+            headers: &HeaderMap,
+        ) -> Response {
+            if let Err(response) = subscriber(options, headers).await {
+                return response;
+            }
+
+            let pool = match db().await {
+                Ok(pool) => pool,
+                Err(e) => {
+                    return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+                }
+            };
+
+            let todos = match list {
+                Some(list) => {
+                    sqlx::query_as::<_, TodoItem>(
+                        "SELECT * FROM todos WHERE list = ? COLLATE NOCASE ORDER BY id",
+                    )
+                    .bind(list)
+                    .fetch_all(&pool)
+                    .await
+                }
+                None => {
+                    sqlx::query_as::<_, TodoItem>("SELECT * FROM todos ORDER BY id")
+                        .fetch_all(&pool)
+                        .await
+                }
+            };
+
+            match todos {
+                Ok(todos) => {
+                    let name = match list {
+                        Some(list) => format!("Todos: {list}"),
+                        None => "Todos".to_string(),
+                    };
+                    let stamp = now();
+                    let vtodos: Vec<_> = todos
+                        .iter()
+                        .map(|todo| vtodo(todo, &uid(todo.id), &stamp))
+                        .collect();
+                    (
+                        [(header::CONTENT_TYPE, "text/calendar; charset=utf-8")],
+                        calendar(Some(&name), &vtodos),
+                    )
+                        .into_response()
+                }
+                Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todo() -> TodoItem {
+        TodoItem {
+            id: 7,
+            task: "call mom; then dad, maybe".into(),
+            version: 2,
+            priority: Some(Priority::Medium),
+            due: Some("2024-04-01".into()),
+            tags: vec!["family".into(), "phone".into()],
+            ..TodoItem::default()
+        }
+    }
+
+    #[test]
+    fn writes_the_todo_as_a_vtodo() {
+        let vtodo = vtodo(&todo(), &uid(7), "20240301T120000Z");
+        assert_eq!(
+            vtodo.split("\r\n").collect::<Vec<_>>(),
+            [
+                "BEGIN:VTODO",
+                "UID:todo-7@leptos-todo",
+                "DTSTAMP:20240301T120000Z",
+                "SEQUENCE:2",
+                "SUMMARY:call mom\\; then dad\\, maybe",
+                "STATUS:NEEDS-ACTION",
+                "PRIORITY:5",
+                "DUE;VALUE=DATE:20240401",
+                "CATEGORIES:family,phone",
+                "END:VTODO",
+                "",
+            ]
+        );
+    }
+
+    #[test]
+    fn folds_long_lines_between_characters() {
+        let line = format!("SUMMARY:{}", "é".repeat(40));
+        let folded = fold(&line);
+        assert!(folded.split("\r\n").all(|part| part.len() <= LINE_LIMIT));
+        assert_eq!(folded.replace("\r\n ", ""), format!("{line}\r\n"));
+    }
+
+    #[test]
+    fn reads_back_what_it_writes() {
+        let parsed = parse_vtodo(&calendar(None, &[vtodo(&todo(), "u", "20240301T120000Z")]));
+        assert_eq!(
+            parsed,
+            Ok(ParsedTodo {
+                uid: Some("u".into()),
+                summary: "call mom; then dad, maybe".into(),
+                done: false,
+                priority: Some(Priority::Medium),
+                due: Some("2024-04-01".into()),
+                tags: vec!["family".into(), "phone".into()],
+            })
+        );
+    }
+
+    #[test]
+    fn reads_what_clients_send() {
+        let parsed = parse_vtodo(
+            "BEGIN:VCALENDAR\n\
+            BEGIN:VTODO\n\
+            UID:abc\n\
+            SUMMARY:a long\n  summary\n\
+            priority:2\n\
+            DUE;TZID=Europe/Paris:20240402T090000\n\
+            CATEGORIES:Home\\, too,Work\n\
+            CATEGORIES:errands\n\
+            COMPLETED:20240402T100000Z\n\
+            BEGIN:VALARM\n\
+            SUMMARY:not the summary\n\
+            END:VALARM\n\
+            END:VTODO\n\
+            END:VCALENDAR\n",
+        )
+        .unwrap();
+        assert_eq!(parsed.summary, "a long summary");
+        assert_eq!(parsed.priority, Some(Priority::High));
+        assert_eq!(parsed.due.as_deref(), Some("2024-04-02"));
+        assert_eq!(parsed.tags, ["home", "too", "work", "errands"]);
+        // no STATUS, the completion time says it's done
+        assert!(parsed.done);
+    }
+
+    #[test]
+    fn maps_every_priority() {
+        let priorities: Vec<_> = (0..=9).map(priority_of).collect();
+        assert_eq!(priorities[0], None);
+        assert_eq!(priorities[1..5], [Some(Priority::High); 4]);
+        assert_eq!(priorities[5], Some(Priority::Medium));
+        assert_eq!(priorities[6..], [Some(Priority::Low); 4]);
+        for priority in Priority::ALL {
+            assert_eq!(priority_of(priority_number(priority)), Some(priority));
+        }
+    }
+
+    #[test]
+    fn rejects_resources_without_one_vtodo() {
+        assert_eq!(
+            parse_vtodo("BEGIN:VCALENDAR\r\nEND:VCALENDAR\r\n"),
+            Err("no VTODO in the resource".into())
+        );
+        let twice = "BEGIN:VTODO\nEND:VTODO\nBEGIN:VTODO\nEND:VTODO\n";
+        assert_eq!(
+            parse_vtodo(twice),
+            Err("only one VTODO per resource".into())
+        );
+    }
+}
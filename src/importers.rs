@@ -0,0 +1,713 @@
+//! Export files of other todo apps, for moving off them.
+//!
+//! Lists become lists, labels and categories tags, and due dates, priorities and
+//! subtasks those of the todos. Anything else, like descriptions, reminders and
+//! assignees, is listed as unmapped.
+
+use crate::app::{list_name, parse_tags, Priority};
+use crate::{csv, dates};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// The apps todos are imported from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Source {
+    /// A project exported as CSV, only has open tasks.
+    Todoist,
+    /// Lists as the Microsoft Graph API returns them, as the app has no export of
+    /// its own.
+    MicrosoftToDo,
+    /// `Tasks.json` from Google Takeout.
+    GoogleTasks,
+}
+
+impl Source {
+    pub const ALL: [Source; 3] = [Source::Todoist, Source::MicrosoftToDo, Source::GoogleTasks];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Source::Todoist => "todoist",
+            Source::MicrosoftToDo => "microsoft_to_do",
+            Source::GoogleTasks => "google_tasks",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Source::Todoist => "Todoist CSV",
+            Source::MicrosoftToDo => "Microsoft To Do JSON",
+            Source::GoogleTasks => "Google Tasks JSON",
+        }
+    }
+
+    /// Reads the todos of an export file, and what of it has nowhere to go.
+    pub fn convert(self, file: &str) -> Result<Converted, String> {
+        let mut converted = Converted::default();
+        match self {
+            Source::Todoist => todoist(file, &mut converted)?,
+            Source::MicrosoftToDo => microsoft_to_do(file, &mut converted)?,
+            Source::GoogleTasks => google_tasks(file, &mut converted)?,
+        }
+        Ok(converted)
+    }
+}
+
+/// A todo read from another app, with where it was for messages.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Task {
+    pub place: String,
+    pub task: String,
+    pub done: bool,
+    pub priority: Option<Priority>,
+    /// `YYYY-MM-DD`.
+    pub due: Option<String>,
+    /// Lowercase, see [`parse_tags`].
+    pub tags: Vec<String>,
+    pub list: Option<String>,
+    /// The index of the task this one is a subtask of, always before it.
+    pub parent: Option<usize>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Converted {
+    pub tasks: Vec<Task>,
+    /// What couldn't be mapped, a message each.
+    pub unmapped: Vec<String>,
+}
+
+impl Converted {
+    /// Adds a task, skipping ones without a title, which other apps allow. Returns
+    /// the index of the task, for its subtasks.
+    fn push(&mut self, place: String, title: &str, done: bool, details: Details) -> Option<usize> {
+        let title = title.trim();
+        if title.is_empty() {
+            self.unmapped
+                .push(format!("{place}: skipped a task without a title"));
+            return None;
+        }
+        self.tasks.push(Task {
+            place,
+            task: title.to_string(),
+            done,
+            priority: details.priority,
+            due: details.due,
+            tags: details.tags,
+            list: details.list,
+            parent: details.parent,
+        });
+        Some(self.tasks.len() - 1)
+    }
+
+    fn unmapped(&mut self, place: &str, title: &str, what: &str) {
+        self.unmapped
+            .push(format!("{place}: {what} of `{}`", title.trim()));
+    }
+}
+
+/// The fields of a task besides its title.
+#[derive(Default)]
+struct Details {
+    priority: Option<Priority>,
+    due: Option<String>,
+    tags: Vec<String>,
+    list: Option<String>,
+    parent: Option<usize>,
+}
+
+/// Labels as tags, with spaces as `_` as tags are a single word.
+fn tags<'a>(labels: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let labels: Vec<_> = labels
+        .into_iter()
+        .map(|label| label.split_whitespace().collect::<Vec<_>>().join("_"))
+        .collect();
+    parse_tags(&labels.join(" "))
+}
+
+/// The `YYYY-MM-DD` date a date or date-time like `2024-03-01T00:00:00Z` starts with.
+fn date(value: &str) -> Option<String> {
+    let date = value.trim().get(..10)?;
+    let rest = &value.trim()[10..];
+    (dates::is_date(date) && (rest.is_empty() || rest.starts_with(['T', ' '])))
+        .then(|| date.to_string())
+}
+
+/// A Todoist CSV export, with `TYPE` and `CONTENT` columns and optional
+/// `PRIORITY`, `INDENT`, `DATE` and others. An export is a single project, which
+/// goes in the list picked for the import. Sections and the `@labels` in the
+/// content become tags, priority 1 is the highest and 4 none.
+fn todoist(file: &str, converted: &mut Converted) -> Result<(), String> {
+    let mut records = csv::parse(file).map_err(|e| e.to_string())?.into_iter();
+    let header = records.next().ok_or("the file is empty")?;
+    let column = |name: &str| {
+        header
+            .fields
+            .iter()
+            .position(|field| field.trim().eq_ignore_ascii_case(name))
+    };
+    let (Some(type_column), Some(content_column)) = (column("TYPE"), column("CONTENT")) else {
+        return Err("not a Todoist export, there are no `TYPE` and `CONTENT` columns".into());
+    };
+    let dropped = [
+        ("DESCRIPTION", "description"),
+        ("RESPONSIBLE", "assignee"),
+        ("DURATION", "duration"),
+        ("DEADLINE", "deadline"),
+    ]
+    .map(|(name, what)| (column(name), what));
+
+    let mut section = None;
+    let mut last_task = String::new();
+    // the last task of each indent, the ones the next may be a subtask of
+    let mut indents: Vec<Option<usize>> = vec![];
+    for record in records {
+        let field = |column: Option<usize>| {
+            column
+                .and_then(|column| record.fields.get(column))
+                .map_or("", |field| field.trim())
+        };
+        let place = format!("line {}", record.line);
+        let content = field(Some(content_column));
+
+        match field(Some(type_column)) {
+            "task" => {}
+            "section" => {
+                section = Some(content.to_string());
+                indents.clear();
+                continue;
+            }
+            "note" => {
+                converted.unmapped(&place, &last_task, "comment");
+                continue;
+            }
+            // blank rows between sections
+            "" if content.is_empty() => continue,
+            other => {
+                converted
+                    .unmapped
+                    .push(format!("{place}: skipped a row of type `{other}`"));
+                continue;
+            }
+        }
+
+        let priority = match field(column("PRIORITY")) {
+            "1" => Some(Priority::High),
+            "2" => Some(Priority::Medium),
+            "3" => Some(Priority::Low),
+            "4" | "" => None,
+            other => {
+                converted.unmapped(&place, content, &format!("priority `{other}`"));
+                None
+            }
+        };
+        let due = match field(column("DATE")) {
+            "" => None,
+            value => {
+                let due = date(value);
+                if due.is_none() {
+                    converted.unmapped(&place, content, &format!("due date `{value}`"));
+                }
+                due
+            }
+        };
+        let indent = field(column("INDENT"))
+            .parse::<usize>()
+            .map_or(1, |indent| indent.max(1));
+        let parent = indents.get(indent.saturating_sub(2)).copied().flatten();
+        if indent > 1 && parent.is_none() {
+            converted.unmapped(&place, content, "parent task");
+        }
+        for (column, what) in dropped {
+            if !field(column).is_empty() {
+                converted.unmapped(&place, content, what);
+            }
+        }
+
+        let (labels, words): (Vec<_>, Vec<_>) = content
+            .split_whitespace()
+            .partition(|word| word.len() > 1 && word.starts_with('@'));
+        let labels = labels.iter().map(|label| &label[1..]);
+        let details = Details {
+            priority,
+            due,
+            tags: tags(section.as_deref().into_iter().chain(labels)),
+            parent,
+            ..Details::default()
+        };
+        let index = converted.push(place, &words.join(" "), false, details);
+        indents.resize(indent - 1, None);
+        indents.push(index);
+        last_task = content.to_string();
+    }
+    Ok(())
+}
+
+/// Microsoft To Do lists as the Graph API has them, `[{"displayName": .., "tasks":
+/// [..]}, ..]`, or the tasks of one list as `{"value": [..]}`, as tools exporting
+/// them write them.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ToDoFile {
+    Lists(Vec<ToDoList>),
+    Tasks { value: Vec<ToDoTask> },
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ToDoList {
+    display_name: String,
+    #[serde(default)]
+    tasks: Vec<ToDoTask>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ToDoTask {
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    status: String,
+    #[serde(default)]
+    importance: String,
+    due_date_time: Option<ToDoDateTime>,
+    #[serde(default)]
+    categories: Vec<String>,
+    body: Option<ToDoBody>,
+    #[serde(default)]
+    checklist_items: Vec<ToDoChecklistItem>,
+    recurrence: Option<serde_json::Value>,
+    #[serde(default)]
+    is_reminder_on: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ToDoDateTime {
+    date_time: String,
+}
+
+#[derive(Deserialize)]
+struct ToDoBody {
+    #[serde(default)]
+    content: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ToDoChecklistItem {
+    display_name: String,
+    #[serde(default)]
+    is_checked: bool,
+}
+
+/// Microsoft To Do, lists go in lists of the same name and categories become tags,
+/// high importance is high priority and low low. Steps are subtasks.
+fn microsoft_to_do(file: &str, converted: &mut Converted) -> Result<(), String> {
+    let lists = match serde_json::from_str::<ToDoFile>(file) {
+        Ok(ToDoFile::Lists(lists)) => lists,
+        Ok(ToDoFile::Tasks { value }) => vec![ToDoList {
+            display_name: String::new(),
+            tasks: value,
+        }],
+        Err(e) => return Err(format!("not a Microsoft To Do export: {e}")),
+    };
+
+    for list in lists {
+        let name = list_name(&list.display_name);
+        for (i, task) in list.tasks.iter().enumerate() {
+            let place = match &name {
+                Some(name) => format!("task {} of `{name}`", i + 1),
+                None => format!("task {}", i + 1),
+            };
+
+            let priority = match task.importance.as_str() {
+                "high" => Some(Priority::High),
+                "low" => Some(Priority::Low),
+                _ => None,
+            };
+            let due = task.due_date_time.as_ref().and_then(|due| {
+                let date = date(&due.date_time);
+                if date.is_none() {
+                    converted.unmapped(
+                        &place,
+                        &task.title,
+                        &format!("due date `{}`", due.date_time),
+                    );
+                }
+                date
+            });
+            if task
+                .body
+                .as_ref()
+                .is_some_and(|body| !body.content.trim().is_empty())
+            {
+                converted.unmapped(&place, &task.title, "note");
+            }
+            if task
+                .recurrence
+                .as_ref()
+                .is_some_and(|recurrence| !recurrence.is_null())
+            {
+                converted.unmapped(&place, &task.title, "repeat");
+            }
+            if task.is_reminder_on {
+                converted.unmapped(&place, &task.title, "reminder");
+            }
+
+            let details = Details {
+                priority,
+                due,
+                tags: tags(task.categories.iter().map(String::as_str)),
+                list: name.clone(),
+                parent: None,
+            };
+            let done = task.status == "completed";
+            let Some(parent) = converted.push(place.clone(), &task.title, done, details) else {
+                continue;
+            };
+
+            for (j, item) in task.checklist_items.iter().enumerate() {
+                let details = Details {
+                    list: name.clone(),
+                    parent: Some(parent),
+                    ..Details::default()
+                };
+                converted.push(
+                    format!("{place}, step {}", j + 1),
+                    &item.display_name,
+                    item.is_checked,
+                    details,
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Google Takeout's `Tasks.json`, `{"items": [{"title": .., "items": [..]}, ..]}`.
+#[derive(Deserialize)]
+struct GoogleFile {
+    items: Vec<GoogleList>,
+}
+
+#[derive(Deserialize)]
+struct GoogleList {
+    title: String,
+    #[serde(default)]
+    items: Vec<GoogleTask>,
+}
+
+#[derive(Deserialize)]
+struct GoogleTask {
+    id: Option<String>,
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    status: String,
+    due: Option<String>,
+    notes: Option<String>,
+    parent: Option<String>,
+    #[serde(default)]
+    deleted: bool,
+    #[serde(default)]
+    links: Vec<serde_json::Value>,
+}
+
+/// Google Tasks, lists go in lists of the same name. Deleted tasks are skipped, as
+/// Takeout includes them.
+fn google_tasks(file: &str, converted: &mut Converted) -> Result<(), String> {
+    let lists = serde_json::from_str::<GoogleFile>(file)
+        .map_err(|e| format!("not a Google Tasks export: {e}"))?
+        .items;
+
+    for list in lists {
+        // subtasks are a single level deep, adding them after the rest puts their
+        // task before them
+        let mut tasks: Vec<_> = list.items.iter().enumerate().collect();
+        tasks.sort_by_key(|(_, task)| task.parent.is_some());
+        let mut indices = HashMap::new();
+
+        for (i, task) in tasks {
+            let place = format!("task {} of `{}`", i + 1, list.title);
+            if task.deleted {
+                continue;
+            }
+
+            let due = task.due.as_ref().and_then(|due| {
+                let date = date(due);
+                if date.is_none() {
+                    converted.unmapped(&place, &task.title, &format!("due date `{due}`"));
+                }
+                date
+            });
+            if task
+                .notes
+                .as_ref()
+                .is_some_and(|notes| !notes.trim().is_empty())
+            {
+                converted.unmapped(&place, &task.title, "notes");
+            }
+            if !task.links.is_empty() {
+                converted.unmapped(&place, &task.title, "links");
+            }
+            let parent = task
+                .parent
+                .as_ref()
+                .and_then(|parent| indices.get(parent).copied());
+            if task.parent.is_some() && parent.is_none() {
+                converted.unmapped(&place, &task.title, "parent task");
+            }
+
+            let details = Details {
+                due,
+                list: list_name(&list.title),
+                parent,
+                ..Details::default()
+            };
+            let done = task.status == "completed";
+            let index = converted.push(place, &task.title, done, details);
+            if let (Some(id), Some(index)) = (&task.id, index) {
+                indices.insert(id.clone(), index);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(task: &str) -> Task {
+        Task {
+            task: task.into(),
+            ..Task::default()
+        }
+    }
+
+    /// The tasks without their places, which the unmapped messages show.
+    fn convert(source: Source, file: &str) -> (Vec<Task>, Vec<String>) {
+        let converted = source.convert(file).unwrap();
+        let tasks = converted
+            .tasks
+            .into_iter()
+            .map(|task| Task {
+                place: String::new(),
+                ..task
+            })
+            .collect();
+        (tasks, converted.unmapped)
+    }
+
+    #[test]
+    fn reads_dates_and_date_times() {
+        assert_eq!(date("2024-03-01"), Some("2024-03-01".into()));
+        assert_eq!(date(" 2024-03-01T00:00:00.000Z"), Some("2024-03-01".into()));
+        assert_eq!(date("2024-03-01 09:00"), Some("2024-03-01".into()));
+        assert_eq!(date("2024-02-30"), None);
+        assert_eq!(date("2024-03-012"), None);
+        assert_eq!(date("every monday"), None);
+        assert_eq!(date(""), None);
+    }
+
+    #[test]
+    fn turns_labels_into_tags() {
+        assert_eq!(
+            tags(["Blue category", "home", "Home"]),
+            ["blue_category", "home"]
+        );
+        assert!(tags([" "]).is_empty());
+    }
+
+    #[test]
+    fn skips_tasks_without_a_title() {
+        let mut converted = Converted::default();
+        let details = Details {
+            priority: Some(Priority::High),
+            ..Details::default()
+        };
+        assert_eq!(converted.push("line 2".into(), "  ", false, details), None);
+        assert_eq!(
+            converted.push("line 3".into(), " milk ", true, Details::default()),
+            Some(0)
+        );
+        assert_eq!(
+            converted.tasks,
+            [Task {
+                place: "line 3".into(),
+                done: true,
+                ..task("milk")
+            }]
+        );
+        assert_eq!(
+            converted.unmapped,
+            ["line 2: skipped a task without a title"]
+        );
+    }
+
+    #[test]
+    fn reads_todoist_exports() {
+        let file = "TYPE,CONTENT,DESCRIPTION,PRIORITY,INDENT,DATE\r\n\
+            task,Buy milk @errands,,1,1,2024-03-01\r\n\
+            note,oat,,,,\r\n\
+            ,,,,,\r\n\
+            section,Home Stuff,,,,\r\n\
+            task,Fix sink,call a plumber,4,1,every monday\r\n\
+            task,Buy washer,,2,2,\r\n\
+            task,Measure,,3,3,\r\n\
+            task,Sell old one,,,2,\r\n\
+            section,Later,,,,\r\n\
+            task,Orphan,,,2,\r\n";
+        let (tasks, unmapped) = convert(Source::Todoist, file);
+        assert_eq!(
+            tasks,
+            [
+                Task {
+                    priority: Some(Priority::High),
+                    due: Some("2024-03-01".into()),
+                    tags: vec!["errands".into()],
+                    ..task("Buy milk")
+                },
+                Task {
+                    tags: vec!["home_stuff".into()],
+                    ..task("Fix sink")
+                },
+                Task {
+                    priority: Some(Priority::Medium),
+                    tags: vec!["home_stuff".into()],
+                    parent: Some(1),
+                    ..task("Buy washer")
+                },
+                Task {
+                    priority: Some(Priority::Low),
+                    tags: vec!["home_stuff".into()],
+                    parent: Some(2),
+                    ..task("Measure")
+                },
+                Task {
+                    tags: vec!["home_stuff".into()],
+                    parent: Some(1),
+                    ..task("Sell old one")
+                },
+                Task {
+                    tags: vec!["later".into()],
+                    ..task("Orphan")
+                },
+            ]
+        );
+        assert_eq!(
+            unmapped,
+            [
+                "line 3: comment of `Buy milk @errands`",
+                "line 6: due date `every monday` of `Fix sink`",
+                "line 6: description of `Fix sink`",
+                "line 11: parent task of `Orphan`",
+            ]
+        );
+
+        let wrong = Source::Todoist.convert("task,title\n1,2").unwrap_err();
+        assert!(wrong.starts_with("not a Todoist export"));
+    }
+
+    #[test]
+    fn reads_microsoft_to_do_lists() {
+        let file = r#"[{
+            "displayName": " Work Items ",
+            "tasks": [
+                {
+                    "title": "Plan",
+                    "status": "completed",
+                    "importance": "high",
+                    "dueDateTime": {"dateTime": "2024-03-05T00:00:00.0000000", "timeZone": "UTC"},
+                    "categories": ["Blue category"],
+                    "body": {"content": "", "contentType": "text"},
+                    "checklistItems": [{"displayName": "step one", "isChecked": true}],
+                    "isReminderOn": true
+                },
+                {"title": "Low", "importance": "low", "recurrence": {"pattern": {}}}
+            ]
+        }]"#;
+        let (tasks, unmapped) = convert(Source::MicrosoftToDo, file);
+        let work = Some("Work Items".to_string());
+        assert_eq!(
+            tasks,
+            [
+                Task {
+                    done: true,
+                    priority: Some(Priority::High),
+                    due: Some("2024-03-05".into()),
+                    tags: vec!["blue_category".into()],
+                    list: work.clone(),
+                    ..task("Plan")
+                },
+                Task {
+                    done: true,
+                    list: work.clone(),
+                    parent: Some(0),
+                    ..task("step one")
+                },
+                Task {
+                    priority: Some(Priority::Low),
+                    list: work,
+                    ..task("Low")
+                },
+            ]
+        );
+        assert_eq!(
+            unmapped,
+            [
+                "task 1 of `Work Items`: reminder of `Plan`",
+                "task 2 of `Work Items`: repeat of `Low`",
+            ]
+        );
+
+        // the tasks of a single list
+        let (tasks, _) = convert(Source::MicrosoftToDo, r#"{"value": [{"title": "a"}]}"#);
+        assert_eq!(tasks, [task("a")]);
+    }
+
+    #[test]
+    fn reads_google_takeout_tasks() {
+        let file = r#"{"kind": "tasks#taskLists", "items": [{
+            "title": "My Tasks",
+            "items": [
+                {"id": "c", "title": "child", "status": "completed", "parent": "p"},
+                {"id": "p", "title": "Call mom", "status": "needsAction",
+                 "due": "2024-03-01T00:00:00.000Z", "notes": "about the trip"},
+                {"title": "gone", "status": "completed", "deleted": true},
+                {"title": "lost", "status": "needsAction", "parent": "deleted"},
+                {"title": "", "status": "needsAction"}
+            ]
+        }]}"#;
+        let (tasks, unmapped) = convert(Source::GoogleTasks, file);
+        let mine = Some("My Tasks".to_string());
+        assert_eq!(
+            tasks,
+            [
+                Task {
+                    due: Some("2024-03-01".into()),
+                    list: mine.clone(),
+                    ..task("Call mom")
+                },
+                Task {
+                    done: true,
+                    list: mine.clone(),
+                    parent: Some(0),
+                    ..task("child")
+                },
+                Task {
+                    list: mine,
+                    ..task("lost")
+                },
+            ]
+        );
+        assert_eq!(
+            unmapped,
+            [
+                "task 2 of `My Tasks`: notes of `Call mom`",
+                "task 5 of `My Tasks`: skipped a task without a title",
+                "task 4 of `My Tasks`: parent task of `lost`",
+            ]
+        );
+    }
+}
@@ -1,7 +1,22 @@
 use cfg_if::cfg_if;
+pub mod admin;
 pub mod app;
+pub mod auth;
+pub mod backup;
+pub mod caldav;
+pub mod csv;
+pub mod dates;
 pub mod error_template;
 pub mod fileserv;
+pub mod ical;
+pub mod importers;
+pub mod markdown;
+pub mod offline;
+pub mod query;
+pub mod rest;
+pub mod saved_search;
+pub mod shortcuts;
+pub mod todo_txt;
 
 cfg_if! { if #[cfg(feature = "hydrate")] {
     use leptos::*;
@@ -1,38 +1,350 @@
+//! The server, and commands to manage its database, like `leptos-todo check`.
+//! Without a command it serves, as `cargo leptos` runs it.
+//!
+//! Commands exit with 0 when they succeed, 1 when they fail, 2 for wrong arguments,
+//! 3 when `check` finds problems, and 4 for a file or filter `import` or `export`
+//! can't use, or a user name `create-user` or `reset-password` can't.
+
+use cfg_if::cfg_if;
+
+cfg_if! { if #[cfg(feature = "ssr")] {
+    use clap::{Parser, Subcommand};
+    use leptos::ServerFnError;
+    use leptos_todo::admin;
+    use leptos_todo::app::TODOS_DB;
+    use leptos_todo::auth;
+    use leptos_todo::backup::{export_file, import_todos, FileType, ImportMode};
+    use leptos_todo::importers::Source;
+    use std::fmt::Display;
+    use std::io::Write;
+    use std::path::PathBuf;
+    use std::process::ExitCode;
+
+    const EXIT_FAILED: u8 = 1;
+    const EXIT_PROBLEMS: u8 = 3;
+    const EXIT_INVALID: u8 = 4;
+
+    #[derive(Parser)]
+    #[command(about = "The todo app's server, and commands to manage its database")]
+    struct Cli {
+        /// SQLite file todos are kept in, `Todos.db` without one.
+        #[arg(long, global = true, env = TODOS_DB)]
+        db: Option<String>,
+        #[command(subcommand)]
+        command: Option<Command>,
+    }
+
+    #[derive(Subcommand)]
+    enum Command {
+        /// Serve the app, the default.
+        Serve,
+        /// Create the database if it's missing and apply the migrations it's missing.
+        Migrate,
+        /// Write todos to a file, or to stdout.
+        Export {
+            /// File to write.
+            #[arg(short, long)]
+            output: Option<PathBuf>,
+            /// json, csv, txt or md, by default the extension of the output, or json.
+            #[arg(long = "type", value_parser = file_type)]
+            file_type: Option<FileType>,
+            /// Only the todos matching a filter in the search syntax, like `+work`.
+            #[arg(short, long, default_value = "")]
+            query: String,
+        },
+        /// Add the todos of a file, all or none of them.
+        Import {
+            file: PathBuf,
+            /// json, csv, txt or md, by default the extension of the file.
+            #[arg(long = "type", value_parser = file_type)]
+            file_type: Option<FileType>,
+            /// The app the file is an export of: todoist, microsoft_to_do or google_tasks.
+            #[arg(long, value_parser = source, conflicts_with = "file_type")]
+            from: Option<Source>,
+            /// The list todos go into when the file doesn't put them in one.
+            #[arg(long)]
+            list: Option<String>,
+            /// merge, skipping todos already there, or replace, deleting them first.
+            #[arg(long, value_parser = import_mode, default_value = "merge")]
+            mode: ImportMode,
+            /// Only tell what the import would do.
+            #[arg(long)]
+            dry_run: bool,
+        },
+        /// Rebuild the database file without its free space.
+        Vacuum,
+        /// Look for corruption in the database.
+        Check,
+        /// Add a user, printing the token they sign in with. It's only shown once.
+        CreateUser { name: String },
+        /// Give a user a new token, printing it, and stop their old one working.
+        ResetPassword { name: String },
+    }
+
+    fn file_type(name: &str) -> Result<FileType, String> {
+        FileType::ALL
+            .into_iter()
+            .find(|file_type| file_type.extension() == name)
+            .ok_or_else(|| "expected json, csv, txt or md".into())
+    }
+
+    fn source(name: &str) -> Result<Source, String> {
+        Source::ALL
+            .into_iter()
+            .find(|source| source.as_str() == name)
+            .ok_or_else(|| "expected todoist, microsoft_to_do or google_tasks".into())
+    }
+
+    fn import_mode(name: &str) -> Result<ImportMode, String> {
+        ImportMode::ALL
+            .into_iter()
+            .find(|mode| mode.as_str() == name)
+            .ok_or_else(|| "expected merge or replace".into())
+    }
+
+    /// Why a command failed, and the code it exits with.
+    struct Failure {
+        code: u8,
+        message: String,
+    }
+
+    impl Failure {
+        fn failed(e: impl Display) -> Self {
+            Failure {
+                code: EXIT_FAILED,
+                message: e.to_string(),
+            }
+        }
+    }
+
+    impl From<ServerFnError> for Failure {
+        fn from(e: ServerFnError) -> Self {
+            match e {
+                ServerFnError::Args(message) => Failure {
+                    code: EXIT_INVALID,
+                    message,
+                },
+                ServerFnError::ServerError(message) => Failure::failed(message),
+                e => Failure::failed(e),
+            }
+        }
+    }
+
+    impl From<std::io::Error> for Failure {
+        fn from(e: std::io::Error) -> Self {
+            Failure::failed(e)
+        }
+    }
+
+    async fn serve() -> Result<(), Failure> {
+        use axum::{
+            middleware,
+            routing::{get, post},
+            Router,
+        };
+        use leptos::*;
+        use leptos_axum::{generate_route_list, LeptosRoutes};
+        use leptos_todo::app::*;
+        use leptos_todo::auth::sign_in_routes;
+        use leptos_todo::backup::file_routes;
+        use leptos_todo::caldav::caldav_routes;
+        use leptos_todo::fileserv::{file_and_error_handler, service_worker};
+        use leptos_todo::ical;
+        use leptos_todo::rest::rest_routes;
+
+        simple_logger::init_with_level(log::Level::Debug)
+            .map_err(|e| Failure::failed(format!("couldn't initialize logging: {e}")))?;
+        auth::warn_if_open().await;
+
+        // Setting get_configuration(None) means we'll be using cargo-leptos's env values
+        // For deployment these variables are:
+        // <https://github.com/leptos-rs/start-axum#executing-a-server-on-a-remote-machine-without-the-toolchain>
+        // Alternately a file can be specified such as Some("Cargo.toml")
+        // The file would need to be included with the executable when moved to deployment
+        let conf = get_configuration(None)
+            .await
+            .map_err(|e| Failure::failed(format!("couldn't read the configuration: {e}")))?;
+        let leptos_options = conf.leptos_options;
+        let addr = leptos_options.site_addr;
+        let routes = generate_route_list(App);
+
+        // build our application with a route
+        // server functions, imports, exports and pages need a signed in user, the
+        // routes merged after them check tokens their own way or are public
+        let app = Router::new()
+            .merge(file_routes())
+            .route("/api/*fn_name", post(leptos_axum::handle_server_fns))
+            .leptos_routes(&leptos_options, routes, App)
+            .route_layer(middleware::from_fn(auth::require_user))
+            .merge(caldav_routes())
+            .merge(rest_routes())
+            .merge(sign_in_routes())
+            .route("/api/todos.ics", get(ical::feed))
+            .route("/api/lists/:file", get(ical::list_feed))
+            .route("/sw.js", get(service_worker))
+            .fallback(file_and_error_handler)
+            .with_state(leptos_options);
+
+        // run our app with hyper
+        // `axum::Server` is a re-export of `hyper::Server`
+        log::info!("listening on http://{}", &addr);
+        axum::Server::try_bind(&addr)
+            .map_err(|e| Failure::failed(format!("couldn't listen on {addr}: {e}")))?
+            .serve(app.into_make_service())
+            .await
+            .map_err(Failure::failed)
+    }
+
+    async fn export(
+        output: Option<PathBuf>,
+        file_type: Option<FileType>,
+        filter: &str,
+    ) -> Result<(), Failure> {
+        use axum::body::HttpBody;
+
+        admin::require_database().await?;
+        let file_type = file_type
+            .or_else(|| {
+                let name = output.as_ref()?.to_string_lossy();
+                FileType::from_name(&name)
+            })
+            .unwrap_or_default();
+
+        let response = export_file(file_type, filter).await;
+        let status = response.status();
+        let mut body = response.into_body();
+
+        if !status.is_success() {
+            let mut message = vec![];
+            while let Some(chunk) = body.data().await {
+                message.extend_from_slice(&chunk.map_err(Failure::failed)?);
+            }
+            return Err(Failure {
+                code: if status.is_client_error() { EXIT_INVALID } else { EXIT_FAILED },
+                message: String::from_utf8_lossy(&message).into(),
+            });
+        }
+
+        let mut out: Box<dyn Write> = match &output {
+            Some(path) => Box::new(std::fs::File::create(path)?),
+            None => Box::new(std::io::stdout().lock()),
+        };
+        // an error partway is a failure, not a file missing todos
+        while let Some(chunk) = body.data().await {
+            out.write_all(&chunk.map_err(Failure::failed)?)?;
+        }
+        out.flush()?;
+        Ok(())
+    }
+
+    async fn import(
+        file: PathBuf,
+        file_type: Option<FileType>,
+        from: Option<Source>,
+        list: Option<String>,
+        mode: ImportMode,
+        dry_run: bool,
+    ) -> Result<(), Failure> {
+        let contents = std::fs::read_to_string(&file).map_err(|e| Failure {
+            code: EXIT_INVALID,
+            message: format!("can't read {}: {e}", file.display()),
+        })?;
+        let file_type = match (file_type, from) {
+            (Some(file_type), _) => file_type,
+            // exports of other apps are told apart by `from`
+            (None, Some(_)) => FileType::default(),
+            (None, None) => {
+                FileType::from_name(&file.to_string_lossy()).ok_or_else(|| Failure {
+                    code: EXIT_INVALID,
+                    message: format!("can't tell the type of {}, pass --type", file.display()),
+                })?
+            }
+        };
+
+        let summary = import_todos(contents, file_type, from, list, mode, dry_run).await?;
+        println!("{}", summary.describe());
+        for unmapped in summary.unmapped() {
+            println!("not imported: {unmapped}");
+        }
+        Ok(())
+    }
+
+    /// Prints a new token alone on stdout, for scripts to read, and what it's for on
+    /// stderr.
+    fn print_token(token: Result<String, ServerFnError>) -> Result<(), Failure> {
+        println!("{}", token?);
+        eprintln!("sign in with this token, it won't be shown again");
+        Ok(())
+    }
+
+    async fn check() -> Result<(), Failure> {
+        let problems = admin::check().await?;
+        for problem in &problems {
+            println!("{problem}");
+        }
+
+        match problems.len() {
+            0 => {
+                println!("no problems found");
+                Ok(())
+            }
+            count => Err(Failure {
+                code: EXIT_PROBLEMS,
+                message: format!("{count} problems found"),
+            }),
+        }
+    }
+}}
+
 #[cfg(feature = "ssr")]
 #[tokio::main]
-async fn main() {
-    use axum::{routing::post, Router};
-    use leptos::*;
-    use leptos_axum::{generate_route_list, LeptosRoutes};
-    use leptos_todo::app::*;
-    use leptos_todo::fileserv::file_and_error_handler;
-
-    simple_logger::init_with_level(log::Level::Debug).expect("couldn't initialize logging");
-
-    // Setting get_configuration(None) means we'll be using cargo-leptos's env values
-    // For deployment these variables are:
-    // <https://github.com/leptos-rs/start-axum#executing-a-server-on-a-remote-machine-without-the-toolchain>
-    // Alternately a file can be specified such as Some("Cargo.toml")
-    // The file would need to be included with the executable when moved to deployment
-    let conf = get_configuration(None).await.unwrap();
-    let leptos_options = conf.leptos_options;
-    let addr = leptos_options.site_addr;
-    let routes = generate_route_list(App);
-
-    // build our application with a route
-    let app = Router::new()
-        .route("/api/*fn_name", post(leptos_axum::handle_server_fns))
-        .leptos_routes(&leptos_options, routes, App)
-        .fallback(file_and_error_handler)
-        .with_state(leptos_options);
-
-    // run our app with hyper
-    // `axum::Server` is a re-export of `hyper::Server`
-    log::info!("listening on http://{}", &addr);
-    axum::Server::bind(&addr)
-        .serve(app.into_make_service())
-        .await
-        .unwrap();
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    if let Some(db) = &cli.db {
+        std::env::set_var(TODOS_DB, db);
+    }
+
+    let result = match cli.command.unwrap_or(Command::Serve) {
+        Command::Serve => serve().await,
+        Command::Migrate => match admin::migrate().await {
+            Ok(latest) => {
+                println!("migrated to {latest}");
+                Ok(())
+            }
+            Err(e) => Err(e.into()),
+        },
+        Command::Export {
+            output,
+            file_type,
+            query,
+        } => export(output, file_type, &query).await,
+        Command::Import {
+            file,
+            file_type,
+            from,
+            list,
+            mode,
+            dry_run,
+        } => import(file, file_type, from, list, mode, dry_run).await,
+        Command::Vacuum => match admin::vacuum().await {
+            Ok((before, after)) => {
+                println!("{before} bytes before, {after} after");
+                Ok(())
+            }
+            Err(e) => Err(e.into()),
+        },
+        Command::Check => check().await,
+        Command::CreateUser { name } => print_token(auth::create_user(&name).await),
+        Command::ResetPassword { name } => print_token(auth::reset_token(&name).await),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(failure) => {
+            eprintln!("error: {}", failure.message);
+            ExitCode::from(failure.code)
+        }
+    }
 }
 
 #[cfg(not(feature = "ssr"))]
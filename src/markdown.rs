@@ -0,0 +1,255 @@
+//! Markdown checklists, `- [ ] item` and `- [x] item`, as GitHub issues and docs
+//! have them.
+//!
+//! Items of nested lists are subtasks of the item they're under. Lines that aren't
+//! checklist items, like headings, prose, plain list items and whatever is in fenced
+//! code blocks, are skipped.
+
+use crate::app::TodoItem;
+
+/// Columns a tab stands for when telling how deep an item is.
+const TAB_WIDTH: usize = 4;
+
+/// A checklist item, with the line it's on for error messages.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Item {
+    pub line: usize,
+    pub task: String,
+    pub done: bool,
+    /// The index of the item this one is nested under.
+    pub parent: Option<usize>,
+}
+
+/// Reads every checklist item.
+pub fn parse(input: &str) -> Vec<Item> {
+    let input = input.strip_prefix('\u{feff}').unwrap_or(input);
+
+    let mut items = vec![];
+    // the items the next one may be nested under, with how far they're indented
+    let mut open: Vec<(usize, usize)> = vec![];
+    // the character and length of the fence of the code block the line is in
+    let mut fence = None::<(char, usize)>;
+
+    for (i, line) in input.lines().enumerate() {
+        let trimmed = line.trim_start();
+        let fence_char = trimmed.chars().next().filter(|c| matches!(c, '`' | '~'));
+        if let Some(c) = fence_char {
+            let length = trimmed.chars().take_while(|&found| found == c).count();
+            match fence {
+                // closed by a fence of the same character, at least as long
+                Some((open_char, open_length)) if open_char == c && length >= open_length => {
+                    fence = None;
+                    continue;
+                }
+                None if length >= 3 => {
+                    fence = Some((c, length));
+                    continue;
+                }
+                _ => {}
+            }
+        }
+        if fence.is_some() || trimmed.is_empty() {
+            continue;
+        }
+
+        let indent = indent(line);
+        while open
+            .last()
+            .is_some_and(|&(open_indent, _)| open_indent >= indent)
+        {
+            open.pop();
+        }
+        let Some((task, done)) = parse_line(line) else {
+            // anything but an item ends the lists it isn't indented into
+            continue;
+        };
+        items.push(Item {
+            line: i + 1,
+            task,
+            done,
+            parent: open.last().map(|&(_, parent)| parent),
+        });
+        open.push((indent, items.len() - 1));
+    }
+
+    items
+}
+
+/// How far a line is indented, in columns.
+fn indent(line: &str) -> usize {
+    line.chars()
+        .take_while(|c| matches!(c, ' ' | '\t'))
+        .map(|c| if c == '\t' { TAB_WIDTH } else { 1 })
+        .sum()
+}
+
+/// The task and whether it's done, `None` for a line that isn't a checklist item.
+pub fn parse_line(line: &str) -> Option<(String, bool)> {
+    let rest = strip_marker(line.trim_start())?;
+    let (done, task) = match rest.get(..3)? {
+        "[ ]" => (false, &rest[3..]),
+        "[x]" | "[X]" => (true, &rest[3..]),
+        _ => return None,
+    };
+    // `- [ ]` alone is an item without a task, `- [ ]text` isn't an item
+    if !task.is_empty() && !task.starts_with([' ', '\t']) {
+        return None;
+    }
+    Some((task.trim().to_string(), done))
+}
+
+/// A todo as a checklist item, without the line break.
+pub fn format_line(task: &str, done: bool) -> String {
+    let task = task.replace(['\r', '\n'], " ");
+    format!("- [{}] {task}", if done { 'x' } else { ' ' })
+}
+
+/// Todos as a checklist, in order but with subtasks nested under the todo they're
+/// part of when it's there too.
+pub fn checklist(todos: &[TodoItem]) -> String {
+    let has = |id| todos.iter().any(|todo| todo.id == id);
+    let mut checklist = String::new();
+    let mut stack: Vec<(&TodoItem, usize)> = todos
+        .iter()
+        .rev()
+        .filter(|todo| !todo.parent.is_some_and(has))
+        .map(|todo| (todo, 0))
+        .collect();
+    while let Some((todo, depth)) = stack.pop() {
+        checklist.push_str(&"  ".repeat(depth));
+        checklist.push_str(&format_line(&todo.task, todo.done));
+        checklist.push('\n');
+        stack.extend(
+            todos
+                .iter()
+                .rev()
+                .filter(|subtask| subtask.parent == Some(todo.id))
+                .map(|subtask| (subtask, depth + 1)),
+        );
+    }
+    checklist
+}
+
+/// What's after a `-`, `*` or `+` bullet, or a `1.` or `1)` number, and the spaces
+/// after it.
+fn strip_marker(line: &str) -> Option<&str> {
+    let digits = line.bytes().take_while(u8::is_ascii_digit).count();
+    let rest = match digits {
+        0 => line.strip_prefix(['-', '*', '+'])?,
+        // CommonMark allows at most nine digits
+        1..=9 => line[digits..].strip_prefix(['.', ')'])?,
+        _ => return None,
+    };
+    rest.starts_with([' ', '\t'])
+        .then(|| rest.trim_start_matches([' ', '\t']))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_bullets_and_numbers() {
+        assert_eq!(strip_marker("- [ ] a"), Some("[ ] a"));
+        assert_eq!(strip_marker("*\t[x] a"), Some("[x] a"));
+        assert_eq!(strip_marker("+   a"), Some("a"));
+        assert_eq!(strip_marker("12. a"), Some("a"));
+        assert_eq!(strip_marker("3) a"), Some("a"));
+        assert_eq!(strip_marker("-a"), None);
+        assert_eq!(strip_marker("1.a"), None);
+        assert_eq!(strip_marker("1234567890. a"), None);
+        assert_eq!(strip_marker("a. b"), None);
+    }
+
+    #[test]
+    fn reads_checklist_items() {
+        assert_eq!(parse_line("- [ ] milk"), Some(("milk".into(), false)));
+        assert_eq!(parse_line("  * [X]   eggs  "), Some(("eggs".into(), true)));
+        assert_eq!(parse_line("1. [x] bread"), Some(("bread".into(), true)));
+        assert_eq!(parse_line("- [ ]"), Some(("".into(), false)));
+        assert_eq!(parse_line("- [ ]milk"), None);
+        assert_eq!(parse_line("- [-] milk"), None);
+        assert_eq!(parse_line("- milk"), None);
+        assert_eq!(parse_line("[ ] milk"), None);
+    }
+
+    fn items(input: &str) -> Vec<(String, Option<usize>)> {
+        parse(input)
+            .into_iter()
+            .map(|item| (item.task, item.parent))
+            .collect()
+    }
+
+    #[test]
+    fn nests_subtasks_under_their_item() {
+        let input = "## Release\n\
+            - [ ] notes\n  \
+              - [x] draft\n    \
+                * [ ] review\n  \
+              - [ ] publish\n\
+            \tprose in the item\n\
+            - [ ] tag\n\
+            \t- [ ] push\n\
+            Prose ends the list\n  \
+              - [ ] alone\n";
+        assert_eq!(
+            items(input),
+            [
+                ("notes".into(), None),
+                ("draft".into(), Some(0)),
+                ("review".into(), Some(1)),
+                ("publish".into(), Some(0)),
+                ("tag".into(), None),
+                ("push".into(), Some(4)),
+                ("alone".into(), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_fenced_code_blocks() {
+        let input = "- [ ] before\n\
+            ```markdown\n\
+            - [ ] in code\n\
+            ~~~\n\
+            - [ ] still in code\n\
+            ````\n\
+            - [ ] after\n";
+        let tasks: Vec<_> = parse(input).into_iter().map(|item| item.task).collect();
+        assert_eq!(tasks, ["before", "after"]);
+
+        let lines: Vec<_> = parse("~~~~\n- [ ] a\n~~~\n- [ ] b\n~~~~\n- [ ] c\n")
+            .into_iter()
+            .map(|item| item.line)
+            .collect();
+        assert_eq!(lines, [6]);
+    }
+
+    #[test]
+    fn writes_subtasks_under_their_todo() {
+        let todo = |id, task: &str, parent| TodoItem {
+            id,
+            task: task.into(),
+            parent,
+            ..TodoItem::default()
+        };
+        let todos = [
+            todo(1, "notes", None),
+            todo(2, "tag", None),
+            todo(3, "draft", Some(1)),
+            todo(4, "review", Some(3)),
+            todo(5, "orphan", Some(9)),
+        ];
+        assert_eq!(
+            checklist(&todos),
+            "- [ ] notes\n  - [ ] draft\n    - [ ] review\n- [ ] tag\n- [ ] orphan\n"
+        );
+
+        let written = checklist(&todos);
+        let parents: Vec<_> = parse(&written)
+            .into_iter()
+            .map(|item| item.parent)
+            .collect();
+        assert_eq!(parents, [None, Some(0), Some(1), None, None]);
+    }
+}
@@ -0,0 +1,498 @@
+use crate::app::{
+    add_todo, delete_todo, edit_details, edit_todo, get_todos_by_ids, remove_todos, replace_todos,
+    set_done, Priority, TodoItem, MAX_PAGE_SIZE,
+};
+use cfg_if::cfg_if;
+use leptos::*;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::cell::Cell;
+use std::collections::HashSet;
+use std::future::Future;
+
+const TODOS_KEY: &str = "todos";
+const QUEUE_KEY: &str = "todos-queue";
+const LOCAL_ID_KEY: &str = "todos-local-id";
+const LEASE_KEY: &str = "todos-replay-lease";
+
+/// How long a tab may replay for before another takes over, in milliseconds, in
+/// case it was closed halfway. Renewed before each mutation.
+const LEASE_MS: f64 = 30_000.0;
+
+/// A change made to the todo list that has yet to reach the server.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Mutation {
+    /// `id` is a local id, replaced by the server-assigned one once replayed.
+    Add {
+        id: u32,
+        task: String,
+    },
+    SetDone {
+        id: u32,
+        done: bool,
+        version: u32,
+    },
+    Edit {
+        id: u32,
+        task: String,
+        version: u32,
+    },
+    /// `tags` as typed, see [`crate::app::parse_tags`].
+    Details {
+        id: u32,
+        priority: Option<Priority>,
+        due: Option<String>,
+        tags: String,
+        version: u32,
+    },
+    Delete {
+        id: u32,
+        version: u32,
+    },
+}
+
+/// A mutation in the queue, with a key telling it apart from the same mutation
+/// queued again, so tabs sharing the queue remove the one they replayed.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct Queued {
+    key: u64,
+    mutation: Mutation,
+}
+
+/// The tab replaying the queue, until when, so tabs don't send its mutations twice.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+struct Lease {
+    tab: u64,
+    until: f64,
+}
+
+impl Lease {
+    /// Whether `tab` may replay at `now`: it holds the lease, or the lease ran out.
+    fn lets(&self, tab: u64, now: f64) -> bool {
+        self.tab == tab || self.until < now
+    }
+}
+
+impl Mutation {
+    /// Points a mutation of the todo known locally as `id` at `todo` as the server has it.
+    fn rebase(&mut self, id: u32, todo: &TodoItem) {
+        match self {
+            Mutation::Add { id: todo_id, .. } if *todo_id == id => *todo_id = todo.id,
+            Mutation::SetDone {
+                id: todo_id,
+                version,
+                ..
+            }
+            | Mutation::Edit {
+                id: todo_id,
+                version,
+                ..
+            }
+            | Mutation::Details {
+                id: todo_id,
+                version,
+                ..
+            }
+            | Mutation::Delete {
+                id: todo_id,
+                version,
+            } if *todo_id == id => {
+                *todo_id = todo.id;
+                *version = todo.version;
+            }
+            _ => {}
+        }
+    }
+}
+
+cfg_if! {
+    if #[cfg(feature = "hydrate")] {
+        fn storage() -> Option<web_sys::Storage> {
+            window().local_storage().ok().flatten()
+        }
+
+        pub(crate) fn load<T: DeserializeOwned>(key: &str) -> Option<T> {
+            let value = storage()?.get_item(key).ok()??;
+            serde_json::from_str(&value).ok()
+        }
+
+        pub(crate) fn save<T: Serialize>(key: &str, value: &T) {
+            if let (Some(storage), Ok(value)) = (storage(), serde_json::to_string(value)) {
+                if let Err(e) = storage.set_item(key, &value) {
+                    log::warn!("couldn't save {key} to local storage: {e:?}");
+                }
+            }
+        }
+
+        pub fn is_online() -> bool {
+            window().navigator().on_line()
+        }
+
+        fn random_key() -> u64 {
+            // 53 bits, all a double holds
+            (wasm_bindgen_futures::js_sys::Math::random() * 9_007_199_254_740_992.0) as u64
+        }
+
+        fn now() -> f64 {
+            wasm_bindgen_futures::js_sys::Date::now()
+        }
+    } else {
+        pub(crate) fn load<T: DeserializeOwned>(_key: &str) -> Option<T> {
+            None
+        }
+
+        pub(crate) fn save<T: Serialize>(_key: &str, _value: &T) {}
+
+        pub fn is_online() -> bool {
+            true
+        }
+
+        fn random_key() -> u64 {
+            0
+        }
+
+        fn now() -> f64 {
+            0.0
+        }
+    }
+}
+
+/// The todo list as last seen by this browser, including changes not yet replayed.
+pub fn load_todos() -> Vec<TodoItem> {
+    load(TODOS_KEY).unwrap_or_default()
+}
+
+pub fn save_todos(todos: &[TodoItem]) {
+    save(TODOS_KEY, &todos);
+}
+
+fn load_queue() -> Vec<Queued> {
+    if let Some(queue) = load(QUEUE_KEY) {
+        return queue;
+    }
+    // queued before mutations had keys, given theirs once
+    let queue: Vec<_> = load::<Vec<Mutation>>(QUEUE_KEY)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|mutation| Queued {
+            key: random_key(),
+            mutation,
+        })
+        .collect();
+    if !queue.is_empty() {
+        save_queue(&queue);
+    }
+    queue
+}
+
+fn save_queue(queue: &[Queued]) {
+    save(QUEUE_KEY, &queue);
+}
+
+/// Removes the mutation queued as `key`, if another tab hasn't already.
+fn dequeue(key: u64) {
+    let mut queue = load_queue();
+    queue.retain(|queued| queued.key != key);
+    save_queue(&queue);
+}
+
+pub fn has_pending() -> bool {
+    !load_queue().is_empty()
+}
+
+thread_local! {
+    /// The next local id, for when local storage is unavailable or out of date.
+    static LOCAL_ID: Cell<u32> = const { Cell::new(u32::MAX) };
+    /// This tab, as the holder of a [`Lease`].
+    static TAB: u64 = random_key();
+    /// Whether this tab is replaying, which the lease doesn't tell as it's renewed.
+    static REPLAYING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Takes or renews the lease on replaying the queue, unless another tab holds it.
+/// Local storage has no compare-and-swap, so two tabs taking it at the very same
+/// time can both send the same mutation, though removing it by its key stays right.
+fn take_lease() -> bool {
+    let tab = TAB.with(|tab| *tab);
+    let free = load::<Lease>(LEASE_KEY).is_none_or(|lease| lease.lets(tab, now()));
+    if free {
+        save(
+            LEASE_KEY,
+            &Lease {
+                tab,
+                until: now() + LEASE_MS,
+            },
+        );
+    }
+    free
+}
+
+fn release_lease() {
+    let tab = TAB.with(|tab| *tab);
+    if load::<Lease>(LEASE_KEY).is_some_and(|lease| lease.tab == tab) {
+        save(LEASE_KEY, &Lease { tab, until: 0.0 });
+    }
+}
+
+/// Hands out ids for todos created offline, counting down from `u32::MAX` so they
+/// don't collide with the ids SQLite assigns. Kept in local storage so they stay
+/// unique across reloads, and in memory so they do without it.
+pub fn next_local_id() -> u32 {
+    let id = load::<u32>(LOCAL_ID_KEY)
+        .unwrap_or(u32::MAX)
+        .min(LOCAL_ID.with(Cell::get));
+    LOCAL_ID.with(|next| next.set(id - 1));
+    save(LOCAL_ID_KEY, &(id - 1));
+    id
+}
+
+/// Sends `request` to the server, unless offline or earlier mutations are still
+/// queued, in which case the mutation from `queued` is queued and its result
+/// returned in place of the server's.
+pub async fn call<T>(
+    todos: RwSignal<Vec<RwSignal<TodoItem>>>,
+    request: impl Future<Output = Result<T, ServerFnError>>,
+    queued: impl FnOnce() -> (Mutation, T),
+) -> Result<T, ServerFnError> {
+    if is_online() && !has_pending() {
+        match request.await {
+            Err(ServerFnError::Request(e)) => log::warn!("server unreachable: {e}"),
+            result => return result,
+        }
+    }
+
+    let (mutation, result) = queued();
+    let mut queue = load_queue();
+    queue.push(Queued {
+        key: random_key(),
+        mutation,
+    });
+    save_queue(&queue);
+
+    if is_online() {
+        spawn_local(replay(todos));
+    }
+    Ok(result)
+}
+
+/// Sends the queued mutations to the server in order, stopping if it's unreachable.
+/// Changed todos get their server-assigned ids and versions, both in `todos` and in
+/// the mutations still queued. Once the queue is drained, `todos` is reconciled with
+/// the server, see [`reconcile`]. Only one tab replays at a time, see [`take_lease`].
+pub async fn replay(todos: RwSignal<Vec<RwSignal<TodoItem>>>) {
+    if REPLAYING.with(|replaying| replaying.replace(true)) {
+        return;
+    }
+
+    let mut replayed = false;
+    while take_lease() {
+        let Some(Queued { key, mutation }) = load_queue().first().cloned() else {
+            break;
+        };
+        let (id, result) = match &mutation {
+            Mutation::Add { id, task } => (*id, add_todo(task.clone()).await.map(Some)),
+            Mutation::SetDone { id, done, version } => {
+                (*id, set_done(*id, *done, *version).await.map(Some))
+            }
+            Mutation::Edit { id, task, version } => {
+                (*id, edit_todo(*id, task.clone(), *version).await.map(Some))
+            }
+            Mutation::Details {
+                id,
+                priority,
+                due,
+                tags,
+                version,
+            } => (
+                *id,
+                edit_details(*id, *priority, due.clone(), tags.clone(), *version)
+                    .await
+                    .map(Some),
+            ),
+            Mutation::Delete { id, version } => {
+                (*id, delete_todo(*id, *version).await.map(|_| None))
+            }
+        };
+
+        let changed = match result {
+            Err(ServerFnError::Request(e)) => {
+                log::warn!("server unreachable, {mutation:?} stays queued: {e}");
+                break;
+            }
+            Err(e) => {
+                log::error!("dropping {mutation:?}, rejected by server: {e}");
+                None
+            }
+            Ok(changed) => changed,
+        };
+
+        dequeue(key);
+        replayed = true;
+
+        if let Some(todo) = changed {
+            rebase(todos, id, todo);
+        }
+    }
+
+    release_lease();
+
+    if replayed && !has_pending() {
+        reconcile(todos).await;
+    }
+
+    REPLAYING.with(|replaying| replaying.set(false));
+}
+
+/// Brings the loaded todos in line with the server, as while changes were queued the
+/// list was rendered from the local copy instead of the server's answers. Todos
+/// changed elsewhere meanwhile are replaced and deleted ones removed. Only the loaded
+/// ones are fetched, those added elsewhere come with the next page or reload.
+async fn reconcile(todos: RwSignal<Vec<RwSignal<TodoItem>>>) {
+    let ids = todos.with_untracked(|todos| {
+        todos
+            .iter()
+            .map(|todo| todo.with_untracked(|todo| todo.id))
+            .collect::<Vec<_>>()
+    });
+
+    let mut current = vec![];
+    for chunk in ids.chunks(MAX_PAGE_SIZE as usize) {
+        match get_todos_by_ids(chunk.to_vec()).await {
+            Ok(todos) => current.extend(todos),
+            Err(e) => {
+                log::warn!("couldn't reconcile with the server: {e}");
+                return;
+            }
+        }
+    }
+
+    let on_server: HashSet<u32> = current.iter().map(|todo| todo.id).collect();
+    let deleted = ids
+        .into_iter()
+        .filter(|id| !on_server.contains(id))
+        .collect();
+    remove_todos(todos, &deleted);
+    replace_todos(todos, &current);
+}
+
+fn rebase(todos: RwSignal<Vec<RwSignal<TodoItem>>>, id: u32, changed: TodoItem) {
+    let mut queue = load_queue();
+    queue
+        .iter_mut()
+        .for_each(|queued| queued.mutation.rebase(id, &changed));
+    save_queue(&queue);
+
+    // later changes made offline still apply locally, only id and version are the server's
+    todos.with_untracked(|todos| {
+        if let Some(todo) = todos
+            .iter()
+            .find(|todo| todo.with_untracked(|todo| todo.id == id))
+        {
+            todo.update(|todo| {
+                todo.id = changed.id;
+                todo.version = changed.version;
+            });
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todo(id: u32, version: u32) -> TodoItem {
+        TodoItem {
+            id,
+            version,
+            task: "call mom".into(),
+            ..TodoItem::default()
+        }
+    }
+
+    #[test]
+    fn replayed_todos_move_later_mutations_to_their_id_and_version() {
+        let local = u32::MAX;
+        let mut queue = vec![
+            Mutation::SetDone {
+                id: local,
+                done: true,
+                version: 0,
+            },
+            Mutation::Edit {
+                id: 7,
+                task: "pay rent".into(),
+                version: 2,
+            },
+            Mutation::Delete {
+                id: local,
+                version: 0,
+            },
+        ];
+        queue
+            .iter_mut()
+            .for_each(|mutation| mutation.rebase(local, &todo(12, 1)));
+        assert_eq!(
+            queue,
+            [
+                Mutation::SetDone {
+                    id: 12,
+                    done: true,
+                    version: 1,
+                },
+                Mutation::Edit {
+                    id: 7,
+                    task: "pay rent".into(),
+                    version: 2,
+                },
+                Mutation::Delete { id: 12, version: 1 },
+            ]
+        );
+
+        // an add has no version to catch up with
+        let mut add = Mutation::Add {
+            id: local,
+            task: "call mom".into(),
+        };
+        add.rebase(local, &todo(12, 1));
+        assert_eq!(
+            add,
+            Mutation::Add {
+                id: 12,
+                task: "call mom".into(),
+            }
+        );
+    }
+
+    #[test]
+    fn queues_saved_before_keys_read_as_mutations_only() {
+        let old = r#"[{"Add":{"id":4294967295,"task":"call mom"}}]"#;
+        assert!(serde_json::from_str::<Vec<Queued>>(old).is_err());
+        assert_eq!(
+            serde_json::from_str::<Vec<Mutation>>(old).unwrap(),
+            [Mutation::Add {
+                id: u32::MAX,
+                task: "call mom".into(),
+            }]
+        );
+
+        let queued = vec![Queued {
+            key: 1 << 52,
+            mutation: Mutation::Delete { id: 3, version: 1 },
+        }];
+        let saved = serde_json::to_string(&queued).unwrap();
+        assert_eq!(serde_json::from_str::<Vec<Queued>>(&saved).unwrap(), queued);
+        assert!(serde_json::from_str::<Vec<Mutation>>(&saved).is_err());
+    }
+
+    #[test]
+    fn only_the_holder_replays_until_the_lease_runs_out() {
+        let lease = Lease {
+            tab: 1,
+            until: 1_000.0,
+        };
+        assert!(lease.lets(1, 500.0));
+        assert!(!lease.lets(2, 500.0));
+        assert!(lease.lets(2, 1_001.0));
+        // released
+        assert!(Lease { tab: 1, until: 0.0 }.lets(2, 500.0));
+    }
+}
@@ -0,0 +1,723 @@
+use crate::app::{Priority, TodoItem};
+use crate::dates;
+use serde::{Deserialize, Serialize};
+use std::{cmp::Ordering, fmt, str::FromStr};
+
+/// A parsed filter, such as `is:open tag:shopping milk -"oat milk"`, matching todos
+/// with every one of its clauses.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Query {
+    clauses: Vec<Clause>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Clause {
+    negated: bool,
+    term: Term,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Term {
+    /// A word, matching words in the task starting with it.
+    Word(String),
+    /// A quoted phrase, matching those words in the task in that order.
+    Phrase(String),
+    /// `is:open` or `is:done`.
+    Done(bool),
+    /// `tag:shopping`, lowercased.
+    Tag(String),
+    /// `list:work` or `list:"Home office"`, ignoring ASCII case like SQLite's `NOCASE`,
+    /// or `list:none` for todos in no list.
+    List(Option<String>),
+    /// `priority:high`, or `priority:none` for todos without one.
+    Priority(Option<Priority>),
+    /// `due:<7d`, `due:today`, `due:2024-03-01`, or `due:none` for todos without a
+    /// due date, which no comparison matches.
+    Due(Option<(Comparison, Day)>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Comparison {
+    Before,
+    OnOrBefore,
+    On,
+    OnOrAfter,
+    After,
+}
+
+impl Comparison {
+    fn sql(self) -> &'static str {
+        match self {
+            Comparison::Before => "<",
+            Comparison::OnOrBefore => "<=",
+            Comparison::On => "=",
+            Comparison::OnOrAfter => ">=",
+            Comparison::After => ">",
+        }
+    }
+
+    fn holds(self, ordering: Ordering) -> bool {
+        match self {
+            Comparison::Before => ordering.is_lt(),
+            Comparison::OnOrBefore => ordering.is_le(),
+            Comparison::On => ordering.is_eq(),
+            Comparison::OnOrAfter => ordering.is_ge(),
+            Comparison::After => ordering.is_gt(),
+        }
+    }
+}
+
+/// A date in a `due:` filter, relative ones are resolved when the filter is applied,
+/// so saved searches keep meaning the same.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Day {
+    Date(String),
+    /// Days from today, before it when negative.
+    FromToday(i64),
+}
+
+impl Day {
+    fn date(&self) -> String {
+        match self {
+            Day::Date(date) => date.clone(),
+            Day::FromToday(days) => dates::days_from_today(*days),
+        }
+    }
+}
+
+/// Why a filter couldn't be parsed, with the byte range of the offending part.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub start: usize,
+    pub end: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl Query {
+    pub fn parse(input: &str) -> Result<Query, ParseError> {
+        let mut clauses = vec![];
+        let mut chars = input.char_indices().peekable();
+
+        while let Some(&(start, c)) = chars.peek() {
+            if c.is_whitespace() {
+                chars.next();
+                continue;
+            }
+
+            let negated = c == '-';
+            if negated {
+                chars.next();
+            }
+            let term_start = chars.peek().map_or(input.len(), |&(i, _)| i);
+
+            let term = match chars.peek() {
+                None | Some((_, ' ' | '\t' | '\n')) => {
+                    return Err(ParseError {
+                        start,
+                        end: term_start,
+                        message: "nothing to exclude after `-`".into(),
+                    })
+                }
+                Some((_, '"')) => Term::Phrase(phrase(input, &mut chars)?),
+                Some(_) => {
+                    let mut end = input.len();
+                    while let Some(&(i, c)) = chars.peek() {
+                        if c.is_whitespace() {
+                            end = i;
+                            break;
+                        }
+                        chars.next();
+                    }
+
+                    let word = &input[term_start..end];
+                    match word.split_once(':') {
+                        Some((key, value)) => {
+                            let value = if value.starts_with('"') {
+                                // `key:"some value"` continues past the whitespace
+                                let mut rest = input[term_start + key.len() + 1..]
+                                    .char_indices()
+                                    .map(|(i, c)| (i + term_start + key.len() + 1, c))
+                                    .peekable();
+                                let value = phrase(input, &mut rest)?;
+                                let end = rest.peek().map_or(input.len(), |&(i, _)| i);
+                                while chars.peek().is_some_and(|&(i, _)| i < end) {
+                                    chars.next();
+                                }
+                                value
+                            } else {
+                                value.to_string()
+                            };
+                            filter(key, &value, term_start, term_start + word.len())?
+                        }
+                        None => Term::Word(word.to_string()),
+                    }
+                }
+            };
+
+            clauses.push(Clause { negated, term });
+        }
+
+        Ok(Query { clauses })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.clauses.is_empty()
+    }
+
+    /// Evaluates the filter against a todo, for todos already loaded. Unlike the
+    /// full-text index on the server, only letter case is folded, not accents.
+    pub fn matches(&self, todo: &TodoItem) -> bool {
+        let task: Vec<_> = words(&todo.task).collect();
+
+        self.clauses.iter().all(|clause| {
+            let matched = match &clause.term {
+                Term::Word(word) => {
+                    let word: Vec<_> = words(word).collect();
+                    contains_words(&task, &word, true)
+                }
+                Term::Phrase(phrase) => {
+                    let phrase: Vec<_> = words(phrase).collect();
+                    contains_words(&task, &phrase, false)
+                }
+                Term::Done(done) => todo.done == *done,
+                Term::Tag(tag) => todo.tags.contains(tag),
+                Term::List(list) => match (list, &todo.list) {
+                    (Some(list), Some(todo_list)) => list.eq_ignore_ascii_case(todo_list),
+                    (list, todo_list) => list.is_none() && todo_list.is_none(),
+                },
+                Term::Priority(priority) => todo.priority == *priority,
+                Term::Due(None) => todo.due.is_none(),
+                Term::Due(Some((comparison, day))) => todo
+                    .due
+                    .as_ref()
+                    .is_some_and(|due| comparison.holds(due.as_str().cmp(&day.date()))),
+            };
+            matched != clause.negated
+        })
+    }
+
+    /// Compiles the filter to an SQL condition on the `todos` table, with the values
+    /// to bind to its parameters in order. Text is matched through `todos_fts`.
+    /// Conditions are never null, so negating them matches the todos they don't.
+    pub fn to_sql(&self) -> (String, Vec<String>) {
+        if self.clauses.is_empty() {
+            return ("true".into(), vec![]);
+        }
+
+        let mut params = vec![];
+        let conditions: Vec<_> = self
+            .clauses
+            .iter()
+            .map(|clause| {
+                let condition: String = match &clause.term {
+                    Term::Word(_) | Term::Phrase(_) => {
+                        params.push(clause.term.fts().unwrap_or_default());
+                        "todos.id IN (SELECT rowid FROM todos_fts WHERE todos_fts MATCH ?)".into()
+                    }
+                    Term::Done(true) => "todos.done = true".into(),
+                    Term::Done(false) => "todos.done = false".into(),
+                    Term::Tag(tag) => {
+                        params.push(tag.clone());
+                        "EXISTS (SELECT 1 FROM json_each(todos.tags) WHERE json_each.value = ?)"
+                            .into()
+                    }
+                    Term::List(Some(list)) => {
+                        params.push(list.clone());
+                        "COALESCE(todos.list = ? COLLATE NOCASE, false)".into()
+                    }
+                    Term::List(None) => "todos.list IS NULL".into(),
+                    Term::Priority(Some(priority)) => {
+                        format!("todos.priority IS {}", *priority as i32)
+                    }
+                    Term::Priority(None) => "todos.priority IS NULL".into(),
+                    Term::Due(None) => "todos.due IS NULL".into(),
+                    Term::Due(Some((comparison, day))) => {
+                        params.push(day.date());
+                        format!("COALESCE(todos.due {} ?, false)", comparison.sql())
+                    }
+                };
+                if clause.negated {
+                    format!("NOT ({condition})")
+                } else {
+                    format!("({condition})")
+                }
+            })
+            .collect();
+
+        (conditions.join(" AND "), params)
+    }
+
+    /// An FTS5 query for the words and phrases todos must contain, to rank and
+    /// highlight matches by. `None` when there are none.
+    pub fn fts_match(&self) -> Option<String> {
+        let terms: Vec<_> = self
+            .clauses
+            .iter()
+            .filter(|clause| !clause.negated)
+            .filter_map(|clause| clause.term.fts())
+            .collect();
+        (!terms.is_empty()).then(|| terms.join(" "))
+    }
+}
+
+/// How the todo list is ordered.
+#[cfg_attr(feature = "ssr", derive(sqlx::Type), sqlx(rename_all = "snake_case"))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Sort {
+    /// Best match first while filtering by text, oldest first otherwise.
+    #[default]
+    Relevance,
+    Newest,
+    Oldest,
+    Task,
+    OpenFirst,
+}
+
+impl Sort {
+    pub const ALL: [Sort; 5] = [
+        Sort::Relevance,
+        Sort::Newest,
+        Sort::Oldest,
+        Sort::Task,
+        Sort::OpenFirst,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Sort::Relevance => "relevance",
+            Sort::Newest => "newest",
+            Sort::Oldest => "oldest",
+            Sort::Task => "task",
+            Sort::OpenFirst => "open_first",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Sort::Relevance => "Best match",
+            Sort::Newest => "Newest first",
+            Sort::Oldest => "Oldest first",
+            Sort::Task => "A to Z",
+            Sort::OpenFirst => "Open first",
+        }
+    }
+
+    /// Orders todos for a stable sort, keeping the order they came in for [`Sort::Relevance`].
+    pub fn compare(self, a: &TodoItem, b: &TodoItem) -> Ordering {
+        match self {
+            Sort::Relevance => Ordering::Equal,
+            Sort::Newest => b.id.cmp(&a.id),
+            Sort::Oldest => a.id.cmp(&b.id),
+            Sort::Task => a.task.to_lowercase().cmp(&b.task.to_lowercase()),
+            Sort::OpenFirst => a.done.cmp(&b.done),
+        }
+    }
+}
+
+impl FromStr for Sort {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Sort::ALL
+            .into_iter()
+            .find(|sort| sort.as_str() == s)
+            .ok_or_else(|| format!("unknown sort `{s}`"))
+    }
+}
+
+/// Which todos the list shows, by whether they're done.
+#[cfg_attr(feature = "ssr", derive(sqlx::Type), sqlx(rename_all = "snake_case"))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    #[default]
+    All,
+    Open,
+    Done,
+}
+
+impl Status {
+    pub const ALL: [Status; 3] = [Status::All, Status::Open, Status::Done];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Status::All => "all",
+            Status::Open => "open",
+            Status::Done => "done",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Status::All => "All",
+            Status::Open => "Open",
+            Status::Done => "Done",
+        }
+    }
+
+    /// The same as a filter, `is:open` or `is:done`, for none with [`Status::All`].
+    pub fn clause(self) -> Option<&'static str> {
+        match self {
+            Status::All => None,
+            Status::Open => Some("is:open"),
+            Status::Done => Some("is:done"),
+        }
+    }
+
+    pub fn matches(self, todo: &TodoItem) -> bool {
+        match self {
+            Status::All => true,
+            Status::Open => !todo.done,
+            Status::Done => todo.done,
+        }
+    }
+}
+
+impl FromStr for Status {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Status::ALL
+            .into_iter()
+            .find(|status| status.as_str() == s)
+            .ok_or_else(|| format!("unknown status `{s}`"))
+    }
+}
+
+impl Term {
+    /// Words match as prefixes, phrases as they are. Both are quoted, so FTS5 syntax
+    /// in them is taken literally.
+    fn fts(&self) -> Option<String> {
+        match self {
+            Term::Word(word) => Some(format!("\"{}\"*", word.replace('"', "\"\""))),
+            Term::Phrase(phrase) => Some(format!("\"{}\"", phrase.replace('"', "\"\""))),
+            Term::Done(_) | Term::Tag(_) | Term::List(_) | Term::Priority(_) | Term::Due(_) => None,
+        }
+    }
+}
+
+/// Reads a `"quoted phrase"`, with `chars` at the opening quote.
+fn phrase(
+    input: &str,
+    chars: &mut std::iter::Peekable<impl Iterator<Item = (usize, char)>>,
+) -> Result<String, ParseError> {
+    let (start, _) = chars.next().expect("phrase starts at a quote");
+    for (i, c) in chars.by_ref() {
+        if c == '"' {
+            let phrase = input[start + 1..i].trim();
+            if phrase.is_empty() {
+                return Err(ParseError {
+                    start,
+                    end: i + 1,
+                    message: "empty phrase `\"\"`".into(),
+                });
+            }
+            return Ok(phrase.to_string());
+        }
+    }
+
+    Err(ParseError {
+        start,
+        end: input.len(),
+        message: "missing closing `\"`".into(),
+    })
+}
+
+fn filter(key: &str, value: &str, start: usize, end: usize) -> Result<Term, ParseError> {
+    let error = |message: String| ParseError {
+        start,
+        end,
+        message,
+    };
+
+    match key.to_lowercase().as_str() {
+        "is" => match value.to_lowercase().as_str() {
+            "open" | "undone" | "todo" => Ok(Term::Done(false)),
+            "done" | "closed" | "completed" => Ok(Term::Done(true)),
+            "" => Err(error("`is:` needs a value, `is:open` or `is:done`".into())),
+            _ => Err(error(format!(
+                "unknown state `is:{value}`, expected `is:open` or `is:done`"
+            ))),
+        },
+        "tag" => match value.trim_start_matches('#') {
+            "" => Err(error("`tag:` needs a tag, like `tag:shopping`".into())),
+            tag => Ok(Term::Tag(tag.to_lowercase())),
+        },
+        "list" => match value.trim() {
+            "" => Err(error("`list:` needs a list, like `list:work`".into())),
+            list if list.eq_ignore_ascii_case("none") => Ok(Term::List(None)),
+            list => Ok(Term::List(Some(list.to_string()))),
+        },
+        "priority" => match value.to_lowercase().as_str() {
+            "none" => Ok(Term::Priority(None)),
+            "" => Err(error(
+                "`priority:` needs a value, `high`, `medium`, `low` or `none`".into(),
+            )),
+            value => match value.parse() {
+                Ok(priority) => Ok(Term::Priority(Some(priority))),
+                Err(_) => Err(error(format!(
+                    "unknown priority `priority:{value}`, expected `high`, `medium`, `low` or `none`"
+                ))),
+            },
+        },
+        "due" => due(value).map(Term::Due).map_err(error),
+        "" => Err(error(format!(
+            "`:{value}` is missing a filter name, such as `is:`"
+        ))),
+        _ => Err(error(format!(
+            "unknown filter `{key}:`, filters are `is:`, `tag:`, `list:`, `priority:` and `due:`, \
+            quote it to search for the text"
+        ))),
+    }
+}
+
+/// Reads the value of a `due:` filter, a day with an optional comparison before it,
+/// `overdue` or `none`.
+fn due(value: &str) -> Result<Option<(Comparison, Day)>, String> {
+    let value = value.to_lowercase();
+    let (comparison, day) = [
+        ("<=", Comparison::OnOrBefore),
+        (">=", Comparison::OnOrAfter),
+        ("<", Comparison::Before),
+        (">", Comparison::After),
+        ("=", Comparison::On),
+    ]
+    .into_iter()
+    .find_map(|(prefix, comparison)| Some((comparison, value.strip_prefix(prefix)?)))
+    .unwrap_or((Comparison::On, &value));
+
+    let day = match day {
+        "none" if comparison == Comparison::On => return Ok(None),
+        "overdue" if comparison == Comparison::On => {
+            return Ok(Some((Comparison::Before, Day::FromToday(0))))
+        }
+        "" => {
+            return Err(
+                "`due:` needs a day, like `due:today`, `due:<7d` or `due:2024-03-01`".into(),
+            )
+        }
+        "today" => Day::FromToday(0),
+        "tomorrow" => Day::FromToday(1),
+        "yesterday" => Day::FromToday(-1),
+        day if dates::is_date(day) => Day::Date(day.to_string()),
+        day => {
+            let days = match day.strip_suffix('d') {
+                Some(days) => days.parse::<i64>().ok(),
+                None => day
+                    .strip_suffix('w')
+                    .and_then(|weeks| weeks.parse::<i64>().ok())
+                    .map(|weeks| weeks * 7),
+            };
+            match days {
+                // keeps resolved dates within what `dates` formats
+                Some(days) if days.abs() <= 100_000 => Day::FromToday(days),
+                _ => {
+                    return Err(format!(
+                        "unknown day `due:{value}`, expected a date like `2024-03-01`, \
+                        `today`, `tomorrow` or a number of days or weeks like `7d` or `2w`"
+                    ))
+                }
+            }
+        }
+    };
+    Ok(Some((comparison, day)))
+}
+
+/// The words of `text` as the full-text index sees them, lowercased.
+fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+}
+
+/// Whether `words` has `needle` in it, with the last word of `needle` matching as a
+/// prefix if `prefix`. An empty needle, like a word of only punctuation, matches nothing.
+fn contains_words(words: &[String], needle: &[String], prefix: bool) -> bool {
+    if needle.is_empty() || needle.len() > words.len() {
+        return false;
+    }
+
+    words.windows(needle.len()).any(|window| {
+        window
+            .iter()
+            .zip(needle)
+            .enumerate()
+            .all(|(i, (word, expected))| {
+                if prefix && i == needle.len() - 1 {
+                    word.starts_with(expected.as_str())
+                } else {
+                    word == expected
+                }
+            })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todo(task: &str) -> TodoItem {
+        TodoItem {
+            id: 1,
+            task: task.into(),
+            ..TodoItem::default()
+        }
+    }
+
+    fn clause(negated: bool, term: Term) -> Clause {
+        Clause { negated, term }
+    }
+
+    #[test]
+    fn parses_words_phrases_and_filters() {
+        let query = Query::parse(r#"milk -"oat milk" is:open -tag:#Shop priority:high due:<7d"#);
+        assert_eq!(
+            query.map(|query| query.clauses),
+            Ok(vec![
+                clause(false, Term::Word("milk".into())),
+                clause(true, Term::Phrase("oat milk".into())),
+                clause(false, Term::Done(false)),
+                clause(true, Term::Tag("shop".into())),
+                clause(false, Term::Priority(Some(Priority::High))),
+                clause(
+                    false,
+                    Term::Due(Some((Comparison::Before, Day::FromToday(7))))
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn parses_due_days() {
+        let parsed = |value: &str| due(value).unwrap();
+        assert_eq!(parsed("none"), None);
+        assert_eq!(
+            parsed("overdue"),
+            Some((Comparison::Before, Day::FromToday(0)))
+        );
+        assert_eq!(
+            parsed("tomorrow"),
+            Some((Comparison::On, Day::FromToday(1)))
+        );
+        assert_eq!(
+            parsed(">=2w"),
+            Some((Comparison::OnOrAfter, Day::FromToday(14)))
+        );
+        assert_eq!(
+            parsed("<=-3d"),
+            Some((Comparison::OnOrBefore, Day::FromToday(-3)))
+        );
+        assert_eq!(
+            parsed(">2024-03-01"),
+            Some((Comparison::After, Day::Date("2024-03-01".into())))
+        );
+    }
+
+    #[test]
+    fn points_at_what_fails_to_parse() {
+        let error = |input: &str| Query::parse(input).map(|_| ()).unwrap_err();
+
+        assert_eq!((error("milk -").start, error("milk -").end), (5, 6));
+        assert_eq!((error("a \"milk").start, error("a \"milk").end), (2, 7));
+        assert_eq!(
+            (error("a color:red").start, error("a color:red").end),
+            (2, 11)
+        );
+        assert!(error("due:soon").message.contains("unknown day"));
+        assert!(error("due:<none").message.contains("unknown day"));
+        assert!(error("priority:urgent")
+            .message
+            .contains("unknown priority"));
+        assert!(error("tag:").message.contains("needs a tag"));
+        assert!(error("list:").message.contains("needs a list"));
+    }
+
+    #[test]
+    fn matches_loaded_todos() {
+        let mut todo = todo("Buy oat milk");
+        todo.tags = vec!["shop".into()];
+        todo.priority = Some(Priority::Medium);
+        todo.due = Some(dates::days_from_today(3));
+        let matches = |input: &str, todo: &TodoItem| Query::parse(input).unwrap().matches(todo);
+
+        assert!(matches("mil", &todo));
+        assert!(matches("\"oat milk\"", &todo));
+        assert!(!matches("\"milk oat\"", &todo));
+        assert!(matches("is:open tag:shop", &todo));
+        assert!(!matches("tag:work", &todo));
+        assert!(matches("list:none", &todo));
+        todo.list = Some("Home office".into());
+        assert!(matches("list:\"home OFFICE\" -list:none", &todo));
+        assert!(!matches("list:home", &todo));
+        assert!(matches("priority:medium -priority:high", &todo));
+        assert!(!matches("priority:none", &todo));
+        assert!(matches("due:<7d due:>today -due:none", &todo));
+        assert!(!matches("due:overdue", &todo));
+        assert!(matches(
+            &format!("due:{}", dates::days_from_today(3)),
+            &todo
+        ));
+
+        // todos without a due date match no comparison, only their negation
+        todo.due = None;
+        assert!(matches("due:none -due:<7d", &todo));
+        assert!(!matches("due:>=today", &todo));
+    }
+
+    #[test]
+    fn compiles_to_sql() {
+        let sql = |input: &str| Query::parse(input).unwrap().to_sql();
+
+        assert_eq!(sql(""), ("true".into(), vec![]));
+        assert_eq!(
+            sql("milk -is:done"),
+            (
+                "(todos.id IN (SELECT rowid FROM todos_fts WHERE todos_fts MATCH ?)) \
+                AND NOT (todos.done = true)"
+                    .into(),
+                vec!["\"milk\"*".into()]
+            )
+        );
+        assert_eq!(
+            sql("tag:shop -priority:high priority:none"),
+            (
+                "(EXISTS (SELECT 1 FROM json_each(todos.tags) WHERE json_each.value = ?)) \
+                AND NOT (todos.priority IS 3) AND (todos.priority IS NULL)"
+                    .into(),
+                vec!["shop".into()]
+            )
+        );
+        assert_eq!(
+            sql("-due:<=2024-03-01 due:none"),
+            (
+                "NOT (COALESCE(todos.due <= ?, false)) AND (todos.due IS NULL)".into(),
+                vec!["2024-03-01".into()]
+            )
+        );
+        assert_eq!(
+            sql("list:\"Home office\" -list:none"),
+            (
+                "(COALESCE(todos.list = ? COLLATE NOCASE, false)) AND NOT (todos.list IS NULL)"
+                    .into(),
+                vec!["Home office".into()]
+            )
+        );
+        assert_eq!(sql("due:tomorrow").1, vec![dates::days_from_today(1)]);
+    }
+
+    #[test]
+    fn searches_words_as_prefixes_and_phrases_as_they_are() {
+        let query = Query::parse(r#"mil "oat ""milk" is:open"#).unwrap();
+        assert_eq!(query.fts_match(), Some("\"mil\"* \"oat\" \"milk\"".into()));
+        assert_eq!(Query::parse("is:open").unwrap().fts_match(), None);
+    }
+}
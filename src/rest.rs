@@ -0,0 +1,827 @@
+//! A conventional JSON API under `/api/v1`, for clients other than the app, made of
+//! the same functions as the app's server functions:
+//!
+//! - `GET /api/v1/todos?q=&after=&limit=`, a [`TodoPage`](crate::app::TodoPage) of the
+//!   todos matching `q`
+//! - `POST /api/v1/todos` with a [`NewTodo`], 201 with the todo
+//! - `GET /api/v1/todos/:id`
+//! - `PATCH /api/v1/todos/:id` with [`TodoChanges`]
+//! - `DELETE /api/v1/todos/:id?version=`, `version` being required
+//! - `POST /api/v1/todos/bulk/done`, `/bulk/undone` and `/bulk/delete` with a
+//!   [`Selection`]
+//! - `POST /api/v1/todos/bulk/tag` with [`TagTodos`], `/bulk/move` with
+//!   [`MoveTodos`] and `/bulk/priority` with [`PrioritizeTodos`]
+//! - `GET /api/v1/lists`, the saved searches as [`Lists`]
+//! - `GET /api/v1/events`, a stream of server-sent `changed` events, one when
+//!   connecting and one whenever todos are added, changed or deleted, by anyone
+//!
+//! Errors are an [`ErrorResponse`], with 404 for todos that don't exist, 409 for
+//! changes to a todo at another version, and 422 for invalid input. When the server
+//! has users, requests pass one's token as `Authorization: Bearer <token>`, or are
+//! a 401, see [`auth`](crate::auth).
+//!
+//! The OpenAPI document at `/api/openapi.json` is generated from the handlers and
+//! types here.
+
+use crate::app::{Priority, TodoItem, Versioned};
+use crate::saved_search::SavedSearch;
+use cfg_if::cfg_if;
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// Body of `POST /api/v1/todos`, the todo added isn't done.
+#[cfg_attr(feature = "ssr", derive(utoipa::ToSchema))]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NewTodo {
+    pub task: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<Priority>,
+    /// `YYYY-MM-DD`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due: Option<String>,
+    /// Lowercased, with a leading `#` dropped.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// The list it goes in, none when blank.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub list: Option<String>,
+}
+
+/// Body of `PATCH /api/v1/todos/:id`, what's set is changed, and `null` clears
+/// `priority`, `due` and `list`. `version` is the one last seen, changing a todo
+/// changed since is a conflict.
+#[cfg_attr(feature = "ssr", derive(utoipa::ToSchema))]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TodoChanges {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub done: Option<bool>,
+    #[serde(
+        default,
+        deserialize_with = "set",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[cfg_attr(feature = "ssr", schema(value_type = Option<Priority>))]
+    pub priority: Option<Option<Priority>>,
+    /// `YYYY-MM-DD`.
+    #[serde(
+        default,
+        deserialize_with = "set",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[cfg_attr(feature = "ssr", schema(value_type = Option<String>))]
+    pub due: Option<Option<String>>,
+    /// All of the todo's tags, replacing the ones it has.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+    #[serde(
+        default,
+        deserialize_with = "set",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[cfg_attr(feature = "ssr", schema(value_type = Option<String>))]
+    pub list: Option<Option<String>>,
+    pub version: u32,
+}
+
+/// A field that's there, `null` or not, which serde otherwise reads as missing.
+fn set<'de, T, D>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    Option::deserialize(deserializer).map(Some)
+}
+
+/// Body of the bulk endpoints, either `todos` at the versions last seen, or those
+/// matching `filter` in the search syntax, every todo when it's empty.
+#[cfg_attr(feature = "ssr", derive(utoipa::ToSchema))]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Selection {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub todos: Option<Vec<Versioned>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
+}
+
+/// Body of `/bulk/tag`, the tags to add to `todos` at the versions last seen.
+#[cfg_attr(feature = "ssr", derive(utoipa::ToSchema))]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TagTodos {
+    pub todos: Vec<Versioned>,
+    pub tags: Vec<String>,
+}
+
+/// Body of `/bulk/move`, the list to move `todos` at the versions last seen to, out
+/// of any when it's `null` or blank.
+#[cfg_attr(feature = "ssr", derive(utoipa::ToSchema))]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MoveTodos {
+    pub todos: Vec<Versioned>,
+    pub list: Option<String>,
+}
+
+/// Body of `/bulk/priority`, the priority to give `todos` at the versions last seen,
+/// none when it's `null`.
+#[cfg_attr(feature = "ssr", derive(utoipa::ToSchema))]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PrioritizeTodos {
+    pub todos: Vec<Versioned>,
+    pub priority: Option<Priority>,
+}
+
+/// Todos changed by `/bulk/done`, `/bulk/undone`, `/bulk/tag`, `/bulk/move` and
+/// `/bulk/priority`, and ids of the selected ones left alone as they changed since
+/// their versions.
+#[cfg_attr(feature = "ssr", derive(utoipa::ToSchema))]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Changed {
+    pub todos: Vec<TodoItem>,
+    pub conflicts: Vec<u32>,
+}
+
+/// Ids of the todos `/bulk/delete` deleted, and of the selected ones left alone as
+/// they changed since their versions.
+#[cfg_attr(feature = "ssr", derive(utoipa::ToSchema))]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Deleted {
+    pub deleted: Vec<u32>,
+    pub conflicts: Vec<u32>,
+}
+
+/// A saved search, which clients show as a list of the todos matching `filter`, like
+/// `list:work` for the todos in a list. Its status tab is part of the filter, as `is:`.
+#[cfg_attr(feature = "ssr", derive(utoipa::ToSchema))]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TodoList {
+    pub id: u32,
+    pub name: String,
+    pub filter: String,
+}
+
+impl From<SavedSearch> for TodoList {
+    fn from(saved: SavedSearch) -> Self {
+        TodoList {
+            id: saved.id,
+            name: saved.name,
+            filter: match saved.status.clause() {
+                Some(clause) => format!("{} {clause}", saved.query).trim().to_string(),
+                None => saved.query,
+            },
+        }
+    }
+}
+
+/// Body of `GET /api/v1/lists`, in the order the app shows them.
+#[cfg_attr(feature = "ssr", derive(utoipa::ToSchema))]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lists {
+    pub lists: Vec<TodoList>,
+}
+
+/// Body of every error response.
+#[cfg_attr(feature = "ssr", derive(utoipa::ToSchema))]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErrorResponse {
+    pub error: ApiError,
+}
+
+#[cfg_attr(feature = "ssr", derive(utoipa::ToSchema))]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiError {
+    /// For programs to tell errors apart, like `not_found`, `conflict` or `invalid`.
+    pub code: String,
+    /// For people.
+    pub message: String,
+}
+
+cfg_if! { if #[cfg(feature = "ssr")] {
+    use crate::auth;
+    use crate::app::{
+        check_due, check_task, db, delete_all, delete_many, delete_todo, get_todo, list_name,
+        list_todos, mark_all_done, mark_all_undone, move_many, parse_tags, set_completed,
+        set_done_many, set_priority_many, tag_many, todos_changed, todos_tag, watch_todos, Bulk,
+        TodoPage, CONFLICT, NOT_FOUND,
+    };
+    use crate::saved_search::saved_searches_of;
+    use axum::{
+        extract::{
+            rejection::{JsonRejection, PathRejection, QueryRejection},
+            Path, Query,
+        },
+        http::{header, HeaderMap, Request, StatusCode},
+        middleware::{self, Next},
+        response::{
+            sse::{Event, KeepAlive, Sse},
+            IntoResponse, Response,
+        },
+        routing::{get, post, MethodRouter},
+        Json, Router,
+    };
+    use futures::stream::{self, Stream};
+    use leptos::ServerFnError;
+    use std::{convert::Infallible, time::Duration};
+    use utoipa::{IntoParams, OpenApi};
+
+    /// Todos `GET /api/v1/todos` returns without a `limit`.
+    const DEFAULT_LIMIT: u32 = 100;
+
+    /// How often `/api/v1/events` looks for changes it wasn't told of, made to the
+    /// database outside the server.
+    const EVENTS_INTERVAL: Duration = Duration::from_secs(10);
+
+    #[derive(OpenApi)]
+    #[openapi(
+        info(
+            title = "Todos",
+            version = "1",
+            description = "Todos, as the app has them. When the server has users, \
+                requests pass one's token as `Authorization: Bearer <token>`."
+        ),
+        paths(
+            list, create, fetch, update, remove, mark_done, mark_undone, remove_many, tag,
+            move_to, prioritize, lists, events
+        ),
+        components(schemas(
+            TodoItem, Priority, TodoPage, NewTodo, TodoChanges, Selection, Versioned, Changed,
+            Deleted, TagTodos, MoveTodos, PrioritizeTodos, TodoList, Lists, ErrorResponse,
+            ApiError,
+        ))
+    )]
+    struct ApiDoc;
+
+    /// The routes of the API by path, each one in the OpenAPI document.
+    fn api_routes<S: Clone + Send + Sync + 'static>() -> Vec<(&'static str, MethodRouter<S>)> {
+        vec![
+            ("/api/v1/todos", get(list).post(create)),
+            ("/api/v1/todos/:id", get(fetch).patch(update).delete(remove)),
+            ("/api/v1/todos/bulk/done", post(mark_done)),
+            ("/api/v1/todos/bulk/undone", post(mark_undone)),
+            ("/api/v1/todos/bulk/delete", post(remove_many)),
+            ("/api/v1/todos/bulk/tag", post(tag)),
+            ("/api/v1/todos/bulk/move", post(move_to)),
+            ("/api/v1/todos/bulk/priority", post(prioritize)),
+            ("/api/v1/lists", get(lists)),
+            ("/api/v1/events", get(events)),
+        ]
+    }
+
+    pub fn rest_routes<S: Clone + Send + Sync + 'static>() -> Router<S> {
+        api_routes()
+            .into_iter()
+            .fold(Router::new(), |router, (path, route)| router.route(path, route))
+            .route_layer(middleware::from_fn(require_token))
+            // the document is open, for reading before having a token
+            .route("/api/openapi.json", get(|| async { Json(ApiDoc::openapi()) }))
+    }
+
+    async fn require_token<B>(request: Request<B>, next: Next<B>) -> Response {
+        if auth::allowed(request.headers()).await {
+            next.run(request).await
+        } else {
+            let failure = Failure::new(
+                StatusCode::UNAUTHORIZED,
+                "unauthorized",
+                "missing or wrong API token",
+            );
+            ([(header::WWW_AUTHENTICATE, "Bearer")], failure).into_response()
+        }
+    }
+
+    /// An error response, see [`ErrorResponse`].
+    struct Failure {
+        status: StatusCode,
+        code: &'static str,
+        message: String,
+    }
+
+    impl Failure {
+        fn new(status: StatusCode, code: &'static str, message: impl Into<String>) -> Self {
+            Failure {
+                status,
+                code,
+                message: message.into(),
+            }
+        }
+
+        fn not_found() -> Self {
+            Failure::new(StatusCode::NOT_FOUND, "not_found", NOT_FOUND)
+        }
+
+        /// Requests axum couldn't read, keeping its status.
+        fn rejected(status: StatusCode, message: String) -> Self {
+            let code = match status {
+                StatusCode::UNPROCESSABLE_ENTITY => "invalid",
+                StatusCode::UNSUPPORTED_MEDIA_TYPE => "unsupported_media_type",
+                _ => "bad_request",
+            };
+            Failure::new(status, code, message)
+        }
+    }
+
+    impl From<ServerFnError> for Failure {
+        fn from(e: ServerFnError) -> Self {
+            match e {
+                ServerFnError::Args(message) => {
+                    Failure::new(StatusCode::UNPROCESSABLE_ENTITY, "invalid", message)
+                }
+                ServerFnError::ServerError(message) if message == CONFLICT => {
+                    Failure::new(StatusCode::CONFLICT, "conflict", message)
+                }
+                ServerFnError::ServerError(message) if message == NOT_FOUND => {
+                    Failure::not_found()
+                }
+                e => Failure::new(StatusCode::INTERNAL_SERVER_ERROR, "internal", e.to_string()),
+            }
+        }
+    }
+
+    impl From<JsonRejection> for Failure {
+        fn from(rejection: JsonRejection) -> Self {
+            Failure::rejected(rejection.status(), rejection.body_text())
+        }
+    }
+
+    impl From<QueryRejection> for Failure {
+        fn from(rejection: QueryRejection) -> Self {
+            Failure::rejected(rejection.status(), rejection.body_text())
+        }
+    }
+
+    impl From<PathRejection> for Failure {
+        fn from(rejection: PathRejection) -> Self {
+            Failure::rejected(rejection.status(), rejection.body_text())
+        }
+    }
+
+    impl IntoResponse for Failure {
+        fn into_response(self) -> Response {
+            let body = ErrorResponse {
+                error: ApiError {
+                    code: self.code.into(),
+                    message: self.message,
+                },
+            };
+            (self.status, Json(body)).into_response()
+        }
+    }
+
+    type ApiResult<T> = Result<T, Failure>;
+
+    #[derive(Deserialize, IntoParams)]
+    #[into_params(parameter_in = Query)]
+    struct ListOptions {
+        /// Only the todos matching this filter in the search syntax, like `+work is:open`.
+        #[serde(default)]
+        q: String,
+        /// The `next` of the page before, to start after.
+        after: Option<u32>,
+        /// Most todos to return, 100 by default and 500 at most.
+        limit: Option<u32>,
+    }
+
+    /// Todos in id order, a page at a time.
+    #[utoipa::path(
+        get,
+        path = "/api/v1/todos",
+        params(ListOptions),
+        responses(
+            (status = 200, description = "A page of todos", body = TodoPage),
+            (status = 422, description = "Invalid filter", body = ErrorResponse),
+        )
+    )]
+    async fn list(
+        options: Result<Query<ListOptions>, QueryRejection>,
+    ) -> ApiResult<Json<TodoPage>> {
+        let Query(options) = options?;
+        let limit = options.limit.unwrap_or(DEFAULT_LIMIT);
+        Ok(Json(list_todos(&options.q, options.after, limit).await?))
+    }
+
+    #[utoipa::path(
+        get,
+        path = "/api/v1/todos/{id}",
+        params(("id" = u32, Path, description = "Id of the todo")),
+        responses(
+            (status = 200, description = "The todo", body = TodoItem),
+            (status = 404, description = "No such todo", body = ErrorResponse),
+        )
+    )]
+    async fn fetch(id: Result<Path<u32>, PathRejection>) -> ApiResult<Json<TodoItem>> {
+        let Path(id) = id?;
+        get_todo(id).await?.map(Json).ok_or_else(Failure::not_found)
+    }
+
+    #[utoipa::path(
+        post,
+        path = "/api/v1/todos",
+        request_body = NewTodo,
+        responses(
+            (status = 201, description = "The todo added, not done", body = TodoItem,
+                headers(("location" = String, description = "URL of the todo"))),
+            (status = 422, description = "Empty task, or invalid due date", body = ErrorResponse),
+        )
+    )]
+    async fn create(body: Result<Json<NewTodo>, JsonRejection>) -> ApiResult<Response> {
+        let Json(new) = body?;
+        let task = check_task(&new.task)?;
+        let due = check_due(new.due.as_deref())?;
+
+        let pool = db().await?;
+        let todo = sqlx::query_as::<_, TodoItem>(
+            "INSERT INTO todos (task, done, priority, due, tags, list, created)
+            VALUES (?, false, ?, ?, ?, ?, date('now')) RETURNING *",
+        )
+        .bind(task)
+        .bind(new.priority)
+        .bind(due)
+        .bind(sqlx::types::Json(parse_tags(&new.tags.join(" "))))
+        .bind(new.list.as_deref().and_then(list_name))
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))?;
+        todos_changed();
+
+        let location = format!("/api/v1/todos/{}", todo.id);
+        Ok((StatusCode::CREATED, [(header::LOCATION, location)], Json(todo)).into_response())
+    }
+
+    /// Changes what's set of a todo's task, whether it's done, and its details.
+    #[utoipa::path(
+        patch,
+        path = "/api/v1/todos/{id}",
+        params(("id" = u32, Path, description = "Id of the todo")),
+        request_body = TodoChanges,
+        responses(
+            (status = 200, description = "The todo changed", body = TodoItem),
+            (status = 404, description = "No such todo", body = ErrorResponse),
+            (status = 409, description = "Changed since `version`", body = ErrorResponse),
+            (status = 422, description = "Empty task, invalid due date, or nothing to change",
+                body = ErrorResponse),
+        )
+    )]
+    async fn update(
+        id: Result<Path<u32>, PathRejection>,
+        body: Result<Json<TodoChanges>, JsonRejection>,
+    ) -> ApiResult<Json<TodoItem>> {
+        let Path(id) = id?;
+        let Json(changes) = body?;
+        let task = changes.task.as_deref().map(check_task).transpose()?;
+        let done = changes.done;
+        let priority = changes.priority;
+        let due = changes.due.map(|due| check_due(due.as_deref())).transpose()?;
+        let tags = changes.tags.map(|tags| parse_tags(&tags.join(" ")));
+        let list = changes.list.map(|list| list.as_deref().and_then(list_name));
+        if task.is_none()
+            && done.is_none()
+            && priority.is_none()
+            && due.is_none()
+            && tags.is_none()
+            && list.is_none()
+        {
+            return Err(Failure::new(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "invalid",
+                "nothing to change, set `task`, `done`, `priority`, `due`, `tags` or `list`",
+            ));
+        }
+
+        // a single statement, so all of it changes or none does. Fields that can be
+        // cleared come as whether they're set and what to, as `null` is a value
+        let pool = db().await?;
+        let sql = format!(
+            "UPDATE todos SET task = coalesce(?1, task), done = coalesce(?2, done), {},
+            priority = CASE WHEN ?5 THEN ?6 ELSE priority END,
+            due = CASE WHEN ?7 THEN ?8 ELSE due END,
+            tags = coalesce(?9, tags),
+            list = CASE WHEN ?10 THEN ?11 ELSE list END,
+            version = version + 1
+            WHERE id = ?3 AND version = ?4
+            AND (task != coalesce(?1, task) OR done != coalesce(?2, done)
+            OR ?5 AND priority IS NOT ?6 OR ?7 AND due IS NOT ?8
+            OR tags != coalesce(?9, tags) OR ?10 AND list IS NOT ?11) RETURNING *",
+            set_completed("coalesce(?2, done)")
+        );
+        let updated = sqlx::query_as::<_, TodoItem>(&sql)
+            .bind(task.as_deref())
+            .bind(done)
+            .bind(id)
+            .bind(changes.version)
+            .bind(priority.is_some())
+            .bind(priority.flatten())
+            .bind(due.is_some())
+            .bind(due.clone().flatten())
+            .bind(tags.clone().map(sqlx::types::Json))
+            .bind(list.is_some())
+            .bind(list.clone().flatten())
+            .fetch_optional(&pool)
+            .await
+            .map_err(|e| ServerFnError::ServerError(e.to_string()))?;
+
+        match updated {
+            Some(todo) => {
+                todos_changed();
+                Ok(Json(todo))
+            }
+            // passes when the todo already is as asked, so retries don't fail
+            None => match get_todo(id).await? {
+                Some(todo)
+                    if task.as_ref().is_none_or(|task| *task == todo.task)
+                        && done.is_none_or(|done| done == todo.done)
+                        && priority.is_none_or(|priority| priority == todo.priority)
+                        && due.as_ref().is_none_or(|due| *due == todo.due)
+                        && tags.as_ref().is_none_or(|tags| *tags == todo.tags)
+                        && list.as_ref().is_none_or(|list| *list == todo.list) =>
+                {
+                    Ok(Json(todo))
+                }
+                Some(_) => Err(ServerFnError::ServerError(CONFLICT.into()).into()),
+                None => Err(Failure::not_found()),
+            },
+        }
+    }
+
+    #[derive(Deserialize, IntoParams)]
+    #[into_params(parameter_in = Query)]
+    struct DeleteOptions {
+        /// The version last seen, to not delete a todo changed since.
+        version: u32,
+    }
+
+    /// Deletes a todo at `version`. Deleting what's already gone passes, like
+    /// [`delete_todo`], so retries don't fail.
+    #[utoipa::path(
+        delete,
+        path = "/api/v1/todos/{id}",
+        params(("id" = u32, Path, description = "Id of the todo"), DeleteOptions),
+        responses(
+            (status = 204, description = "Deleted"),
+            (status = 400, description = "No `version`", body = ErrorResponse),
+            (status = 409, description = "Changed since `version`", body = ErrorResponse),
+        )
+    )]
+    async fn remove(
+        id: Result<Path<u32>, PathRejection>,
+        options: Result<Query<DeleteOptions>, QueryRejection>,
+    ) -> ApiResult<StatusCode> {
+        let Path(id) = id?;
+        let Query(options) = options?;
+
+        delete_todo(id, options.version).await?;
+        Ok(StatusCode::NO_CONTENT)
+    }
+
+    enum Selected {
+        Todos(Vec<Versioned>),
+        Filter(String),
+    }
+
+    fn selected(body: Result<Json<Selection>, JsonRejection>) -> ApiResult<Selected> {
+        let Json(selection) = body?;
+        match (selection.todos, selection.filter) {
+            (Some(todos), None) => Ok(Selected::Todos(todos)),
+            (None, Some(filter)) => Ok(Selected::Filter(filter)),
+            _ => Err(Failure::new(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "invalid",
+                "set one of `todos` or `filter`",
+            )),
+        }
+    }
+
+    #[utoipa::path(
+        post,
+        path = "/api/v1/todos/bulk/done",
+        request_body = Selection,
+        responses(
+            (status = 200, description = "The todos that weren't done before", body = Changed),
+            (status = 422, description = "Invalid selection or filter", body = ErrorResponse),
+        )
+    )]
+    async fn mark_done(body: Result<Json<Selection>, JsonRejection>) -> ApiResult<Json<Changed>> {
+        mark(body, true).await
+    }
+
+    #[utoipa::path(
+        post,
+        path = "/api/v1/todos/bulk/undone",
+        request_body = Selection,
+        responses(
+            (status = 200, description = "The todos that were done before", body = Changed),
+            (status = 422, description = "Invalid selection or filter", body = ErrorResponse),
+        )
+    )]
+    async fn mark_undone(
+        body: Result<Json<Selection>, JsonRejection>,
+    ) -> ApiResult<Json<Changed>> {
+        mark(body, false).await
+    }
+
+    async fn mark(
+        body: Result<Json<Selection>, JsonRejection>,
+        done: bool,
+    ) -> ApiResult<Json<Changed>> {
+        let changed = match (selected(body)?, done) {
+            (Selected::Todos(todos), done) => set_done_many(todos, done).await?.into(),
+            (Selected::Filter(filter), true) => Changed {
+                todos: mark_all_done(filter).await?,
+                conflicts: vec![],
+            },
+            (Selected::Filter(filter), false) => Changed {
+                todos: mark_all_undone(filter).await?,
+                conflicts: vec![],
+            },
+        };
+        Ok(Json(changed))
+    }
+
+    #[utoipa::path(
+        post,
+        path = "/api/v1/todos/bulk/delete",
+        request_body = Selection,
+        responses(
+            (status = 200, description = "Ids of the todos deleted", body = Deleted),
+            (status = 422, description = "Invalid selection or filter", body = ErrorResponse),
+        )
+    )]
+    async fn remove_many(
+        body: Result<Json<Selection>, JsonRejection>,
+    ) -> ApiResult<Json<Deleted>> {
+        let deleted = match selected(body)? {
+            Selected::Todos(todos) => {
+                let bulk = delete_many(todos).await?;
+                Deleted {
+                    deleted: bulk.changed,
+                    conflicts: bulk.conflicts,
+                }
+            }
+            Selected::Filter(filter) => Deleted {
+                deleted: delete_all(filter).await?,
+                conflicts: vec![],
+            },
+        };
+        Ok(Json(deleted))
+    }
+
+    #[utoipa::path(
+        post,
+        path = "/api/v1/todos/bulk/tag",
+        request_body = TagTodos,
+        responses(
+            (status = 200, description = "The todos that didn't have every tag", body = Changed),
+            (status = 422, description = "No tags", body = ErrorResponse),
+        )
+    )]
+    async fn tag(body: Result<Json<TagTodos>, JsonRejection>) -> ApiResult<Json<Changed>> {
+        let Json(body) = body?;
+        Ok(Json(tag_many(body.todos, body.tags.join(" ")).await?.into()))
+    }
+
+    #[utoipa::path(
+        post,
+        path = "/api/v1/todos/bulk/move",
+        request_body = MoveTodos,
+        responses(
+            (status = 200, description = "The todos that were in another list", body = Changed),
+            (status = 422, description = "Invalid todos", body = ErrorResponse),
+        )
+    )]
+    async fn move_to(body: Result<Json<MoveTodos>, JsonRejection>) -> ApiResult<Json<Changed>> {
+        let Json(body) = body?;
+        let list = body.list.unwrap_or_default();
+        Ok(Json(move_many(body.todos, list).await?.into()))
+    }
+
+    #[utoipa::path(
+        post,
+        path = "/api/v1/todos/bulk/priority",
+        request_body = PrioritizeTodos,
+        responses(
+            (status = 200, description = "The todos that had another priority", body = Changed),
+            (status = 422, description = "Invalid todos or priority", body = ErrorResponse),
+        )
+    )]
+    async fn prioritize(
+        body: Result<Json<PrioritizeTodos>, JsonRejection>,
+    ) -> ApiResult<Json<Changed>> {
+        let Json(body) = body?;
+        Ok(Json(set_priority_many(body.todos, body.priority).await?.into()))
+    }
+
+    impl From<Bulk<TodoItem>> for Changed {
+        fn from(bulk: Bulk<TodoItem>) -> Self {
+            Changed {
+                todos: bulk.changed,
+                conflicts: bulk.conflicts,
+            }
+        }
+    }
+
+    #[utoipa::path(
+        get,
+        path = "/api/v1/lists",
+        responses(
+            (status = 200, description = "The saved searches of the token's user", body = Lists)
+        )
+    )]
+    async fn lists(headers: HeaderMap) -> ApiResult<Json<Lists>> {
+        let user = auth::request_user(&headers).await;
+        let lists = saved_searches_of(user.as_ref().map(|user| user.0.as_str())).await?;
+        Ok(Json(Lists {
+            lists: lists.into_iter().map(TodoList::from).collect(),
+        }))
+    }
+
+    /// A stream of server-sent `changed` events, with the [`todos_tag`] of the todos
+    /// as data, one when connecting and one whenever todos change.
+    ///
+    /// Compares [`todos_tag`] whenever the server changes todos, see [`watch_todos`],
+    /// and every [`EVENTS_INTERVAL`] besides, so changes to the database from outside
+    /// the server are sent too.
+    #[utoipa::path(
+        get,
+        path = "/api/v1/events",
+        responses(
+            (
+                status = 200,
+                description = "`changed` events, as long as the connection is open",
+                body = String,
+                content_type = "text/event-stream"
+            )
+        )
+    )]
+    async fn events() -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+        let state = (None::<String>, watch_todos());
+        let changes = stream::unfold(state, |(last, mut changed)| async move {
+            let mut wait = last.is_some();
+            loop {
+                if wait {
+                    // until told of a change, or for the interval
+                    let _ = tokio::time::timeout(EVENTS_INTERVAL, changed.changed()).await;
+                }
+                wait = true;
+                // an error is most likely the database being busy, tried again next time
+                let Ok(tag) = todos_tag().await else {
+                    continue;
+                };
+                if last.as_ref() != Some(&tag) {
+                    let event = Event::default().event("changed").data(&tag);
+                    return Some((Ok(event), (Some(tag), changed)));
+                }
+            }
+        });
+        Sse::new(changes).keep_alive(KeepAlive::default())
+    }
+}}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::Method};
+    use tower::ServiceExt;
+    use utoipa::openapi::PathItemType;
+
+    /// Stands in for the handlers, so requests only tell whether a method is routed.
+    async fn routed<B>(_: Request<B>, _: Next<B>) -> StatusCode {
+        StatusCode::OK
+    }
+
+    #[tokio::test]
+    async fn the_openapi_document_describes_every_route() {
+        let document = ApiDoc::openapi();
+        let mut paths: Vec<_> = api_routes::<()>()
+            .into_iter()
+            .map(|(path, _)| path.replace(":id", "{id}"))
+            .collect();
+        paths.sort();
+        let mut documented: Vec<_> = document.paths.paths.keys().cloned().collect();
+        documented.sort();
+        assert_eq!(paths, documented);
+
+        let methods = [
+            (Method::GET, PathItemType::Get),
+            (Method::POST, PathItemType::Post),
+            (Method::PUT, PathItemType::Put),
+            (Method::PATCH, PathItemType::Patch),
+            (Method::DELETE, PathItemType::Delete),
+        ];
+        for (path, route) in api_routes::<()>() {
+            let operations = &document.paths.paths[&path.replace(":id", "{id}")].operations;
+            let router = Router::new().route(path, route.route_layer(middleware::from_fn(routed)));
+            for (method, item_type) in &methods {
+                let request = Request::builder()
+                    .method(method.clone())
+                    .uri(path.replace(":id", "1"))
+                    .body(Body::empty())
+                    .unwrap();
+                let status = router.clone().oneshot(request).await.unwrap().status();
+                let expected = if operations.contains_key(item_type) {
+                    StatusCode::OK
+                } else {
+                    StatusCode::METHOD_NOT_ALLOWED
+                };
+                assert_eq!(status, expected, "{method} {path}");
+            }
+        }
+    }
+}
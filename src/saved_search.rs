@@ -0,0 +1,353 @@
+use crate::query::{Sort, Status};
+use cfg_if::cfg_if;
+use leptos::*;
+use leptos_router::ActionForm;
+use serde::{Deserialize, Serialize};
+
+/// A filter, sort order and status tab saved under a name. Each user has their own,
+/// and sees those saved before the server had users too.
+#[cfg_attr(feature = "ssr", derive(sqlx::FromRow))]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Hash)]
+pub struct SavedSearch {
+    pub(crate) id: u32,
+    pub(crate) name: String,
+    pub(crate) query: String,
+    pub(crate) sort: Sort,
+    pub(crate) status: Status,
+    pub(crate) position: u32,
+}
+
+/// Server error message of changes to a saved search that doesn't exist.
+pub const SAVED_SEARCH_NOT_FOUND: &str = "saved search not found";
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use crate::app::db;
+        use crate::auth::current_user;
+        use crate::query::Query;
+
+        /// Who saves and sees saved searches, `None` when the server is open.
+        async fn owner() -> Option<String> {
+            current_user().await.map(|user| user.0)
+        }
+
+        /// The saved searches `owner` sees, in order: their own, and everyone's.
+        pub(crate) async fn saved_searches_of(
+            owner: Option<&str>,
+        ) -> Result<Vec<SavedSearch>, ServerFnError> {
+            let pool = db().await?;
+
+            let saved = sqlx::query_as::<_, SavedSearch>(
+                "SELECT * FROM saved_searches WHERE owner IS NULL OR owner = ?
+                ORDER BY position, id",
+            )
+            .bind(owner)
+            .fetch_all(&pool)
+            .await?;
+
+            Ok(saved)
+        }
+
+        fn check_name(name: &str) -> Result<String, ServerFnError> {
+            match name.trim() {
+                "" => Err(ServerFnError::Args("saved search needs a name".into())),
+                name => Ok(name.to_string()),
+            }
+        }
+
+        /// `ids` with `id` moved to `position`, or to the end past it, `None` when it
+        /// isn't one of them.
+        fn reorder(mut ids: Vec<u32>, id: u32, position: u32) -> Option<Vec<u32>> {
+            let from = ids.iter().position(|saved_id| *saved_id == id)?;
+            ids.remove(from);
+            ids.insert((position as usize).min(ids.len()), id);
+            Some(ids)
+        }
+    }
+}
+
+#[server(GetSavedSearches, "/api")]
+pub async fn get_saved_searches() -> Result<Vec<SavedSearch>, ServerFnError> {
+    saved_searches_of(owner().await.as_deref()).await
+}
+
+/// Saves a filter after the existing ones.
+#[server(CreateSavedSearch, "/api")]
+pub async fn create_saved_search(
+    name: String,
+    query: String,
+    sort: Sort,
+    status: Status,
+) -> Result<SavedSearch, ServerFnError> {
+    let name = check_name(&name)?;
+    if let Err(e) = Query::parse(&query) {
+        return Err(ServerFnError::Args(format!("invalid filter: {e}")));
+    }
+
+    let owner = owner().await;
+    let pool = db().await?;
+
+    match sqlx::query_as::<_, SavedSearch>(
+        "INSERT INTO saved_searches (name, query, sort, status, owner, position)
+        VALUES (?1, ?2, ?3, ?4, ?5, (
+            SELECT COALESCE(MAX(position) + 1, 0) FROM saved_searches
+            WHERE owner IS NULL OR owner = ?5
+        ))
+        RETURNING *",
+    )
+    .bind(name)
+    .bind(query)
+    .bind(sort)
+    .bind(status)
+    .bind(owner)
+    .fetch_one(&pool)
+    .await
+    {
+        Ok(saved) => Ok(saved),
+        Err(e) => Err(ServerFnError::ServerError(e.to_string())),
+    }
+}
+
+#[server(RenameSavedSearch, "/api")]
+pub async fn rename_saved_search(id: u32, name: String) -> Result<SavedSearch, ServerFnError> {
+    let name = check_name(&name)?;
+
+    let owner = owner().await;
+    let pool = db().await?;
+
+    match sqlx::query_as::<_, SavedSearch>(
+        "UPDATE saved_searches SET name = ? WHERE id = ? AND (owner IS NULL OR owner = ?)
+        RETURNING *",
+    )
+    .bind(name)
+    .bind(id)
+    .bind(owner)
+    .fetch_optional(&pool)
+    .await
+    {
+        Ok(Some(saved)) => Ok(saved),
+        Ok(None) => Err(ServerFnError::ServerError(SAVED_SEARCH_NOT_FOUND.into())),
+        Err(e) => Err(ServerFnError::ServerError(e.to_string())),
+    }
+}
+
+/// Moves a saved search to `position` in the list the user sees, shifting the others,
+/// and returns the reordered list.
+#[server(MoveSavedSearch, "/api")]
+pub async fn move_saved_search(id: u32, position: u32) -> Result<Vec<SavedSearch>, ServerFnError> {
+    let owner = owner().await;
+    let pool = db().await?;
+    let mut tx = pool.begin().await?;
+
+    let ids: Vec<u32> = sqlx::query_scalar(
+        "SELECT id FROM saved_searches WHERE owner IS NULL OR owner = ? ORDER BY position, id",
+    )
+    .bind(&owner)
+    .fetch_all(&mut *tx)
+    .await?;
+    let Some(ids) = reorder(ids, id, position) else {
+        return Err(ServerFnError::ServerError(SAVED_SEARCH_NOT_FOUND.into()));
+    };
+
+    for (position, id) in ids.iter().enumerate() {
+        sqlx::query("UPDATE saved_searches SET position = ? WHERE id = ?")
+            .bind(position as u32)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    let saved = sqlx::query_as::<_, SavedSearch>(
+        "SELECT * FROM saved_searches WHERE owner IS NULL OR owner = ? ORDER BY position, id",
+    )
+    .bind(&owner)
+    .fetch_all(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(saved)
+}
+
+#[server(DeleteSavedSearch, "/api")]
+pub async fn delete_saved_search(id: u32) -> Result<u32, ServerFnError> {
+    let owner = owner().await;
+    let pool = db().await?;
+
+    match sqlx::query("DELETE FROM saved_searches WHERE id = ? AND (owner IS NULL OR owner = ?)")
+        .bind(id)
+        .bind(owner)
+        .execute(&pool)
+        .await
+    {
+        Ok(_) => Ok(id),
+        Err(e) => Err(ServerFnError::ServerError(e.to_string())),
+    }
+}
+
+/// Navbar dropdown of the saved searches, opening one restores its filter, sort and
+/// status tab.
+#[component]
+pub fn SavedSearches(
+    filter: RwSignal<String>,
+    sort: RwSignal<Sort>,
+    status: RwSignal<Status>,
+) -> impl IntoView {
+    let create = create_server_action::<CreateSavedSearch>();
+    let rename = create_server_action::<RenameSavedSearch>();
+    let move_to = create_server_action::<MoveSavedSearch>();
+    let delete = create_server_action::<DeleteSavedSearch>();
+
+    let saved = create_resource(
+        move || {
+            (
+                create.version().get(),
+                rename.version().get(),
+                move_to.version().get(),
+                delete.version().get(),
+            )
+        },
+        |_| get_saved_searches(),
+    );
+    let saved_list = move || {
+        saved
+            .get()
+            .and_then(Result::ok)
+            .unwrap_or_default()
+            .into_iter()
+            .enumerate()
+            .collect::<Vec<_>>()
+    };
+
+    let on_rename = move |saved: &SavedSearch| {
+        let name = window()
+            .prompt_with_message_and_default("Rename saved search", &saved.name)
+            .ok()
+            .flatten();
+        if let Some(name) = name {
+            rename.dispatch(RenameSavedSearch { id: saved.id, name });
+        }
+    };
+
+    // the first error of the last changes, if any
+    let error = move || {
+        [
+            create.value().get().and_then(Result::err),
+            rename.value().get().and_then(Result::err),
+            move_to.value().get().and_then(Result::err),
+            delete.value().get().and_then(Result::err),
+            saved.get().and_then(Result::err),
+        ]
+        .into_iter()
+        .flatten()
+        .next()
+    };
+
+    view! {
+        <li class="nav-item dropdown">
+            <a class="nav-link dropdown-toggle" href="#" role="button"
+                data-bs-toggle="dropdown" data-bs-auto-close="outside" aria-expanded="false">
+                <i class="bi bi-bookmark me-1"></i> Saved
+            </a>
+
+            <ul class="dropdown-menu">
+                <Transition fallback=move || view! {
+                    <li class="dropdown-item-text text-muted">"Loading..."</li>
+                }>
+                    <For
+                        each=saved_list
+                        key=|(position, saved)| (*position, saved.clone())
+                        children=move |(position, saved)| {
+                            let active = {
+                                let saved = saved.clone();
+                                move || {
+                                    filter.get() == saved.query
+                                        && sort.get() == saved.sort
+                                        && status.get() == saved.status
+                                }
+                            };
+                            let open = {
+                                let saved = saved.clone();
+                                move |_| {
+                                    filter.set(saved.query.clone());
+                                    sort.set(saved.sort);
+                                    status.set(saved.status);
+                                }
+                            };
+                            let renamed = saved.clone();
+                            let id = saved.id;
+                            view! {
+                                <li class="d-flex align-items-center pe-2">
+                                    <button type="button" class="dropdown-item"
+                                        class:active=active on:click=open
+                                        title=saved.query.clone()>
+                                        {saved.name.clone()}
+                                    </button>
+                                    <button type="button" title="Rename"
+                                        class="btn btn-sm border-0 bi bi-pencil"
+                                        on:click=move |_| on_rename(&renamed)/>
+                                    <button type="button" title="Move up"
+                                        class="btn btn-sm border-0 bi bi-arrow-up"
+                                        disabled=position == 0
+                                        on:click=move |_| move_to.dispatch(MoveSavedSearch {
+                                            id,
+                                            position: position.saturating_sub(1) as u32,
+                                        })/>
+                                    <button type="button" title="Move down"
+                                        class="btn btn-sm border-0 bi bi-arrow-down"
+                                        on:click=move |_| move_to.dispatch(MoveSavedSearch {
+                                            id,
+                                            position: position as u32 + 1,
+                                        })/>
+                                    <button type="button" title="Delete"
+                                        class="btn btn-sm border-0 btn-outline-danger bi bi-x-lg"
+                                        on:click=move |_| delete.dispatch(DeleteSavedSearch { id })/>
+                                </li>
+                            }
+                        }
+                    />
+                </Transition>
+
+                <li><hr class="dropdown-divider"/></li>
+                <li>
+                    <ActionForm action=create class="d-flex px-3 py-1">
+                        <input type="hidden" name="query" value=move || filter.get()/>
+                        <input type="hidden" name="sort" value=move || sort.get().as_str()/>
+                        <input type="hidden" name="status" value=move || status.get().as_str()/>
+                        <input type="text" name="name" placeholder="Save current filter as"
+                            class="form-control form-control-sm me-2" required/>
+                        <button type="submit" title="Save"
+                            class="btn btn-sm btn-outline-warning bi bi-bookmark-plus"/>
+                    </ActionForm>
+                </li>
+
+                {move || error().map(|e| view! {
+                    <li class="dropdown-item-text text-danger small">{e.to_string()}</li>
+                })}
+            </ul>
+        </li>
+    }
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_are_trimmed_and_required() {
+        assert_eq!(check_name("  work  ").unwrap(), "work");
+        assert_eq!(
+            check_name(" \t").unwrap_err(),
+            ServerFnError::Args("saved search needs a name".into())
+        );
+    }
+
+    #[test]
+    fn moving_shifts_the_searches_between() {
+        assert_eq!(reorder(vec![1, 2, 3, 4], 1, 2), Some(vec![2, 3, 1, 4]));
+        assert_eq!(reorder(vec![1, 2, 3, 4], 4, 0), Some(vec![4, 1, 2, 3]));
+        assert_eq!(reorder(vec![1, 2, 3, 4], 3, 2), Some(vec![1, 2, 3, 4]));
+        // past the end is the end
+        assert_eq!(reorder(vec![1, 2, 3, 4], 2, 10), Some(vec![1, 3, 4, 2]));
+        assert_eq!(reorder(vec![1, 2], 5, 0), None);
+    }
+}
@@ -0,0 +1,257 @@
+use crate::offline;
+use cfg_if::cfg_if;
+use leptos::*;
+use serde::{Deserialize, Serialize};
+
+const KEYMAP_KEY: &str = "shortcuts";
+
+/// What a keyboard shortcut does.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Command {
+    AddTodo,
+    Filter,
+    Next,
+    Previous,
+    Toggle,
+    Edit,
+    Delete,
+    Help,
+}
+
+impl Command {
+    pub const ALL: [Command; 8] = [
+        Command::AddTodo,
+        Command::Filter,
+        Command::Next,
+        Command::Previous,
+        Command::Toggle,
+        Command::Edit,
+        Command::Delete,
+        Command::Help,
+    ];
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Command::AddTodo => "New todo",
+            Command::Filter => "Filter",
+            Command::Next => "Select next todo",
+            Command::Previous => "Select previous todo",
+            Command::Toggle => "Mark selected done or undone",
+            Command::Edit => "Edit selected",
+            Command::Delete => "Delete selected, with undo",
+            Command::Help => "Show shortcuts",
+        }
+    }
+
+    fn default_key(self) -> &'static str {
+        match self {
+            Command::AddTodo => "n",
+            Command::Filter => "/",
+            Command::Next => "j",
+            Command::Previous => "k",
+            Command::Toggle => "x",
+            Command::Edit => "e",
+            Command::Delete => "Delete",
+            Command::Help => "?",
+        }
+    }
+}
+
+/// The key bound to each command, as `KeyboardEvent.key` names it, kept in local
+/// storage. An empty key leaves a command unbound.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Keymap(Vec<(Command, String)>);
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap(
+            Command::ALL
+                .into_iter()
+                .map(|command| (command, command.default_key().to_string()))
+                .collect(),
+        )
+    }
+}
+
+impl Keymap {
+    pub fn load() -> Keymap {
+        let mut keymap = Keymap::default();
+        // commands added since it was saved keep their default key
+        if let Some(Keymap(saved)) = offline::load::<Keymap>(KEYMAP_KEY) {
+            for (command, key) in saved {
+                keymap.bind(command, key);
+            }
+        }
+        keymap
+    }
+
+    pub fn save(&self) {
+        offline::save(KEYMAP_KEY, self);
+    }
+
+    pub fn key(&self, command: Command) -> &str {
+        self.0
+            .iter()
+            .find(|(bound, _)| *bound == command)
+            .map_or("", |(_, key)| key)
+    }
+
+    pub fn command(&self, key: &str) -> Option<Command> {
+        self.0
+            .iter()
+            .find(|(_, bound)| bound == key)
+            .map(|(command, _)| *command)
+    }
+
+    /// Binds `key` to `command`, unbinding it from any other command.
+    pub fn bind(&mut self, command: Command, key: String) {
+        for (bound, bound_key) in self.0.iter_mut() {
+            if *bound == command {
+                bound_key.clone_from(&key);
+            } else if !key.is_empty() && *bound_key == key {
+                bound_key.clear();
+            }
+        }
+    }
+}
+
+/// Calls `handler` with the command of each key pressed outside of text fields, for
+/// commands it handles by returning `true`.
+pub fn on_shortcut(keymap: RwSignal<Keymap>, handler: impl Fn(Command) -> bool + 'static) {
+    let handle = window_event_listener(ev::keydown, move |ev| {
+        if ev.ctrl_key() || ev.meta_key() || ev.alt_key() || is_typing(&ev) {
+            return;
+        }
+
+        let command = keymap.with_untracked(|keymap| keymap.command(&ev.key()));
+        if command.is_some_and(&handler) {
+            ev.prevent_default();
+        }
+    });
+    on_cleanup(move || handle.remove());
+}
+
+cfg_if! {
+    if #[cfg(feature = "hydrate")] {
+        use wasm_bindgen::JsCast;
+
+        fn is_typing(ev: &ev::KeyboardEvent) -> bool {
+            let Some(target) = ev
+                .target()
+                .and_then(|target| target.dyn_into::<web_sys::HtmlElement>().ok())
+            else {
+                return false;
+            };
+
+            matches!(target.tag_name().as_str(), "INPUT" | "TEXTAREA" | "SELECT")
+                || target.is_content_editable()
+        }
+
+        /// Focuses the element with `id`, such as an input to type in.
+        pub fn focus(id: &str) {
+            if let Some(element) = document()
+                .get_element_by_id(id)
+                .and_then(|element| element.dyn_into::<web_sys::HtmlElement>().ok())
+            {
+                _ = element.focus();
+            }
+        }
+    } else {
+        fn is_typing(_ev: &ev::KeyboardEvent) -> bool {
+            false
+        }
+
+        pub fn focus(_id: &str) {}
+    }
+}
+
+/// Handles the commands that aren't about the selected todo, and shows the cheat
+/// sheet, where keys can be rebound.
+#[component]
+pub fn Shortcuts(keymap: RwSignal<Keymap>) -> impl IntoView {
+    let help = create_rw_signal(false);
+    on_shortcut(keymap, move |command| match command {
+        Command::AddTodo => {
+            focus("floatingTodo");
+            true
+        }
+        Command::Filter => {
+            focus("filter");
+            true
+        }
+        Command::Help => {
+            help.update(|help| *help = !*help);
+            true
+        }
+        _ => false,
+    });
+
+    let close_handle = window_event_listener(ev::keydown, move |ev| {
+        if ev.key() == "Escape" && help.get_untracked() {
+            help.set(false);
+        }
+    });
+    on_cleanup(move || close_handle.remove());
+
+    let rebind = move |command: Command, ev: ev::KeyboardEvent| {
+        match ev.key().as_str() {
+            // still move focus and close the cheat sheet
+            "Tab" | "Escape" => return,
+            "Backspace" => keymap.update(|keymap| keymap.bind(command, String::new())),
+            key => keymap.update(|keymap| keymap.bind(command, key.to_string())),
+        }
+        ev.prevent_default();
+        keymap.with_untracked(Keymap::save);
+    };
+
+    view! {
+        <Show when=move || help.get()>
+            <div class="modal d-block" tabindex="-1" role="dialog" aria-modal="true"
+                on:click=move |ev| {
+                    // clicks on the backdrop close it
+                    if ev.target() == ev.current_target() {
+                        help.set(false);
+                    }
+                }>
+                <div class="modal-dialog">
+                    <div class="modal-content">
+                        <div class="modal-header">
+                            <h5 class="modal-title">Keyboard Shortcuts</h5>
+                            <button type="button" class="btn-close" aria-label="Close"
+                                on:click=move |_| help.set(false)></button>
+                        </div>
+                        <div class="modal-body">
+                            <table class="table table-sm align-middle text-start mb-0">
+                                <tbody>
+                                    {Command::ALL.map(|command| view! {
+                                        <tr>
+                                            <td>{command.description()}</td>
+                                            <td class="w-25">
+                                                <input type="text" readonly
+                                                    class="form-control form-control-sm text-center"
+                                                    title="Press a key to bind, Backspace to unbind"
+                                                    prop:value=move || keymap.with(|keymap| {
+                                                        keymap.key(command).to_string()
+                                                    })
+                                                    placeholder="unbound"
+                                                    on:keydown=move |ev| rebind(command, ev)/>
+                                            </td>
+                                        </tr>
+                                    }).collect_view()}
+                                </tbody>
+                            </table>
+                        </div>
+                        <div class="modal-footer">
+                            <button type="button" class="btn btn-outline-secondary"
+                                on:click=move |_| {
+                                    keymap.set(Keymap::default());
+                                    keymap.with_untracked(Keymap::save);
+                                }>Reset to defaults</button>
+                        </div>
+                    </div>
+                </div>
+            </div>
+            <div class="modal-backdrop show"></div>
+        </Show>
+    }
+}
@@ -0,0 +1,376 @@
+//! The [todo.txt](https://github.com/todotxt/todo.txt) format, a todo per line.
+//!
+//! A leading `(A)`, `(B)` or `(C)` is the todo's priority, high, medium or low, kept
+//! as a `pri:` tag on done todos. `+project` tags are its tags, the first `@context`
+//! its list, with spaces as `_`, and `due:` its due date. Completion and creation
+//! dates are kept too. What the todo has no field for, like other priorities,
+//! contexts and `key:value` tags, stays in the task.
+
+use crate::app::{parse_tags, Priority, TodoItem};
+use crate::dates::{self, is_date};
+
+/// A todo in a todo.txt file, with the line it's on for error messages. Its due date
+/// is as written, for the importer to check.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Line {
+    pub line: usize,
+    pub todo: TodoItem,
+}
+
+/// Reads every todo, skipping blank lines.
+pub fn parse(input: &str) -> Vec<Line> {
+    let input = input.strip_prefix('\u{feff}').unwrap_or(input);
+    input
+        .lines()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            Some(Line {
+                line: i + 1,
+                todo: parse_line(line)?,
+            })
+        })
+        .collect()
+}
+
+/// The todo of a line, `None` for a blank line.
+pub fn parse_line(line: &str) -> Option<TodoItem> {
+    if line.trim().is_empty() {
+        return None;
+    }
+
+    let mut todo = TodoItem::default();
+    let mut rest = line;
+    // only a lowercase `x` and a space mark a todo done, followed by when it was
+    // done and then when it was created
+    if let Some(after) = line.strip_prefix("x ") {
+        todo.done = true;
+        rest = after;
+        if let Some((completed, after)) = split_date(rest) {
+            todo.completed = Some(completed.into());
+            rest = after;
+            if let Some((created, after)) = split_date(rest) {
+                todo.created = Some(created.into());
+                rest = after;
+            }
+        }
+    }
+    // the priority comes first, or after the dates of done todos
+    if let Some((priority, after)) = split_priority(rest) {
+        if let Some(priority) = priority_of(priority) {
+            todo.priority = Some(priority);
+            rest = after;
+        }
+    }
+    if !todo.done {
+        if let Some((created, after)) = split_date(rest) {
+            todo.created = Some(created.into());
+            rest = after;
+        }
+    }
+
+    let mut words = vec![];
+    let mut tags = vec![];
+    for word in rest.split_whitespace() {
+        let tag = |prefix: &str| word.strip_prefix(prefix).filter(|tag| !tag.is_empty());
+        if let Some(tag) = tag("+") {
+            tags.push(tag);
+        } else if let Some(list) = tag("@").filter(|_| todo.list.is_none()) {
+            todo.list = Some(list.replace('_', " "));
+        } else if let Some(due) = tag("due:").filter(|_| todo.due.is_none()) {
+            todo.due = Some(due.to_string());
+        } else if let Some(priority) = priority_tag(word).filter(|_| todo.done) {
+            todo.priority = todo.priority.or(Some(priority));
+        } else {
+            words.push(word);
+        }
+    }
+    todo.task = words.join(" ");
+    todo.tags = parse_tags(&tags.join(" "));
+    Some(todo)
+}
+
+/// A todo as a line, without the line break.
+pub fn format_line(todo: &TodoItem) -> String {
+    let task = todo.task.replace(['\r', '\n'], " ");
+    let task = task.trim();
+    // a task starting with what reads as a date, or as a priority or a done `x` when
+    // not done, is kept by writing dates before it, today's when the todo has none.
+    // A done todo's task starting with `(A)` to `(C)` reads as its priority, as the
+    // format has no way around it.
+    let ambiguous = split_date(task).is_some()
+        || !todo.done
+            && (task.starts_with("x ")
+                || split_priority(task)
+                    .is_some_and(|(priority, _)| priority_of(priority).is_some()));
+    let date = |date: &Option<String>, needed: bool| match date {
+        Some(date) => Some(date.clone()),
+        None => needed.then(dates::today),
+    };
+
+    let mut words = vec![];
+    if todo.done {
+        let created = date(&todo.created, ambiguous);
+        let completed = date(&todo.completed, ambiguous || created.is_some());
+        words.push("x".to_string());
+        words.extend(completed);
+        words.extend(created);
+    } else {
+        words.extend(
+            todo.priority
+                .map(|priority| format!("({})", letter(priority))),
+        );
+        words.extend(date(&todo.created, ambiguous));
+    }
+    words.push(task.to_string());
+    words.extend(todo.tags.iter().map(|tag| format!("+{tag}")));
+    if let Some(list) = &todo.list {
+        words.push(format!(
+            "@{}",
+            list.split_whitespace().collect::<Vec<_>>().join("_")
+        ));
+    }
+    if let Some(due) = &todo.due {
+        words.push(format!("due:{due}"));
+    }
+    if todo.done {
+        words.extend(
+            todo.priority
+                .map(|priority| format!("pri:{}", letter(priority))),
+        );
+    }
+    words.retain(|word| !word.is_empty());
+    words.join(" ")
+}
+
+fn priority_of(letter: char) -> Option<Priority> {
+    match letter {
+        'A' => Some(Priority::High),
+        'B' => Some(Priority::Medium),
+        'C' => Some(Priority::Low),
+        _ => None,
+    }
+}
+
+fn letter(priority: Priority) -> char {
+    match priority {
+        Priority::High => 'A',
+        Priority::Medium => 'B',
+        Priority::Low => 'C',
+    }
+}
+
+/// A leading `(A)` to `(Z)` followed by a space, and what's after it.
+fn split_priority(text: &str) -> Option<(char, &str)> {
+    let bytes = text.as_bytes();
+    match bytes {
+        [b'(', priority @ b'A'..=b'Z', b')', b' ', ..] => Some((*priority as char, &text[4..])),
+        _ => None,
+    }
+}
+
+/// The priority of a `pri:A` to `pri:C` tag.
+fn priority_tag(word: &str) -> Option<Priority> {
+    match word.as_bytes() {
+        [b'p', b'r', b'i', b':', priority] => priority_of(*priority as char),
+        _ => None,
+    }
+}
+
+/// A leading `YYYY-MM-DD` date followed by a space, and what's after it.
+fn split_date(text: &str) -> Option<(&str, &str)> {
+    text.split_once(' ').filter(|(date, _)| is_date(date))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parsed(line: &str) -> TodoItem {
+        parse_line(line).unwrap()
+    }
+
+    fn todo(task: &str) -> TodoItem {
+        TodoItem {
+            task: task.into(),
+            ..TodoItem::default()
+        }
+    }
+
+    #[test]
+    fn reads_every_field() {
+        assert_eq!(parse_line("   "), None);
+        assert_eq!(
+            parsed("(A) 2024-01-01 call mom +Family @phone due:2024-03-01 +calls"),
+            TodoItem {
+                priority: Some(Priority::High),
+                created: Some("2024-01-01".into()),
+                tags: vec!["family".into(), "calls".into()],
+                list: Some("phone".into()),
+                due: Some("2024-03-01".into()),
+                ..todo("call mom")
+            }
+        );
+        assert_eq!(
+            parsed("x 2024-03-02 2024-03-01 pay rent pri:B"),
+            TodoItem {
+                done: true,
+                completed: Some("2024-03-02".into()),
+                created: Some("2024-03-01".into()),
+                priority: Some(Priority::Medium),
+                ..todo("pay rent")
+            }
+        );
+        // a single date of a done todo is when it was done
+        assert_eq!(
+            parsed("x 2024-03-02 water plants"),
+            TodoItem {
+                done: true,
+                completed: Some("2024-03-02".into()),
+                ..todo("water plants")
+            }
+        );
+    }
+
+    #[test]
+    fn reads_priorities_after_the_dates_of_done_todos() {
+        let expected = TodoItem {
+            done: true,
+            completed: Some("2024-01-02".into()),
+            created: Some("2024-01-01".into()),
+            priority: Some(Priority::High),
+            ..todo("ship it")
+        };
+        assert_eq!(parsed("x 2024-01-02 2024-01-01 (A) ship it"), expected);
+        assert_eq!(
+            parsed("x (C) file taxes"),
+            TodoItem {
+                done: true,
+                priority: Some(Priority::Low),
+                ..todo("file taxes")
+            }
+        );
+        assert_eq!(
+            format_line(&expected),
+            "x 2024-01-02 2024-01-01 ship it pri:A"
+        );
+    }
+
+    #[test]
+    fn keeps_what_has_no_field_in_the_task() {
+        assert_eq!(parsed("(D) later"), todo("(D) later"));
+        assert_eq!(
+            parsed("x renew passport pri:D"),
+            TodoItem {
+                done: true,
+                ..todo("renew passport pri:D")
+            }
+        );
+        let line = parsed("call @home @phone due:2024-03-01 due:soon id:7 + @");
+        assert_eq!(line.task, "call @phone due:soon id:7 + @");
+        assert_eq!(line.list.as_deref(), Some("home"));
+        // the importer checks it's a date
+        assert_eq!(parsed("due:soon").due.as_deref(), Some("soon"));
+    }
+
+    #[test]
+    fn leaves_what_only_looks_like_markers() {
+        for line in [
+            "X uppercase isn't done",
+            "xylophone lessons",
+            "(a) lowercase isn't a priority",
+            "(E)needs a space",
+            "2024-13-45 isn't a date",
+        ] {
+            assert_eq!(parsed(line), todo(line));
+        }
+    }
+
+    #[test]
+    fn writes_every_field() {
+        let call = TodoItem {
+            priority: Some(Priority::High),
+            created: Some("2024-01-01".into()),
+            tags: vec!["family".into()],
+            list: Some("Phone Calls".into()),
+            due: Some("2024-03-01".into()),
+            ..todo("call mom")
+        };
+        assert_eq!(
+            format_line(&call),
+            "(A) 2024-01-01 call mom +family @Phone_Calls due:2024-03-01"
+        );
+        let done = TodoItem {
+            done: true,
+            completed: Some("2024-03-02".into()),
+            ..call
+        };
+        assert_eq!(
+            format_line(&done),
+            "x 2024-03-02 2024-01-01 call mom +family @Phone_Calls due:2024-03-01 pri:A"
+        );
+        assert_eq!(format_line(&todo("two\nlines")), "two lines");
+    }
+
+    #[test]
+    fn round_trips_every_field() {
+        let base = TodoItem {
+            done: true,
+            ..todo("plan trip")
+        };
+        for item in [
+            todo("buy milk"),
+            TodoItem {
+                priority: Some(Priority::Medium),
+                ..todo("book flights")
+            },
+            TodoItem {
+                priority: Some(Priority::Low),
+                ..base.clone()
+            },
+            TodoItem {
+                due: Some("2024-04-01".into()),
+                ..base.clone()
+            },
+            TodoItem {
+                tags: vec!["travel".into(), "summer".into()],
+                ..base.clone()
+            },
+            TodoItem {
+                list: Some("Phone Calls".into()),
+                ..base.clone()
+            },
+            TodoItem {
+                created: Some("2024-03-01".into()),
+                ..todo("dated")
+            },
+            TodoItem {
+                completed: Some("2024-03-02".into()),
+                ..base.clone()
+            },
+            TodoItem {
+                completed: Some("2024-03-02".into()),
+                created: Some("2024-03-01".into()),
+                ..base.clone()
+            },
+        ] {
+            let line = format_line(&item);
+            assert_eq!(parse_line(&line), Some(item), "{line}");
+        }
+    }
+
+    #[test]
+    fn round_trips_tasks_that_read_as_markers() {
+        for (task, done) in [
+            ("x marks the spot", false),
+            ("x marks the spot", true),
+            ("2024-03-01 is a date", false),
+            ("2024-03-01 is a date", true),
+            ("(B) reads as a priority", false),
+        ] {
+            let line = format_line(&TodoItem { done, ..todo(task) });
+            let read = parse_line(&line).unwrap();
+            assert_eq!((read.task.as_str(), read.done), (task, done), "{line}");
+            assert_eq!(read.priority, None, "{line}");
+        }
+    }
+}